@@ -46,31 +46,37 @@ pub(crate) struct KeyBox {
     pub(crate) index: NodeIndex,
 }
 
-#[async_trait]
-impl aleph_bft::KeyBox for KeyBox {
+impl aleph_bft::Verifier for KeyBox {
     type Signature = Signature;
 
     fn node_count(&self) -> NodeCount {
         self.count.into()
     }
 
+    fn verify(&self, _msg: &[u8], _sgn: &Self::Signature, _index: NodeIndex) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl aleph_bft::KeyBox for KeyBox {
     async fn sign(&self, _msg: &[u8]) -> Self::Signature {
         Signature {}
     }
-    fn verify(&self, _msg: &[u8], _sgn: &Self::Signature, _index: NodeIndex) -> bool {
-        true
+}
+
+impl aleph_bft::MultiVerifier for KeyBox {
+    type PartialMultisignature = PartialMultisignature;
+    fn is_complete(&self, _: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        (self.count * 2) / 3 < partial.signed_by.len()
     }
 }
 
 impl aleph_bft::MultiKeychain for KeyBox {
-    type PartialMultisignature = PartialMultisignature;
     fn from_signature(&self, _: &Self::Signature, index: NodeIndex) -> Self::PartialMultisignature {
         let signed_by = vec![index];
         PartialMultisignature { signed_by }
     }
-    fn is_complete(&self, _: &[u8], partial: &Self::PartialMultisignature) -> bool {
-        (self.count * 2) / 3 < partial.signed_by.len()
-    }
 }
 
 impl aleph_bft::Index for KeyBox {
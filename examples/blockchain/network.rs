@@ -1,5 +1,5 @@
 use crate::chain::Block;
-use aleph_bft::{NodeIndex, Recipient, TaskHandle};
+use aleph_bft::{NodeIndex, Recipient, SendError, TaskHandle};
 use codec::{Decode, Encode};
 use futures::{
     channel::{
@@ -165,6 +165,15 @@ impl Behaviour {
                     debug!(target: "Blockchain-network", "No peer_id known for node {:?}.", node_ix);
                 }
             }
+            Nodes(node_ixs) => {
+                for node_ix in node_ixs {
+                    if let Some(peer_id) = self.peer_by_index.get(&node_ix) {
+                        self.rq_rp.send_request(peer_id, message.clone());
+                    } else {
+                        debug!(target: "Blockchain-network", "No peer_id known for node {:?}.", node_ix);
+                    }
+                }
+            }
             Everyone => {
                 for peer_id in self.peers.iter() {
                     self.rq_rp.send_request(peer_id, message.clone());
@@ -246,10 +255,13 @@ pub(crate) struct Network {
 
 #[async_trait::async_trait]
 impl aleph_bft::Network<Hasher256, Data, Signature, PartialMultisignature> for Network {
-    fn send(&self, data: NetworkData, recipient: Recipient) {
-        if let Err(e) = self.msg_to_manager_tx.unbounded_send((data, recipient)) {
-            warn!(target: "Blockchain-network", "Failed network send: {:?}", e);
-        }
+    fn send(&self, data: NetworkData, recipient: Recipient) -> Result<(), SendError> {
+        self.msg_to_manager_tx
+            .unbounded_send((data, recipient))
+            .map_err(|e| {
+                warn!(target: "Blockchain-network", "Failed network send: {:?}", e);
+                SendError::Closed
+            })
     }
     async fn next_event(&mut self) -> Option<NetworkData> {
         self.msg_from_manager_rx.next().await
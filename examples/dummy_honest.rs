@@ -1,4 +1,7 @@
-use aleph_bft::{run_session, NodeCount, NodeIndex, OrderedBatch, Recipient, TaskHandle};
+use aleph_bft::{
+    run_session, DecodingLimits, NodeCount, NodeIndex, OrderedBatch, Recipient, SendError,
+    TaskHandle,
+};
 use async_trait::async_trait;
 use codec::{Decode, Encode};
 use futures::{
@@ -57,8 +60,11 @@ async fn main() {
     let n_members = parse_arg(2);
     let n_finalized = parse_arg(3);
 
+    let config = aleph_bft::default_config(n_members.into(), my_id.into(), 0);
+    let limits = DecodingLimits::from_config(&config, std::mem::size_of::<Data>());
+
     info!(target: "dummy-honest", "Getting network up.");
-    let (network, mut manager) = Network::new().await.unwrap();
+    let (network, mut manager) = Network::new(limits).await.unwrap();
     let (close_network, exit) = oneshot::channel();
     tokio::spawn(async move { manager.run(exit).await });
 
@@ -70,7 +76,6 @@ async fn main() {
             count: n_members,
             index: my_id.into(),
         };
-        let config = aleph_bft::default_config(n_members.into(), my_id.into(), 0);
         run_session(config, network, data_io, keybox, Spawner {}, exit).await
     });
 
@@ -167,31 +172,37 @@ struct KeyBox {
     index: NodeIndex,
 }
 
-#[async_trait]
-impl aleph_bft::KeyBox for KeyBox {
+impl aleph_bft::Verifier for KeyBox {
     type Signature = Signature;
 
     fn node_count(&self) -> NodeCount {
         self.count.into()
     }
 
+    fn verify(&self, _msg: &[u8], _sgn: &Self::Signature, _index: NodeIndex) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl aleph_bft::KeyBox for KeyBox {
     async fn sign(&self, _msg: &[u8]) -> Self::Signature {
         Signature {}
     }
-    fn verify(&self, _msg: &[u8], _sgn: &Self::Signature, _index: NodeIndex) -> bool {
-        true
+}
+
+impl aleph_bft::MultiVerifier for KeyBox {
+    type PartialMultisignature = PartialMultisignature;
+    fn is_complete(&self, _: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        (self.count * 2) / 3 < partial.signed_by.len()
     }
 }
 
 impl aleph_bft::MultiKeychain for KeyBox {
-    type PartialMultisignature = PartialMultisignature;
     fn from_signature(&self, _: &Self::Signature, index: NodeIndex) -> Self::PartialMultisignature {
         let signed_by = vec![index];
         PartialMultisignature { signed_by }
     }
-    fn is_complete(&self, _: &[u8], partial: &Self::PartialMultisignature) -> bool {
-        (self.count * 2) / 3 < partial.signed_by.len()
-    }
 }
 
 impl aleph_bft::Index for KeyBox {
@@ -257,19 +268,25 @@ impl NetworkBehaviourEventProcess<FloodsubEvent> for Behaviour {
 struct Network {
     outgoing_tx: mpsc::UnboundedSender<Vec<u8>>,
     msg_rx: mpsc::UnboundedReceiver<Vec<u8>>,
+    limits: DecodingLimits,
 }
 
 #[async_trait::async_trait]
 impl aleph_bft::Network<Hasher64, Data, Signature, PartialMultisignature> for Network {
-    fn send(&self, data: NetworkData, _recipient: Recipient) {
-        if let Err(e) = self.outgoing_tx.unbounded_send(data.encode()) {
-            warn!(target: "dummy-honest", "Failed network send: {:?}", e)
-        }
+    fn send(&self, data: NetworkData, _recipient: Recipient) -> Result<(), SendError> {
+        self.outgoing_tx.unbounded_send(data.encode()).map_err(|e| {
+            warn!(target: "dummy-honest", "Failed network send: {:?}", e);
+            SendError::Closed
+        })
     }
     async fn next_event(&mut self) -> Option<NetworkData> {
-        self.msg_rx.next().await.map(|msg| {
-            NetworkData::decode(&mut &msg[..]).expect("honest network data should decode")
-        })
+        loop {
+            let msg = self.msg_rx.next().await?;
+            match NetworkData::decode_bounded(&mut &msg[..], &self.limits) {
+                Ok(data) => return Some(data),
+                Err(e) => warn!(target: "dummy-honest", "Dropping malformed network data: {:?}", e),
+            }
+        }
     }
 }
 
@@ -279,7 +296,7 @@ struct NetworkManager {
 }
 
 impl Network {
-    async fn new() -> Result<(Self, NetworkManager), Box<dyn Error>> {
+    async fn new(limits: DecodingLimits) -> Result<(Self, NetworkManager), Box<dyn Error>> {
         let local_key = identity::Keypair::generate_ed25519();
         let local_peer_id = PeerId::from(local_key.public());
         info!(target: "dummy-honest", "Local peer id: {:?}", local_peer_id);
@@ -315,6 +332,7 @@ impl Network {
         let network = Network {
             outgoing_tx,
             msg_rx,
+            limits,
         };
         let network_manager = NetworkManager { swarm, outgoing_rx };
 
@@ -1,6 +1,7 @@
 use aleph_bft::{
-    DataIO as DataIOT, Index, KeyBox as KeyBoxT, MultiKeychain as MultiKeychainT, NetworkData,
-    OrderedBatch, PartialMultisignature as PartialMultisignatureT, Recipient,
+    DataIO as DataIOT, Index, KeyBox as KeyBoxT, MultiKeychain as MultiKeychainT, MultiVerifier,
+    NetworkData, OrderedBatch, PartialMultisignature as PartialMultisignatureT, Recipient,
+    Verifier,
 };
 use futures::task::Poll;
 use parking_lot::Mutex;
@@ -114,32 +115,37 @@ impl Index for KeyBox {
     }
 }
 
-#[async_trait]
-impl KeyBoxT for KeyBox {
+impl Verifier for KeyBox {
     type Signature = Signature;
 
     fn node_count(&self) -> NodeCount {
         self.count
     }
 
+    fn verify(&self, _msg: &[u8], _sgn: &Signature, _index: NodeIndex) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl KeyBoxT for KeyBox {
     async fn sign(&self, _msg: &[u8]) -> Signature {
         Signature {}
     }
+}
 
-    fn verify(&self, _msg: &[u8], _sgn: &Signature, _index: NodeIndex) -> bool {
-        true
+impl MultiVerifier for KeyBox {
+    type PartialMultisignature = PartialMultisignature;
+    fn is_complete(&self, _: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        (self.count * 2) / 3 < NodeCount(partial.signed_by.len())
     }
 }
 
 impl MultiKeychainT for KeyBox {
-    type PartialMultisignature = PartialMultisignature;
     fn from_signature(&self, _: &Self::Signature, index: NodeIndex) -> Self::PartialMultisignature {
         let signed_by = vec![index];
         PartialMultisignature { signed_by }
     }
-    fn is_complete(&self, _: &[u8], partial: &Self::PartialMultisignature) -> bool {
-        (self.count * 2) / 3 < NodeCount(partial.signed_by.len())
-    }
 }
 
 pub type FuzzNetworkData =
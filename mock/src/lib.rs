@@ -22,9 +22,10 @@ use std::{
 };
 
 use aleph_bft::{
-    exponential_slowdown, Config, DataIO as DataIOT, DelayConfig, Hasher, Index, KeyBox as KeyBoxT,
-    Member, MultiKeychain as MultiKeychainT, Network as NetworkT, NodeCount, NodeIndex,
-    OrderedBatch, PartialMultisignature as PartialMultisignatureT, SpawnHandle, TaskHandle,
+    exponential_slowdown, Config, DataIO as DataIOT, DelayConfig, Dissemination, Hasher, Index,
+    KeyBox as KeyBoxT, Member, MultiKeychain as MultiKeychainT, MultiVerifier, Network as NetworkT,
+    NodeCount, NodeIndex, OrderedBatch, PartialMultisignature as PartialMultisignatureT,
+    SpawnHandle, SystemClock, TaskHandle, Verifier,
 };
 
 pub fn init_log() {
@@ -34,14 +35,28 @@ pub fn init_log() {
         .try_init();
 }
 
+fn request_delay(t: usize) -> Duration {
+    exponential_slowdown(t, 50.0, 2, 1.5).min(Duration::from_millis(500))
+}
+
 pub fn gen_config(node_ix: NodeIndex, n_members: NodeCount) -> Config {
     let delay_config = DelayConfig {
         tick_interval: Duration::from_millis(5),
-        requests_interval: Duration::from_millis(50),
         unit_broadcast_delay: Arc::new(|t| exponential_slowdown(t, 100.0, 1, 3.0)),
         //100, 100, 300, 900, 2700, ...
+        coord_request_delay: Arc::new(request_delay),
+        //50, 50, 50, 75, 112, ..., 500, 500, ...
+        coord_request_recipients: Arc::new(|t| 1 << t.min(2)),
+        //1, 2, 4, 4, ...
+        parent_request_delay: Arc::new(request_delay),
+        parent_request_recipients: Arc::new(|t| 1 << t.min(2)),
+        newest_request_delay: Arc::new(|t| exponential_slowdown(t, 100.0, 1, 3.0)),
+        request_attempts_limit: None,
+        network_congestion_delay: Duration::from_millis(50),
+        unit_rebroadcast_horizon: None,
         unit_creation_delay: Arc::new(|t| exponential_slowdown(t, 50.0, usize::MAX, 1.000)),
         //50, 50, 50, 50, ...
+        unit_verification_window: Duration::from_millis(1),
     };
     Config {
         node_ix,
@@ -49,6 +64,9 @@ pub fn gen_config(node_ix: NodeIndex, n_members: NodeCount) -> Config {
         n_members,
         delay_config,
         max_round: 5000,
+        dissemination: Dissemination::Broadcast,
+        verification_workers: 2,
+        clock: Arc::new(SystemClock),
     }
 }
 
@@ -149,21 +167,31 @@ impl<
         MS: aleph_bft::PartialMultisignature,
     > NetworkT<H, D, S, MS> for Network<H, D, S, MS>
 {
-    fn send(&self, data: aleph_bft::NetworkData<H, D, S, MS>, recipient: aleph_bft::Recipient) {
+    fn send(
+        &self,
+        data: aleph_bft::NetworkData<H, D, S, MS>,
+        recipient: aleph_bft::Recipient,
+    ) -> Result<(), aleph_bft::SendError> {
         use aleph_bft::Recipient::*;
         match recipient {
             Node(node) => self
                 .tx
                 .unbounded_send((data, node))
                 .expect("send on channel should work"),
+            Nodes(nodes) => {
+                for node in nodes {
+                    self.send(data.clone(), Node(node))?;
+                }
+            }
             Everyone => {
                 for peer in self.peers.iter() {
                     if *peer != self.index {
-                        self.send(data.clone(), Node(*peer));
+                        self.send(data.clone(), Node(*peer))?;
                     }
                 }
             }
         }
+        Ok(())
     }
 
     async fn next_event(&mut self) -> Option<aleph_bft::NetworkData<H, D, S, MS>> {
@@ -393,32 +421,37 @@ impl Index for KeyBox {
     }
 }
 
-#[async_trait]
-impl KeyBoxT for KeyBox {
+impl Verifier for KeyBox {
     type Signature = Signature;
 
     fn node_count(&self) -> NodeCount {
         self.count
     }
 
+    fn verify(&self, _msg: &[u8], _sgn: &Signature, _index: NodeIndex) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl KeyBoxT for KeyBox {
     async fn sign(&self, _msg: &[u8]) -> Signature {
         Signature {}
     }
+}
 
-    fn verify(&self, _msg: &[u8], _sgn: &Signature, _index: NodeIndex) -> bool {
-        true
+impl MultiVerifier for KeyBox {
+    type PartialMultisignature = PartialMultisignature;
+    fn is_complete(&self, _: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        (self.count * 2) / 3 < NodeCount(partial.signed_by.len())
     }
 }
 
 impl MultiKeychainT for KeyBox {
-    type PartialMultisignature = PartialMultisignature;
     fn from_signature(&self, _: &Self::Signature, index: NodeIndex) -> Self::PartialMultisignature {
         let signed_by = vec![index];
         PartialMultisignature { signed_by }
     }
-    fn is_complete(&self, _: &[u8], partial: &Self::PartialMultisignature) -> bool {
-        (self.count * 2) / 3 < NodeCount(partial.signed_by.len())
-    }
 }
 
 pub fn configure_network<
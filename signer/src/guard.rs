@@ -0,0 +1,215 @@
+use aleph_bft::{Round, SessionId};
+use codec::{Decode, Encode};
+use log::{error, warn};
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// Remembers the hashes of the units signed so far, so that the signer never signs two different
+/// units of the same round in the same session, which would make us a forker.
+///
+/// Every signed unit is appended to a file and synced to disk before its signature is released,
+/// so the guard survives restarts of the signer.
+pub(crate) struct EquivocationGuard {
+    signed_units: HashMap<(SessionId, Round), Vec<u8>>,
+    file: File,
+    // The length of the complete records in the file.
+    len: u64,
+    // Set when a failed write could not be undone, as the records appended after it would be lost
+    // when the file is read again. The guard then refuses all units until it is reopened.
+    poisoned: bool,
+}
+
+impl EquivocationGuard {
+    /// Opens the guard stored in the file at `path`, creating the file if it does not exist.
+    /// A partially written record at the end of the file, left by a crash while appending it,
+    /// is discarded, as the signature of its unit was never released.
+    pub(crate) fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let mut signed_units = HashMap::new();
+        let mut input = &bytes[..];
+        while !input.is_empty() {
+            match <(SessionId, Round, Vec<u8>)>::decode(&mut input) {
+                Ok((session_id, round, msg)) => {
+                    signed_units.insert((session_id, round), msg);
+                }
+                Err(_) => {
+                    let valid_len = bytes.len() - input.len();
+                    warn!(target: "AlephBFT-signer", "Discarding {} bytes of a partially written record of the equivocation guard.", input.len());
+                    file.set_len(valid_len as u64)?;
+                    file.seek(SeekFrom::End(0))?;
+                    break;
+                }
+            }
+        }
+        let len = file.seek(SeekFrom::End(0))?;
+        Ok(EquivocationGuard {
+            signed_units,
+            file,
+            len,
+            poisoned: false,
+        })
+    }
+
+    /// Checks whether the unit with hash `msg` may be signed, and if so records it as signed.
+    /// Signing the same unit again is allowed, e.g. when the response to an earlier request got
+    /// lost. Returns an error if recording the unit on disk fails, in which case it must not be
+    /// signed, and for every unit after a failure the guard could not recover from.
+    pub(crate) fn allows(
+        &mut self,
+        session_id: SessionId,
+        round: Round,
+        msg: &[u8],
+    ) -> io::Result<bool> {
+        if self.poisoned {
+            return Err(io::Error::other(
+                "the equivocation guard could not undo a failed write and must be reopened",
+            ));
+        }
+        if let Some(signed) = self.signed_units.get(&(session_id, round)) {
+            return Ok(signed == msg);
+        }
+        let record = (session_id, round, msg).encode();
+        if let Err(e) = self
+            .file
+            .write_all(&record)
+            .and_then(|()| self.file.sync_data())
+        {
+            self.undo_write();
+            return Err(e);
+        }
+        self.len += record.len() as u64;
+        self.signed_units.insert((session_id, round), msg.to_vec());
+        Ok(true)
+    }
+
+    // Truncates the file back to its complete records, dropping whatever part of a record a
+    // failed write left, so that the records appended later are read back correctly.
+    fn undo_write(&mut self) {
+        if let Err(e) = self
+            .file
+            .set_len(self.len)
+            .and_then(|()| self.file.sync_data())
+        {
+            error!(target: "AlephBFT-signer", "Failed to undo a write to the equivocation guard, refusing to sign until it is reopened: {}.", e);
+            self.poisoned = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EquivocationGuard;
+    use codec::Encode;
+    use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+    fn guard_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "aleph-bft-signer-{}-{}.guard",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn allows_signing_the_same_unit_again() {
+        let mut guard = EquivocationGuard::open(guard_path("again")).expect("the file is free");
+        assert!(guard.allows(0, 3, b"unit").expect("writing succeeds"));
+        assert!(guard.allows(0, 3, b"unit").expect("writing succeeds"));
+    }
+
+    #[test]
+    fn refuses_a_different_unit_of_the_same_round() {
+        let mut guard = EquivocationGuard::open(guard_path("refuses")).expect("the file is free");
+        assert!(guard.allows(0, 3, b"unit").expect("writing succeeds"));
+        assert!(!guard.allows(0, 3, b"fork").expect("writing succeeds"));
+        assert!(guard.allows(0, 4, b"fork").expect("writing succeeds"));
+        assert!(guard.allows(1, 3, b"fork").expect("writing succeeds"));
+    }
+
+    #[test]
+    fn remembers_signed_units_after_reopening() {
+        let path = guard_path("reopens");
+        let mut guard = EquivocationGuard::open(&path).expect("the file is free");
+        assert!(guard.allows(0, 3, b"unit").expect("writing succeeds"));
+        assert!(guard.allows(0, 4, b"unit").expect("writing succeeds"));
+        drop(guard);
+
+        let mut guard = EquivocationGuard::open(&path).expect("the file exists");
+        assert!(!guard.allows(0, 3, b"fork").expect("writing succeeds"));
+        assert!(!guard.allows(0, 4, b"fork").expect("writing succeeds"));
+        assert!(guard.allows(0, 3, b"unit").expect("writing succeeds"));
+    }
+
+    #[test]
+    fn discards_a_partially_written_record() {
+        let path = guard_path("partial");
+        let mut guard = EquivocationGuard::open(&path).expect("the file is free");
+        assert!(guard.allows(0, 3, b"unit").expect("writing succeeds"));
+        drop(guard);
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .expect("the file exists")
+            .write_all(&[0, 0, 0])
+            .expect("writing succeeds");
+
+        let mut guard = EquivocationGuard::open(&path).expect("the file exists");
+        assert!(guard.allows(0, 4, b"unit").expect("writing succeeds"));
+        drop(guard);
+        let mut guard = EquivocationGuard::open(&path).expect("the file exists");
+        assert!(!guard.allows(0, 3, b"fork").expect("writing succeeds"));
+        assert!(!guard.allows(0, 4, b"fork").expect("writing succeeds"));
+    }
+
+    #[test]
+    fn keeps_records_appended_after_a_failed_write() {
+        let path = guard_path("failed-write");
+        let mut guard = EquivocationGuard::open(&path).expect("the file is free");
+        assert!(guard.allows(0, 3, b"unit").expect("writing succeeds"));
+        // A write failing midway leaves a part of the record in the middle of the file.
+        let record = (0u64, 4u16, b"unit".to_vec()).encode();
+        guard
+            .file
+            .write_all(&record[..record.len() / 2])
+            .expect("writing succeeds");
+        guard.undo_write();
+        assert!(guard.allows(0, 5, b"unit").expect("writing succeeds"));
+        drop(guard);
+
+        let mut guard = EquivocationGuard::open(&path).expect("the file exists");
+        assert!(!guard.allows(0, 3, b"fork").expect("writing succeeds"));
+        assert!(!guard.allows(0, 5, b"fork").expect("writing succeeds"));
+        assert!(guard.allows(0, 4, b"fork").expect("writing succeeds"));
+    }
+
+    #[test]
+    fn refuses_everything_until_reopened_if_a_write_cannot_be_undone() {
+        let path = guard_path("poisoned");
+        let mut guard = EquivocationGuard::open(&path).expect("the file is free");
+        assert!(guard.allows(0, 3, b"unit").expect("writing succeeds"));
+        // Neither writing nor truncating works through a read-only handle.
+        guard.file = OpenOptions::new()
+            .read(true)
+            .open(&path)
+            .expect("the file exists");
+        assert!(guard.allows(0, 4, b"unit").is_err());
+        assert!(guard.allows(0, 3, b"unit").is_err());
+        drop(guard);
+
+        let mut guard = EquivocationGuard::open(&path).expect("the file exists");
+        assert!(guard.allows(0, 3, b"unit").expect("writing succeeds"));
+        assert!(guard.allows(0, 4, b"unit").expect("writing succeeds"));
+    }
+}
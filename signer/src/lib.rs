@@ -0,0 +1,303 @@
+//! An AlephBFT [`KeyBox`] keeping the secret key outside of the node process.
+//!
+//! The [`RemoteKeyBox`] forwards every signing request over a Unix domain socket to a separate
+//! signer process, which answers them using its own [`KeyBox`] with [`serve`]. The
+//! `aleph-bft-signer` binary of this crate is a reference signer holding an ed25519 key.
+//! Signatures are verified locally, so only signing involves the signer.
+//!
+//! The signer never signs two different units of the same round in the same session, which
+//! protects the node from accidentally forking, e.g. when it is restarted without its backup.
+//! Units are recognized by their signed bytes with [`aleph_bft::unit_signing_round`], and the
+//! signed ones are recorded on disk before their signatures are released, so this holds across
+//! restarts of the signer as well. The signing of such a unit never finishes, so the node stops
+//! creating units instead.
+
+use crate::frame::{read_frame, write_frame};
+use aleph_bft::{unit_signing_round, Index, KeyBox, NodeCount, NodeIndex, Verifier};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use futures::future;
+use log::{error, warn};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{net::UnixStream, sync::Mutex, time};
+
+mod frame;
+mod guard;
+mod protocol;
+mod server;
+
+use protocol::{Request, Response};
+pub use server::serve;
+
+/// The maximal size of a response accepted from the signer, large enough for any signature.
+const MAX_RESPONSE_SIZE: usize = 64 * 1024;
+
+/// Configuration of a [`RemoteKeyBox`].
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The time after which a request is abandoned and sent again over a new connection.
+    pub request_timeout: Duration,
+    /// The delay before reconnecting to the signer, doubled after every failed attempt.
+    pub reconnect_delay: Duration,
+    /// The maximal delay before reconnecting to the signer.
+    pub max_reconnect_delay: Duration,
+}
+
+/// Default configuration of a [`RemoteKeyBox`].
+pub fn default_config() -> Config {
+    Config {
+        request_timeout: Duration::from_secs(2),
+        reconnect_delay: Duration::from_millis(100),
+        max_reconnect_delay: Duration::from_secs(10),
+    }
+}
+
+/// A [`KeyBox`] asking a signer listening on a Unix domain socket for signatures, and verifying
+/// them with a local [`Verifier`].
+///
+/// Requests are sent one at a time over a single connection, which is reestablished with an
+/// exponential backoff whenever it fails. A request which fails or times out is retried until
+/// the signer answers it, so signing waits for the signer to become available.
+#[derive(Clone)]
+pub struct RemoteKeyBox<V: Verifier> {
+    index: NodeIndex,
+    verifier: V,
+    socket_path: PathBuf,
+    config: Config,
+    connection: Arc<Mutex<Option<UnixStream>>>,
+}
+
+impl<V: Verifier> RemoteKeyBox<V> {
+    /// Creates the key box of the member with `index`, whose signer listens on `socket_path`.
+    /// Does not connect to the signer before the first signing request.
+    pub fn new<P: AsRef<Path>>(
+        index: NodeIndex,
+        verifier: V,
+        socket_path: P,
+        config: Config,
+    ) -> Self {
+        RemoteKeyBox {
+            index,
+            verifier,
+            socket_path: socket_path.as_ref().to_path_buf(),
+            config,
+            connection: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    async fn request(&self, request: Request) -> Response<V::Signature> {
+        let mut connection = self.connection.lock().await;
+        let mut delay = self.config.reconnect_delay;
+        loop {
+            match self.try_request(&mut connection, &request).await {
+                Ok(response) => return response,
+                Err(e) => {
+                    warn!(target: "AlephBFT-signer", "{:?} Signing request failed: {}, retrying in {:?}.", self.index, e, delay);
+                    *connection = None;
+                    time::sleep(delay).await;
+                    delay = (delay * 2).min(self.config.max_reconnect_delay);
+                }
+            }
+        }
+    }
+
+    async fn try_request(
+        &self,
+        connection: &mut Option<UnixStream>,
+        request: &Request,
+    ) -> io::Result<Response<V::Signature>> {
+        if connection.is_none() {
+            *connection = Some(UnixStream::connect(&self.socket_path).await?);
+        }
+        let stream = connection.as_mut().expect("we have just connected");
+        let exchange = async {
+            write_frame(stream, &request.encode()).await?;
+            let payload = read_frame(stream, MAX_RESPONSE_SIZE).await?;
+            Response::decode(&mut &payload[..])
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+        };
+        time::timeout(self.config.request_timeout, exchange)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "The signer did not answer."))?
+    }
+}
+
+impl<V: Verifier> Index for RemoteKeyBox<V> {
+    fn index(&self) -> NodeIndex {
+        self.index
+    }
+}
+
+impl<V: Verifier> Verifier for RemoteKeyBox<V> {
+    type Signature = V::Signature;
+
+    fn node_count(&self) -> NodeCount {
+        self.verifier.node_count()
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        self.verifier.verify(msg, sgn, index)
+    }
+
+    fn verify_batch(&self, batch: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+        self.verifier.verify_batch(batch)
+    }
+}
+
+#[async_trait]
+impl<V: Verifier> KeyBox for RemoteKeyBox<V> {
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        match self.request(Request::Sign(msg.to_vec())).await {
+            Response::Signature(signature) => signature,
+            Response::Refused => match unit_signing_round(msg) {
+                Some((session_id, round)) => {
+                    error!(target: "AlephBFT-signer", "{:?} The signer refused to sign our unit of round {} in session {}, as it has signed a different one already.", self.index, round, session_id);
+                    // We must not fork, so we never finish signing.
+                    future::pending().await
+                }
+                // The signer refuses only units, so it is broken and we cannot sign at all.
+                None => panic!("The signer refused to sign a message which is not a unit."),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{default_config, serve, RemoteKeyBox};
+    use aleph_bft::{
+        ed25519::{generate_keys, KeyBox, PublicKeys},
+        testing::sign_unit_and_newest_response,
+        unit_signing_message, KeyBox as KeyBoxT, NodeIndex, Round, SessionId, Verifier,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::{
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+    use tokio::{net::UnixListener, time::timeout};
+
+    fn temp_path(name: &str, extension: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "aleph-bft-signer-{}-{}.{}",
+            std::process::id(),
+            name,
+            extension
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn keys() -> (KeyBox, PublicKeys) {
+        let (mut secret_keys, public_keys) = generate_keys(&mut StdRng::seed_from_u64(0), 4.into());
+        let keybox = KeyBox::new(NodeIndex(0), secret_keys.remove(0), public_keys.clone())
+            .expect("the key belongs to the member");
+        (keybox, public_keys)
+    }
+
+    fn start_signer(path: &Path, keybox: KeyBox, guard_path: &Path) {
+        let listener = UnixListener::bind(path).expect("the socket should be free");
+        let guard_path = guard_path.to_path_buf();
+        tokio::spawn(serve(listener, keybox, guard_path));
+    }
+
+    /// The bytes signed to create a unit with the given hash.
+    fn unit(session_id: SessionId, round: Round, hash: &[u8]) -> Vec<u8> {
+        unit_signing_message(session_id, round, hash)
+    }
+
+    #[tokio::test]
+    async fn signs_through_the_signer() {
+        let path = temp_path("signs", "sock");
+        let (keybox, public_keys) = keys();
+        start_signer(&path, keybox, &temp_path("signs", "guard"));
+        let remote = RemoteKeyBox::new(NodeIndex(0), public_keys.clone(), &path, default_config());
+
+        let signature = remote.sign(b"message").await;
+        assert!(public_keys.verify(b"message", &signature, NodeIndex(0)));
+        let signature = remote.sign(&unit(0, 3, b"unit")).await;
+        assert!(remote.verify(&unit(0, 3, b"unit"), &signature, NodeIndex(0)));
+    }
+
+    #[tokio::test]
+    async fn signs_units_with_large_data() {
+        let path = temp_path("large", "sock");
+        let (keybox, public_keys) = keys();
+        start_signer(&path, keybox, &temp_path("large", "guard"));
+        let remote = RemoteKeyBox::new(NodeIndex(0), public_keys, &path, default_config());
+
+        let signing = sign_unit_and_newest_response(&remote, 0, 3, vec![7u8; 4096]);
+        assert!(
+            timeout(Duration::from_secs(5), signing)
+                .await
+                .expect("the signer should answer requests about large units"),
+            "the signatures should be correct"
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_to_sign_a_second_unit_of_a_round() {
+        let path = temp_path("refuses", "sock");
+        let (keybox, public_keys) = keys();
+        start_signer(&path, keybox, &temp_path("refuses", "guard"));
+        let remote = RemoteKeyBox::new(NodeIndex(0), public_keys, &path, default_config());
+
+        remote.sign(&unit(0, 3, b"unit")).await;
+        remote.sign(&unit(0, 3, b"unit")).await;
+        remote.sign(&unit(1, 3, b"fork")).await;
+        assert!(
+            timeout(
+                Duration::from_millis(500),
+                remote.sign(&unit(0, 3, b"fork"))
+            )
+            .await
+            .is_err(),
+            "a second unit of the same round should never be signed"
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_to_sign_a_second_unit_of_a_round_after_a_restart() {
+        let guard_path = temp_path("restarts", "guard");
+        let (keybox, public_keys) = keys();
+        let path = temp_path("restarts-first", "sock");
+        start_signer(&path, keybox.clone(), &guard_path);
+        let remote = RemoteKeyBox::new(NodeIndex(0), public_keys.clone(), &path, default_config());
+        remote.sign(&unit(0, 3, b"unit")).await;
+
+        let path = temp_path("restarts-second", "sock");
+        start_signer(&path, keybox, &guard_path);
+        let remote = RemoteKeyBox::new(NodeIndex(0), public_keys, &path, default_config());
+        remote.sign(&unit(0, 3, b"unit")).await;
+        assert!(
+            timeout(
+                Duration::from_millis(500),
+                remote.sign(&unit(0, 3, b"fork"))
+            )
+            .await
+            .is_err(),
+            "a restarted signer should remember the units it signed"
+        );
+    }
+
+    #[tokio::test]
+    async fn waits_for_the_signer_to_start() {
+        let path = temp_path("waits", "sock");
+        let (keybox, public_keys) = keys();
+        let remote = RemoteKeyBox::new(NodeIndex(0), public_keys.clone(), &path, default_config());
+
+        let signing = tokio::spawn(async move { remote.sign(b"message").await });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        start_signer(&path, keybox, &temp_path("waits", "guard"));
+        let signature = timeout(Duration::from_secs(5), signing)
+            .await
+            .expect("signing should finish once the signer starts")
+            .expect("signing should not panic");
+        assert!(public_keys.verify(b"message", &signature, NodeIndex(0)));
+    }
+}
@@ -0,0 +1,55 @@
+use aleph_bft::ed25519::{KeyBox, PublicKeys, SecretKey, SECRET_KEY_LENGTH};
+use aleph_bft_signer::serve;
+use codec::Decode;
+use log::info;
+use std::convert::TryInto;
+use tokio::net::UnixListener;
+
+const USAGE_MSG: &str = "Missing arg. Usage
+    aleph-bft-signer socket_path key_file my_id guard_file
+
+    socket_path -- the Unix domain socket to listen on
+    key_file -- our secret key followed by the SCALE encoded public keys of the committee
+    my_id -- our index
+    guard_file -- the file recording the signed units, kept across restarts";
+
+fn arg(n: usize) -> String {
+    std::env::args()
+        .nth(n)
+        .unwrap_or_else(|| panic!("{}", USAGE_MSG))
+}
+
+fn read_keys(path: &str) -> (SecretKey, PublicKeys) {
+    let bytes = std::fs::read(path).expect("Failed to read the key file");
+    if bytes.len() < SECRET_KEY_LENGTH {
+        panic!("The key file is too short to contain a secret key");
+    }
+    let (secret_key, mut public_keys) = bytes.split_at(SECRET_KEY_LENGTH);
+    let secret_key = SecretKey::from_bytes(secret_key.try_into().expect("the length is correct"));
+    let public_keys = PublicKeys::decode(&mut public_keys).expect("Failed to decode public keys");
+    (secret_key, public_keys)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    env_logger::builder()
+        .filter_module("AlephBFT-signer", log::LevelFilter::Info)
+        .init();
+
+    let socket_path = arg(1);
+    let (secret_key, public_keys) = read_keys(&arg(2));
+    let my_id = arg(3)
+        .parse::<usize>()
+        .unwrap_or_else(|err| panic!("Failed to parse arg {:?}", err));
+    let guard_file = arg(4);
+
+    let keybox = KeyBox::new(my_id.into(), secret_key, public_keys)
+        .expect("The secret key should belong to the member");
+    // A socket left behind by a previous run would make binding fail.
+    let _ = std::fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path).expect("Failed to bind the socket");
+    info!(target: "AlephBFT-signer", "Signing for member {} on {}.", my_id, socket_path);
+    serve(listener, keybox, guard_file)
+        .await
+        .expect("Failed to open the guard file or accept a connection");
+}
@@ -0,0 +1,16 @@
+use codec::{Decode, Encode};
+
+/// A request sent by a [`RemoteKeyBox`](crate::RemoteKeyBox) to the signer.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub(crate) enum Request {
+    /// Sign a message, which might be the hash of one of our units.
+    Sign(Vec<u8>),
+}
+
+/// The answer of the signer to a [`Request`].
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub(crate) enum Response<S> {
+    Signature(S),
+    /// The signer has already signed a different unit of the same round in the same session.
+    Refused,
+}
@@ -0,0 +1,99 @@
+use crate::frame::{read_frame, write_frame};
+use crate::{
+    guard::EquivocationGuard,
+    protocol::{Request, Response},
+};
+use aleph_bft::{unit_signing_round, KeyBox};
+use codec::{Decode, Encode};
+use log::{debug, error, warn};
+use std::{
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    net::{UnixListener, UnixStream},
+    task,
+};
+
+/// The maximal size of a request accepted by the signer. The protocol signs only hashes, so this
+/// is enough for any message it signs.
+const MAX_REQUEST_SIZE: usize = 1024;
+
+/// Answers the signing requests of [`RemoteKeyBox`](crate::RemoteKeyBox)es connecting to
+/// `listener`, signing with `keybox`. Units, recognized with [`unit_signing_round`], are signed
+/// only if no different unit of the same round in the same session was ever signed with the guard
+/// file at `guard_path`, which is created if it does not exist. Has to be called from within a
+/// tokio runtime, runs until opening the guard file or accepting a connection fails.
+pub async fn serve<KB: KeyBox, P: AsRef<Path>>(
+    listener: UnixListener,
+    keybox: KB,
+    guard_path: P,
+) -> io::Result<()> {
+    let guard = Arc::new(Mutex::new(EquivocationGuard::open(guard_path)?));
+    loop {
+        let (stream, _) = listener.accept().await?;
+        debug!(target: "AlephBFT-signer", "Accepted a connection.");
+        tokio::spawn(handle_connection(stream, keybox.clone(), guard.clone()));
+    }
+}
+
+async fn handle_connection<KB: KeyBox>(
+    mut stream: UnixStream,
+    keybox: KB,
+    guard: Arc<Mutex<EquivocationGuard>>,
+) {
+    loop {
+        let payload = match read_frame(&mut stream, MAX_REQUEST_SIZE).await {
+            Ok(payload) => payload,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                debug!(target: "AlephBFT-signer", "Connection closed.");
+                return;
+            }
+            Err(e) => {
+                warn!(target: "AlephBFT-signer", "Failed to read a request: {}.", e);
+                return;
+            }
+        };
+        let request = match Request::decode(&mut &payload[..]) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!(target: "AlephBFT-signer", "Received a malformed request: {}.", e);
+                return;
+            }
+        };
+        let Request::Sign(msg) = request;
+        let response = match unit_signing_round(&msg) {
+            None => Response::Signature(keybox.sign(&msg).await),
+            Some((session_id, round)) => {
+                // Recording the unit waits for the disk, so it cannot block the runtime.
+                let guard = guard.clone();
+                let unit = msg.clone();
+                let allowed = task::spawn_blocking(move || {
+                    guard
+                        .lock()
+                        .expect("the guard is never used while panicking")
+                        .allows(session_id, round, &unit)
+                })
+                .await
+                .unwrap_or_else(|e| Err(io::Error::other(e)));
+                match allowed {
+                    Ok(true) => Response::Signature(keybox.sign(&msg).await),
+                    Ok(false) => {
+                        error!(target: "AlephBFT-signer", "Refused to sign a second unit of round {} in session {}.", round, session_id);
+                        Response::Refused
+                    }
+                    Err(e) => {
+                        // The client retries the request over a new connection.
+                        error!(target: "AlephBFT-signer", "Failed to record the unit of round {} in session {}: {}.", round, session_id, e);
+                        return;
+                    }
+                }
+            }
+        };
+        if let Err(e) = write_frame(&mut stream, &response.encode()).await {
+            warn!(target: "AlephBFT-signer", "Failed to send a response: {}.", e);
+            return;
+        }
+    }
+}
@@ -1,13 +1,16 @@
 use crate::{
-    network::Recipient,
+    environment::Clock,
+    network::{decode_bounded_vec, DecodeBounded, DecodingLimits, Recipient},
     nodes::NodeCount,
     rmc,
     rmc::{DoublingDelayScheduler, ReliableMulticast},
-    signed::{Multisigned, PartialMultisignature, Signable, Signature, Signed, UncheckedSigned},
+    signed::{
+        DomainTag, Multisigned, PartialMultisignature, Signable, Signature, Signed, UncheckedSigned,
+    },
     units::UncheckedSignedUnit,
     Data, Hasher, Index, MultiKeychain, NodeIndex, Receiver, Sender, SessionId,
 };
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, Error, Input};
 use derivative::Derivative;
 use futures::{
     channel::{mpsc, oneshot},
@@ -18,6 +21,7 @@ use parking_lot::RwLock;
 use std::{
     collections::{HashMap, HashSet},
     ops::Deref,
+    sync::Arc,
     time,
 };
 
@@ -27,6 +31,7 @@ pub(crate) type ForkProof<H, D, S> = (UncheckedSignedUnit<H, D, S>, UncheckedSig
 #[derivative(PartialEq, Eq, Hash)]
 pub(crate) struct Alert<H: Hasher, D: Data, S: Signature> {
     sender: NodeIndex,
+    session_id: SessionId,
     proof: ForkProof<H, D, S>,
     legit_units: Vec<UncheckedSignedUnit<H, D, S>>,
     #[codec(skip)]
@@ -43,6 +48,7 @@ impl<H: Hasher, D: Data, S: Signature> Clone for Alert<H, D, S> {
         };
         Alert {
             sender: self.sender,
+            session_id: self.session_id,
             proof: self.proof.clone(),
             legit_units: self.legit_units.clone(),
             hash: RwLock::new(hash),
@@ -53,17 +59,19 @@ impl<H: Hasher, D: Data, S: Signature> Clone for Alert<H, D, S> {
 impl<H: Hasher, D: Data, S: Signature> Alert<H, D, S> {
     pub fn new(
         sender: NodeIndex,
+        session_id: SessionId,
         proof: ForkProof<H, D, S>,
         legit_units: Vec<UncheckedSignedUnit<H, D, S>>,
     ) -> Alert<H, D, S> {
         Alert {
             sender,
+            session_id,
             proof,
             legit_units,
             hash: RwLock::new(None),
         }
     }
-    fn hash(&self) -> H::Hash {
+    pub(crate) fn hash(&self) -> H::Hash {
         let hash = *self.hash.read();
         match hash {
             Some(hash) => hash,
@@ -77,7 +85,7 @@ impl<H: Hasher, D: Data, S: Signature> Alert<H, D, S> {
 
     // Simplified forker check, should only be called for alerts that have already been checked to
     // contain valid proofs.
-    fn forker(&self) -> NodeIndex {
+    pub(crate) fn forker(&self) -> NodeIndex {
         self.proof.0.as_signable().creator()
     }
 
@@ -90,6 +98,21 @@ impl<H: Hasher, D: Data, S: Signature> Alert<H, D, S> {
     }
 }
 
+impl<H: Hasher, D: Data, S: Signature> DecodeBounded for Alert<H, D, S> {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let sender = NodeIndex::decode(input)?;
+        let session_id = SessionId::decode(input)?;
+        let proof = (
+            UncheckedSignedUnit::decode_bounded(input, limits)?,
+            UncheckedSignedUnit::decode_bounded(input, limits)?,
+        );
+        // Alerted units must come from different rounds, so there cannot be more of them than rounds.
+        let max_legit_units = limits.max_round as usize + 1;
+        let legit_units = decode_bounded_vec(input, max_legit_units, limits)?;
+        Ok(Alert::new(sender, session_id, proof, legit_units))
+    }
+}
+
 impl<H: Hasher, D: Data, S: Signature> Index for Alert<H, D, S> {
     fn index(&self) -> NodeIndex {
         self.sender
@@ -97,9 +120,30 @@ impl<H: Hasher, D: Data, S: Signature> Index for Alert<H, D, S> {
 }
 
 impl<H: Hasher, D: Data, S: Signature> Signable for Alert<H, D, S> {
-    type Hash = H::Hash;
+    type Hash = Vec<u8>;
+    fn hash(&self) -> Self::Hash {
+        (DomainTag::Alert, self.session_id, self.hash()).encode()
+    }
+}
+
+/// The hash of an alert together with the session it was raised in, which is what gets
+/// multisigned with RMC, so that a multisigned alert cannot be replayed in another session.
+#[derive(Clone, Copy, Debug, Encode, Decode, PartialEq, Eq, Hash)]
+pub(crate) struct AlertHash<T> {
+    session_id: SessionId,
+    hash: T,
+}
+
+impl<T> AlertHash<T> {
+    pub(crate) fn new(session_id: SessionId, hash: T) -> Self {
+        AlertHash { session_id, hash }
+    }
+}
+
+impl<T: Encode> Signable for AlertHash<T> {
+    type Hash = Vec<u8>;
     fn hash(&self) -> Self::Hash {
-        self.hash()
+        (DomainTag::AlertMulticast, self.session_id, &self.hash).encode()
     }
 }
 
@@ -109,7 +153,7 @@ pub(crate) enum AlertMessage<H: Hasher, D: Data, S: Signature, MS: PartialMultis
     /// Alert regarding forks, signed by the person claiming misconduct.
     ForkAlert(UncheckedSigned<Alert<H, D, S>, S>),
     /// An internal RMC message, together with the id of the sender.
-    RmcMessage(NodeIndex, rmc::Message<H::Hash, S, MS>),
+    RmcMessage(NodeIndex, rmc::Message<AlertHash<H::Hash>, S, MS>),
     /// A request by a node for a fork alert identified by the given hash.
     AlertRequest(NodeIndex, H::Hash),
 }
@@ -124,6 +168,23 @@ impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> AlertMessage<H
     }
 }
 
+impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> DecodeBounded
+    for AlertMessage<H, D, S, MS>
+{
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let message = match input.read_byte()? {
+            0 => Self::ForkAlert(UncheckedSigned::decode_bounded(input, limits)?),
+            1 => Self::RmcMessage(
+                NodeIndex::decode(input)?,
+                rmc::Message::decode_bounded(input, limits)?,
+            ),
+            2 => Self::AlertRequest(NodeIndex::decode(input)?, H::Hash::decode(input)?),
+            _ => return Err(Error::from("Invalid variant of AlertMessage.")),
+        };
+        Ok(message)
+    }
+}
+
 // Notifications being sent to consensus, so that it can learn about proven forkers and receive
 // legitimized units.
 #[derive(Debug, PartialEq, Eq, Hash)]
@@ -132,13 +193,19 @@ pub(crate) enum ForkingNotification<H: Hasher, D: Data, S: Signature> {
     Units(Vec<UncheckedSignedUnit<H, D, S>>),
 }
 
+type RmcMessage<H, MK> = rmc::Message<
+    AlertHash<<H as Hasher>::Hash>,
+    <MK as crate::Verifier>::Signature,
+    <MK as crate::MultiVerifier>::PartialMultisignature,
+>;
+
 /// The component responsible for fork alerts in AlephBFT. We refer to the documentation
 /// https://cardinal-cryptography.github.io/AlephBFT/how_alephbft_does_it.html Section 2.5 and
 /// https://cardinal-cryptography.github.io/AlephBFT/reliable_broadcast.html and to the Aleph
 /// paper https://arxiv.org/abs/1908.05156 Appendix A1 for a discussion.
-struct Alerter<'a, H: Hasher, D: Data, MK: MultiKeychain> {
+struct Alerter<H: Hasher, D: Data, MK: MultiKeychain> {
     session_id: SessionId,
-    keychain: &'a MK,
+    keychain: MK,
     messages_for_network: Sender<(
         AlertMessage<H, D, MK::Signature, MK::PartialMultisignature>,
         Recipient,
@@ -147,22 +214,23 @@ struct Alerter<'a, H: Hasher, D: Data, MK: MultiKeychain> {
     notifications_for_units: Sender<ForkingNotification<H, D, MK::Signature>>,
     alerts_from_units: Receiver<Alert<H, D, MK::Signature>>,
     known_forkers: HashMap<NodeIndex, ForkProof<H, D, MK::Signature>>,
-    known_alerts: HashMap<H::Hash, Signed<'a, Alert<H, D, MK::Signature>, MK>>,
+    known_alerts: HashMap<H::Hash, Signed<Alert<H, D, MK::Signature>, MK>>,
     known_rmcs: HashMap<(NodeIndex, NodeIndex), H::Hash>,
-    rmc: ReliableMulticast<'a, H::Hash, MK>,
-    messages_from_rmc: Receiver<rmc::Message<H::Hash, MK::Signature, MK::PartialMultisignature>>,
-    messages_for_rmc: Sender<rmc::Message<H::Hash, MK::Signature, MK::PartialMultisignature>>,
+    rmc: ReliableMulticast<AlertHash<H::Hash>, MK>,
+    messages_from_rmc: Receiver<RmcMessage<H, MK>>,
+    messages_for_rmc: Sender<RmcMessage<H, MK>>,
     exiting: bool,
 }
 
 pub(crate) struct AlertConfig {
     pub n_members: NodeCount,
     pub session_id: SessionId,
+    pub clock: Arc<dyn Clock>,
 }
 
-impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
+impl<H: Hasher, D: Data, MK: MultiKeychain> Alerter<H, D, MK> {
     fn new(
-        keychain: &'a MK,
+        keychain: MK,
         messages_for_network: Sender<(
             AlertMessage<H, D, MK::Signature, MK::PartialMultisignature>,
             Recipient,
@@ -178,7 +246,7 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
         let (messages_for_us, messages_from_rmc) = mpsc::unbounded();
         Self {
             session_id: config.session_id,
-            keychain,
+            keychain: keychain.clone(),
             messages_for_network,
             messages_from_network,
             notifications_for_units,
@@ -191,7 +259,7 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
                 messages_for_us,
                 keychain,
                 config.n_members,
-                DoublingDelayScheduler::new(time::Duration::from_millis(500)),
+                DoublingDelayScheduler::with_clock(time::Duration::from_millis(500), config.clock),
             ),
             messages_from_rmc,
             messages_for_rmc,
@@ -227,7 +295,7 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
     ) -> bool {
         let mut rounds = HashSet::new();
         for u in units {
-            let u = match u.clone().check(self.keychain) {
+            let u = match u.clone().check(&self.keychain) {
                 Ok(u) => u,
                 Err(_) => {
                     warn!(target: "AlephBFT-alerter", "{:?} One of the units is incorrectly signed.", self.index());
@@ -251,8 +319,8 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
     fn who_is_forking(&self, proof: &ForkProof<H, D, MK::Signature>) -> Option<NodeIndex> {
         let (u1, u2) = proof;
         let (u1, u2) = {
-            let u1 = u1.clone().check(self.keychain);
-            let u2 = u2.clone().check(self.keychain);
+            let u1 = u1.clone().check(&self.keychain);
+            let u2 = u2.clone().check(&self.keychain);
             match (u1, u2) {
                 (Ok(u1), Ok(u2)) => (u1, u2),
                 _ => {
@@ -286,19 +354,21 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
     async fn rmc_alert(
         &mut self,
         forker: NodeIndex,
-        alert: Signed<'a, Alert<H, D, MK::Signature>, MK>,
+        alert: Signed<Alert<H, D, MK::Signature>, MK>,
     ) {
         let hash = alert.as_signable().hash();
         self.known_rmcs
             .insert((alert.as_signable().sender, forker), hash);
         self.known_alerts.insert(hash, alert);
-        self.rmc.start_rmc(hash).await;
+        self.rmc
+            .start_rmc(AlertHash::new(self.session_id, hash))
+            .await;
     }
 
     async fn on_own_alert(&mut self, alert: Alert<H, D, MK::Signature>) {
         let forker = alert.forker();
         self.known_forkers.insert(forker, alert.proof.clone());
-        let alert = Signed::sign(alert, self.keychain).await;
+        let alert = Signed::sign(alert, &self.keychain).await;
         self.send_message_for_network(
             AlertMessage::ForkAlert(alert.clone().into()),
             Recipient::Everyone,
@@ -310,7 +380,7 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
         &mut self,
         alert: UncheckedSigned<Alert<H, D, MK::Signature>, MK::Signature>,
     ) {
-        let alert = match alert.check(self.keychain) {
+        let alert = match alert.check(&self.keychain) {
             Ok(alert) => alert,
             Err(e) => {
                 warn!(target: "AlephBFT-alerter","{:?} We have received an incorrectly signed alert: {:?}.", self.index(), e);
@@ -318,6 +388,10 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
             }
         };
         let contents = alert.as_signable();
+        if contents.session_id != self.session_id {
+            warn!(target: "AlephBFT-alerter", "{:?} Received an alert from session {} in session {}.", self.index(), contents.session_id, self.session_id);
+            return;
+        }
         if let Some(forker) = self.who_is_forking(&contents.proof) {
             if self.known_rmcs.contains_key(&(contents.sender, forker)) {
                 debug!(target: "AlephBFT-alerter","{:?} We already know about an alert by {:?} about {:?}.", self.index(), alert.as_signable().sender, forker);
@@ -345,12 +419,12 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
         self.send_message_for_network(AlertMessage::ForkAlert(alert.into()), Recipient::Node(node));
     }
 
-    fn on_rmc_message(
-        &mut self,
-        sender: NodeIndex,
-        message: rmc::Message<H::Hash, MK::Signature, MK::PartialMultisignature>,
-    ) {
-        let hash = message.hash();
+    fn on_rmc_message(&mut self, sender: NodeIndex, message: RmcMessage<H, MK>) {
+        let AlertHash { session_id, hash } = message.hash();
+        if *session_id != self.session_id {
+            warn!(target: "AlephBFT-alerter", "{:?} Received an RMC message from session {} in session {}.", self.index(), session_id, self.session_id);
+            return;
+        }
         if let Some(alert) = self.known_alerts.get(hash) {
             let alert_id = (alert.as_signable().sender, alert.as_signable().forker());
             if self.known_rmcs.get(&alert_id) == Some(hash) || message.is_complete() {
@@ -383,8 +457,8 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
         }
     }
 
-    fn alert_confirmed(&mut self, multisigned: Multisigned<'a, H::Hash, MK>) {
-        let alert = match self.known_alerts.get(multisigned.as_signable()) {
+    fn alert_confirmed(&mut self, multisigned: Multisigned<AlertHash<H::Hash>, MK>) {
+        let alert = match self.known_alerts.get(&multisigned.as_signable().hash) {
             Some(alert) => alert.as_signable(),
             None => {
                 error!(target: "AlephBFT-alerter", "{:?} Completed an RMC for an unknown alert.", self.index());
@@ -401,10 +475,7 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
         self.send_notification_for_units(notification);
     }
 
-    fn rmc_message_to_network(
-        &mut self,
-        message: rmc::Message<H::Hash, MK::Signature, MK::PartialMultisignature>,
-    ) {
+    fn rmc_message_to_network(&mut self, message: RmcMessage<H, MK>) {
         self.send_message_for_network(
             AlertMessage::RmcMessage(self.index(), message),
             Recipient::Everyone,
@@ -442,7 +513,7 @@ impl<'a, H: Hasher, D: Data, MK: MultiKeychain> Alerter<'a, H, D, MK> {
 
     async fn run(&mut self, mut exit: oneshot::Receiver<()>) {
         loop {
-            futures::select! {
+            futures::select_biased! {
                 message = self.messages_from_network.next() => match message {
                     Some(message) => self.on_message(message).await,
                     None => {
@@ -491,7 +562,7 @@ pub(crate) async fn run<H: Hasher, D: Data, MK: MultiKeychain>(
     exit: oneshot::Receiver<()>,
 ) {
     Alerter::new(
-        &keychain,
+        keychain,
         messages_for_network,
         messages_from_network,
         notifications_for_units,
@@ -0,0 +1,685 @@
+//! A [`MultiKeychain`](crate::MultiKeychain) aggregating BLS signatures, available with the `bls`
+//! feature.
+//!
+//! Signatures are points of the group G1 of the BLS12-381 curve and public keys are points of G2.
+//! A partial [`Multisignature`] is the sum of the signatures it contains together with a bitmap of
+//! their signers, so it takes 48 bytes and a bit per member of the committee, and checking whether
+//! it is complete takes a single pairing check against the sum of the public keys of the signers.
+//!
+//! As the public keys of the signers are summed, a member could choose its public key so that it
+//! cancels the keys of others. To prevent it, every member proves that it knows its secret key with
+//! [`SecretKey::prove_possession`], and [`PublicKeys`] only accept keys with valid proofs.
+//!
+//! Components which only check signatures and multisignatures can use the [`PublicKeys`] alone,
+//! as a [`MultiVerifier`](crate::MultiVerifier). Batches of signatures are verified together, with
+//! a single pairing check against a random linear combination of them.
+
+use crate::{
+    network::DecodeBounded, nodes::BoolNodeMap, DecodingLimits, Index, KeyBox, MultiKeychain,
+    MultiVerifier, NodeCount, NodeIndex, PartialMultisignature, Verifier,
+};
+use async_trait::async_trait;
+use bls12_381::{
+    hash_to_curve::{ExpandMsgXmd, HashToCurve},
+    multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar,
+};
+use codec::{Decode, Encode, Error, Input, Output};
+use rand::{CryptoRng, RngCore};
+use std::fmt::{self, Debug};
+
+/// The length of an encoded [`SecretKey`].
+pub const SECRET_KEY_LENGTH: usize = 32;
+/// The length of an encoded [`PublicKey`].
+pub const PUBLIC_KEY_LENGTH: usize = 96;
+/// The length of an encoded [`Signature`].
+pub const SIGNATURE_LENGTH: usize = 48;
+
+// The domain separation tags of the ciphersuites from the IRTF draft on BLS signatures.
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_";
+const POSSESSION_DST: &[u8] = b"BLS_POP_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_";
+
+/// The reason why key material was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyError {
+    /// The bytes do not encode a point of the right group, or encode the identity.
+    InvalidPoint,
+    /// The bytes do not encode a nonzero scalar.
+    InvalidSecretKey,
+    /// The proof of possession of the public key of the member is invalid.
+    InvalidPossessionProof(NodeIndex),
+    /// The index of the owner of a [`Keychain`] is not smaller than the size of the committee.
+    IndexOutOfRange(NodeIndex),
+    /// The secret key of a [`Keychain`] does not correspond to the public key of its owner.
+    KeyMismatch(NodeIndex),
+}
+
+fn hash_to_g1(msg: &[u8], dst: &[u8]) -> G1Affine {
+    <G1Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(msg, dst).into()
+}
+
+// Checks that e(signature, g2) = e(hash(msg), public_key), with a single final exponentiation.
+fn pairing_check(msg: &[u8], dst: &[u8], signature: &G1Affine, public_key: &G2Affine) -> bool {
+    let hash = hash_to_g1(msg, dst);
+    let generator = G2Prepared::from(-G2Affine::generator());
+    let public_key = G2Prepared::from(*public_key);
+    multi_miller_loop(&[(signature, &generator), (&hash, &public_key)]).final_exponentiation()
+        == Gt::default()
+}
+
+// Checks that e(sum of r_i * signature_i, g2) = product of e(r_i * hash(msg_i), public_key_i) for
+// random coefficients r_i, with a single final exponentiation. It holds if all the signatures are
+// valid, and otherwise fails with overwhelming probability.
+fn batch_pairing_check(items: &[(&[u8], &G1Affine, &G2Affine)], dst: &[u8]) -> bool {
+    let mut rng = rand::thread_rng();
+    let coefficients: Vec<_> = items.iter().map(|_| Scalar::from(rng.next_u64())).collect();
+    let signature: G1Projective = items
+        .iter()
+        .zip(coefficients.iter())
+        .map(|((_, signature, _), coefficient)| G1Projective::from(*signature) * coefficient)
+        .sum();
+    let signature = G1Affine::from(signature);
+    let hashes: Vec<_> = items
+        .iter()
+        .zip(coefficients.iter())
+        .map(|((msg, _, _), coefficient)| {
+            G1Affine::from(G1Projective::from(hash_to_g1(msg, dst)) * coefficient)
+        })
+        .collect();
+    let public_keys: Vec<_> = items
+        .iter()
+        .map(|(_, _, public_key)| G2Prepared::from(**public_key))
+        .collect();
+    let generator = G2Prepared::from(-G2Affine::generator());
+    let mut terms: Vec<_> = hashes.iter().zip(public_keys.iter()).collect();
+    terms.push((&signature, &generator));
+    multi_miller_loop(&terms).final_exponentiation() == Gt::default()
+}
+
+/// A BLS signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature(G1Affine);
+
+impl Signature {
+    pub fn from_bytes(bytes: &[u8; SIGNATURE_LENGTH]) -> Result<Self, KeyError> {
+        Option::from(G1Affine::from_compressed(bytes))
+            .map(Signature)
+            .ok_or(KeyError::InvalidPoint)
+    }
+
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_LENGTH] {
+        self.0.to_compressed()
+    }
+}
+
+impl Encode for Signature {
+    fn size_hint(&self) -> usize {
+        SIGNATURE_LENGTH
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.to_bytes());
+    }
+}
+
+impl Decode for Signature {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let bytes = <[u8; SIGNATURE_LENGTH]>::decode(input)?;
+        Signature::from_bytes(&bytes).map_err(|_| "invalid BLS signature".into())
+    }
+}
+
+/// A BLS public key of a member.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey(G2Affine);
+
+impl PublicKey {
+    pub fn from_bytes(bytes: &[u8; PUBLIC_KEY_LENGTH]) -> Result<Self, KeyError> {
+        match Option::<G2Affine>::from(G2Affine::from_compressed(bytes)) {
+            Some(point) if !bool::from(point.is_identity()) => Ok(PublicKey(point)),
+            _ => Err(KeyError::InvalidPoint),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_LENGTH] {
+        self.0.to_compressed()
+    }
+
+    /// Checks the proof that the owner of this public key knows the corresponding secret key.
+    pub fn verify_possession(&self, proof: &Signature) -> bool {
+        pairing_check(&self.to_bytes(), POSSESSION_DST, &proof.0, &self.0)
+    }
+}
+
+impl Encode for PublicKey {
+    fn size_hint(&self) -> usize {
+        PUBLIC_KEY_LENGTH
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.to_bytes());
+    }
+}
+
+impl Decode for PublicKey {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let bytes = <[u8; PUBLIC_KEY_LENGTH]>::decode(input)?;
+        PublicKey::from_bytes(&bytes).map_err(|_| "invalid BLS public key".into())
+    }
+}
+
+/// A BLS secret key of a member. It is never printed.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretKey(Scalar);
+
+impl SecretKey {
+    /// Generates a new secret key using a cryptographically secure `rng`.
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        loop {
+            let mut bytes = [0; 64];
+            rng.fill_bytes(&mut bytes);
+            let scalar = Scalar::from_bytes_wide(&bytes);
+            if scalar != Scalar::zero() {
+                return SecretKey(scalar);
+            }
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8; SECRET_KEY_LENGTH]) -> Result<Self, KeyError> {
+        match Option::<Scalar>::from(Scalar::from_bytes(bytes)) {
+            Some(scalar) if scalar != Scalar::zero() => Ok(SecretKey(scalar)),
+            _ => Err(KeyError::InvalidSecretKey),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; SECRET_KEY_LENGTH] {
+        self.0.to_bytes()
+    }
+
+    /// The public key corresponding to this secret key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey((G2Projective::generator() * self.0).into())
+    }
+
+    /// Proves that the owner of the public key knows this secret key, see
+    /// [`PublicKey::verify_possession`].
+    pub fn prove_possession(&self) -> Signature {
+        self.sign(&self.public_key().to_bytes(), POSSESSION_DST)
+    }
+
+    fn sign(&self, msg: &[u8], dst: &[u8]) -> Signature {
+        Signature((G1Projective::from(hash_to_g1(msg, dst)) * self.0).into())
+    }
+}
+
+impl Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SecretKey")
+            .field(&self.public_key())
+            .finish()
+    }
+}
+
+impl Encode for SecretKey {
+    fn size_hint(&self) -> usize {
+        SECRET_KEY_LENGTH
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.to_bytes());
+    }
+}
+
+impl Decode for SecretKey {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let bytes = <[u8; SECRET_KEY_LENGTH]>::decode(input)?;
+        SecretKey::from_bytes(&bytes).map_err(|_| "invalid BLS secret key".into())
+    }
+}
+
+/// The public keys of the whole committee, by the index of their owners, together with the proofs
+/// of their possession.
+#[derive(Clone, Debug, PartialEq, Eq, Encode)]
+pub struct PublicKeys(Vec<(PublicKey, Signature)>);
+
+impl PublicKeys {
+    /// The public keys of a committee, the key of the member with index `i` at position `i`,
+    /// each with the proof of its possession made by its owner. Fails if any proof is invalid.
+    pub fn new(keys: Vec<(PublicKey, Signature)>) -> Result<Self, KeyError> {
+        for (index, (public_key, proof)) in keys.iter().enumerate() {
+            if !public_key.verify_possession(proof) {
+                return Err(KeyError::InvalidPossessionProof(NodeIndex(index)));
+            }
+        }
+        Ok(PublicKeys(keys))
+    }
+
+    /// The public key of the member, `None` if there is no member with this index.
+    pub fn get(&self, index: NodeIndex) -> Option<&PublicKey> {
+        self.0.get(index.0).map(|(public_key, _)| public_key)
+    }
+
+    fn quorum(&self) -> usize {
+        2 * self.node_count().0 / 3 + 1
+    }
+}
+
+impl Verifier for PublicKeys {
+    type Signature = Signature;
+
+    fn node_count(&self) -> NodeCount {
+        NodeCount(self.0.len())
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Signature, index: NodeIndex) -> bool {
+        match self.get(index) {
+            Some(public_key) => pairing_check(msg, SIGNATURE_DST, &sgn.0, &public_key.0),
+            None => false,
+        }
+    }
+
+    fn verify_batch(&self, batch: &[(&[u8], &Signature, NodeIndex)]) -> Vec<bool> {
+        let items: Option<Vec<_>> = batch
+            .iter()
+            .map(|(msg, sgn, index)| Some((*msg, &sgn.0, &self.get(*index)?.0)))
+            .collect();
+        match items {
+            Some(items) if items.len() > 1 && batch_pairing_check(&items, SIGNATURE_DST) => {
+                vec![true; batch.len()]
+            }
+            // Some signature is invalid, so we have to find out which ones.
+            _ => batch
+                .iter()
+                .map(|(msg, sgn, index)| self.verify(msg, sgn, *index))
+                .collect(),
+        }
+    }
+}
+
+impl MultiVerifier for PublicKeys {
+    type PartialMultisignature = Multisignature;
+
+    fn is_complete(&self, msg: &[u8], partial: &Multisignature) -> bool {
+        if partial.signers.capacity() != self.node_count().0
+            || partial.signers().count() < self.quorum()
+        {
+            return false;
+        }
+        let public_key: G2Projective = partial
+            .signers()
+            .filter_map(|index| self.get(index))
+            .map(|public_key| G2Projective::from(public_key.0))
+            .sum();
+        pairing_check(msg, SIGNATURE_DST, &partial.signature, &public_key.into())
+    }
+}
+
+impl Decode for PublicKeys {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let keys = Vec::decode(input)?;
+        PublicKeys::new(keys).map_err(|_| "invalid proof of possession of a BLS public key".into())
+    }
+}
+
+/// Generates the secret keys of a committee of `node_count` members, together with their public
+/// keys. Useful for tests and local deployments, in which a single party sets up the committee.
+pub fn generate_keys<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    node_count: NodeCount,
+) -> (Vec<SecretKey>, PublicKeys) {
+    let secret_keys: Vec<_> = (0..node_count.0)
+        .map(|_| SecretKey::generate(rng))
+        .collect();
+    let public_keys = PublicKeys(
+        secret_keys
+            .iter()
+            .map(|secret_key| (secret_key.public_key(), secret_key.prove_possession()))
+            .collect(),
+    );
+    (secret_keys, public_keys)
+}
+
+/// The sum of the signatures of a set of members, together with the bitmap of the set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Multisignature {
+    signature: G1Affine,
+    signers: BoolNodeMap,
+}
+
+impl Multisignature {
+    /// The members whose signatures were added.
+    pub fn signers(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.signers.true_indices()
+    }
+}
+
+impl Encode for Multisignature {
+    fn size_hint(&self) -> usize {
+        SIGNATURE_LENGTH + self.signers.size_hint()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        Signature(self.signature).encode_to(dest);
+        self.signers.encode_to(dest);
+    }
+}
+
+impl Decode for Multisignature {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let Signature(signature) = Signature::decode(input)?;
+        let signers = BoolNodeMap::decode(input)?;
+        Ok(Multisignature { signature, signers })
+    }
+}
+
+impl DecodeBounded for Multisignature {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let Signature(signature) = Signature::decode(input)?;
+        let signers = BoolNodeMap::decode_bounded(input, limits)?;
+        Ok(Multisignature { signature, signers })
+    }
+}
+
+impl PartialMultisignature for Multisignature {
+    type Signature = Signature;
+
+    fn add_signature(mut self, signature: &Self::Signature, index: NodeIndex) -> Self {
+        if index.0 >= self.signers.capacity() || self.signers[index] {
+            return self;
+        }
+        self.signature = (G1Projective::from(self.signature) + signature.0).into();
+        self.signers.set(index);
+        self
+    }
+
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        <Self as DecodeBounded>::decode_bounded(input, limits)
+    }
+}
+
+/// A [`MultiKeychain`](crate::MultiKeychain) holding the secret key of one member and the public
+/// keys of the whole committee, which aggregates signatures into a [`Multisignature`].
+#[derive(Clone, Debug)]
+pub struct Keychain {
+    index: NodeIndex,
+    secret_key: SecretKey,
+    public_keys: PublicKeys,
+}
+
+impl Keychain {
+    /// Creates the keychain of the member with `index`, failing if `secret_key` does not belong
+    /// to the member according to `public_keys`.
+    pub fn new(
+        index: NodeIndex,
+        secret_key: SecretKey,
+        public_keys: PublicKeys,
+    ) -> Result<Self, KeyError> {
+        match public_keys.get(index) {
+            None => Err(KeyError::IndexOutOfRange(index)),
+            Some(public_key) if *public_key != secret_key.public_key() => {
+                Err(KeyError::KeyMismatch(index))
+            }
+            Some(_) => Ok(Keychain {
+                index,
+                secret_key,
+                public_keys,
+            }),
+        }
+    }
+
+    pub fn public_keys(&self) -> &PublicKeys {
+        &self.public_keys
+    }
+}
+
+impl Index for Keychain {
+    fn index(&self) -> NodeIndex {
+        self.index
+    }
+}
+
+impl Verifier for Keychain {
+    type Signature = Signature;
+
+    fn node_count(&self) -> NodeCount {
+        self.public_keys.node_count()
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Signature, index: NodeIndex) -> bool {
+        self.public_keys.verify(msg, sgn, index)
+    }
+
+    fn verify_batch(&self, batch: &[(&[u8], &Signature, NodeIndex)]) -> Vec<bool> {
+        self.public_keys.verify_batch(batch)
+    }
+}
+
+#[async_trait]
+impl KeyBox for Keychain {
+    async fn sign(&self, msg: &[u8]) -> Signature {
+        self.secret_key.sign(msg, SIGNATURE_DST)
+    }
+}
+
+impl MultiVerifier for Keychain {
+    type PartialMultisignature = Multisignature;
+
+    fn is_complete(&self, msg: &[u8], partial: &Multisignature) -> bool {
+        self.public_keys.is_complete(msg, partial)
+    }
+}
+
+impl MultiKeychain for Keychain {
+    fn from_signature(&self, signature: &Signature, index: NodeIndex) -> Multisignature {
+        let multisignature = Multisignature {
+            signature: G1Affine::identity(),
+            signers: BoolNodeMap::with_capacity(self.node_count()),
+        };
+        multisignature.add_signature(signature, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        generate_keys, KeyError, Keychain, Multisignature, PublicKey, PublicKeys, SecretKey,
+        Signature, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH,
+    };
+    use crate::{
+        DecodingLimits, KeyBox, MultiKeychain, MultiVerifier, NodeCount, NodeIndex,
+        PartialMultisignature, Verifier,
+    };
+    use codec::{Decode, Encode};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn committee(node_count: usize) -> Vec<Keychain> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (secret_keys, public_keys) = generate_keys(&mut rng, NodeCount(node_count));
+        secret_keys
+            .into_iter()
+            .enumerate()
+            .map(|(index, secret_key)| {
+                Keychain::new(NodeIndex(index), secret_key, public_keys.clone())
+                    .expect("the keys belong to the member")
+            })
+            .collect()
+    }
+
+    async fn multisign(keychains: &[Keychain], signers: &[usize], msg: &[u8]) -> Multisignature {
+        let first = &keychains[signers[0]];
+        let mut multisignature = first.from_signature(&first.sign(msg).await, first.index);
+        for signer in &signers[1..] {
+            let signature = keychains[*signer].sign(msg).await;
+            multisignature = multisignature.add_signature(&signature, NodeIndex(*signer));
+        }
+        multisignature
+    }
+
+    #[tokio::test]
+    async fn multisignature_has_constant_size() {
+        let keychains = committee(10);
+        let small = multisign(&keychains, &[0, 1, 2, 3, 4, 5, 6], b"Hello").await;
+        let large = multisign(&keychains, &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9], b"Hello").await;
+        assert_eq!(small.encode().len(), large.encode().len());
+        assert!(small.encode().len() < 2 * 48);
+    }
+
+    #[tokio::test]
+    async fn ignores_repeated_signatures() {
+        let keychains = committee(4);
+        let once = multisign(&keychains, &[0, 1, 2], b"Hello").await;
+        let twice = multisign(&keychains, &[0, 1, 1, 2], b"Hello").await;
+        assert_eq!(once, twice);
+        assert!(keychains[3].is_complete(b"Hello", &twice));
+    }
+
+    #[tokio::test]
+    async fn rejects_multisignature_with_wrong_signers() {
+        let keychains = committee(4);
+        let multisignature = multisign(&keychains, &[0, 1, 2], b"Hello").await;
+        // The signature of the last member, attributed to the first one.
+        let signature = keychains[3].sign(b"Hello").await;
+        let forged = multisign(&keychains, &[1, 2], b"Hello")
+            .await
+            .add_signature(&signature, NodeIndex(0));
+        assert!(keychains[0].is_complete(b"Hello", &multisignature));
+        assert!(!keychains[0].is_complete(b"Hello", &forged));
+        assert!(!keychains[0].is_complete(b"Goodbye", &multisignature));
+    }
+
+    #[tokio::test]
+    async fn rejects_multisignature_for_other_committee() {
+        let keychains = committee(4);
+        let multisignature = multisign(&keychains, &[0, 1, 2], b"Hello").await;
+        let larger = committee(5);
+        assert!(!larger[0].is_complete(b"Hello", &multisignature));
+    }
+
+    #[tokio::test]
+    async fn verifies_batches_of_signatures() {
+        let keychains = committee(4);
+        let mut signatures = Vec::new();
+        for keychain in keychains.iter() {
+            signatures.push(keychain.sign(&[keychain.index.0 as u8]).await);
+        }
+        let messages: Vec<_> = (0..4u8).map(|i| vec![i]).collect();
+        let batch: Vec<_> = (0..4)
+            .map(|i| (&messages[i][..], &signatures[i], NodeIndex(i)))
+            .collect();
+        assert_eq!(keychains[0].verify_batch(&batch), vec![true; 4]);
+        // The signature of the last member, attributed to the first one, and one of an unknown
+        // member.
+        let forged = vec![
+            (&messages[0][..], &signatures[3], NodeIndex(0)),
+            batch[1],
+            batch[2],
+            (&messages[3][..], &signatures[3], NodeIndex(4)),
+        ];
+        assert_eq!(
+            keychains[0].verify_batch(&forged),
+            vec![false, true, true, false]
+        );
+    }
+
+    #[tokio::test]
+    async fn restores_multisignature_from_encoding() {
+        let keychains = committee(4);
+        let multisignature = multisign(&keychains, &[0, 2, 3], b"Hello").await;
+        let decoded = Multisignature::decode(&mut &multisignature.encode()[..]).unwrap();
+        assert_eq!(decoded, multisignature);
+        assert_eq!(
+            decoded.signers().collect::<Vec<_>>(),
+            vec![NodeIndex(0), NodeIndex(2), NodeIndex(3)]
+        );
+        assert!(keychains[1].is_complete(b"Hello", &decoded));
+    }
+
+    #[tokio::test]
+    async fn rejects_multisignature_larger_than_committee() {
+        let keychains = committee(4);
+        let encoded = multisign(&keychains, &[0, 2, 3], b"Hello").await.encode();
+        let limits = |n_members: usize| DecodingLimits {
+            n_members: NodeCount(n_members),
+            max_round: 0,
+            max_data_size: 0,
+        };
+        let decoded = <Multisignature as PartialMultisignature>::decode_bounded(
+            &mut &encoded[..],
+            &limits(4),
+        );
+        assert!(decoded.is_ok());
+        let decoded = <Multisignature as PartialMultisignature>::decode_bounded(
+            &mut &encoded[..],
+            &limits(3),
+        );
+        assert!(decoded.is_err());
+    }
+
+    #[tokio::test]
+    async fn restores_keys_from_encoding() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (secret_keys, public_keys) = generate_keys(&mut rng, NodeCount(3));
+        let secret_key = SecretKey::decode(&mut &secret_keys[1].encode()[..]).unwrap();
+        assert_eq!(secret_key, secret_keys[1]);
+        let decoded_keys = PublicKeys::decode(&mut &public_keys.encode()[..]).unwrap();
+        assert_eq!(decoded_keys, public_keys);
+        let keychain = Keychain::new(NodeIndex(1), secret_key, decoded_keys).unwrap();
+        let signature = keychain.sign(b"Hello").await;
+        let decoded = Signature::decode(&mut &signature.encode()[..]).unwrap();
+        assert!(keychain.verify(b"Hello", &decoded, NodeIndex(1)));
+        assert!(!keychain.verify(b"Hello", &decoded, NodeIndex(0)));
+    }
+
+    #[test]
+    fn rejects_keys_without_possession_proof() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (secret_keys, _) = generate_keys(&mut rng, NodeCount(2));
+        let keys = vec![
+            (
+                secret_keys[0].public_key(),
+                secret_keys[0].prove_possession(),
+            ),
+            (
+                secret_keys[1].public_key(),
+                secret_keys[0].prove_possession(),
+            ),
+        ];
+        assert_eq!(
+            PublicKeys::new(keys),
+            Err(KeyError::InvalidPossessionProof(NodeIndex(1)))
+        );
+    }
+
+    #[test]
+    fn rejects_key_of_other_member() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (mut secret_keys, public_keys) = generate_keys(&mut rng, NodeCount(3));
+        let secret_key = secret_keys.remove(1);
+        assert_eq!(
+            Keychain::new(NodeIndex(0), secret_key.clone(), public_keys.clone()).unwrap_err(),
+            KeyError::KeyMismatch(NodeIndex(0))
+        );
+        assert_eq!(
+            Keychain::new(NodeIndex(3), secret_key, public_keys).unwrap_err(),
+            KeyError::IndexOutOfRange(NodeIndex(3))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_key_material() {
+        let mut identity = [0; PUBLIC_KEY_LENGTH];
+        // The compressed encoding of the point at infinity.
+        identity[0] = 0xc0;
+        assert_eq!(
+            PublicKey::from_bytes(&identity),
+            Err(KeyError::InvalidPoint)
+        );
+        assert_eq!(
+            SecretKey::from_bytes(&[0; SECRET_KEY_LENGTH]),
+            Err(KeyError::InvalidSecretKey)
+        );
+        assert_eq!(
+            SecretKey::from_bytes(&[0xff; SECRET_KEY_LENGTH]),
+            Err(KeyError::InvalidSecretKey)
+        );
+    }
+}
@@ -1,21 +1,61 @@
-use crate::{Round, SessionId};
+use crate::{Clock, Round, SessionId, SystemClock};
 use std::{sync::Arc, time::Duration};
 
 use crate::nodes::{NodeCount, NodeIndex};
 
 pub type DelaySchedule = Arc<dyn Fn(usize) -> Duration + Sync + Send + 'static>;
+pub type RecipientCountSchedule = Arc<dyn Fn(usize) -> usize + Sync + Send + 'static>;
 
 /// Configuration of several parameters related to delaying various tasks.
 #[derive(Clone)]
 pub struct DelayConfig {
     /// Tick frequency of the Member. Govers internal task queue of the Member.
     pub tick_interval: Duration,
-    /// After what delay, we repeat a request for a coord or parents.
-    pub requests_interval: Duration,
     /// DelaySchedule(k) represents the delay between the kth and (k+1)th broadcast.
     pub unit_broadcast_delay: DelaySchedule,
+    /// DelaySchedule(k) represents the delay between the kth and (k+1)th request for a coord.
+    /// A random jitter of up to 25% is applied to it.
+    pub coord_request_delay: DelaySchedule,
+    /// RecipientCountSchedule(k) represents the number of peers the kth request for a coord is sent to.
+    pub coord_request_recipients: RecipientCountSchedule,
+    /// DelaySchedule(k) represents the delay between the kth and (k+1)th request for parents.
+    /// A random jitter of up to 25% is applied to it.
+    pub parent_request_delay: DelaySchedule,
+    /// RecipientCountSchedule(k) represents the number of peers the kth request for parents is sent to.
+    pub parent_request_recipients: RecipientCountSchedule,
+    /// DelaySchedule(k) represents the delay between the kth and (k+1)th request for our newest unit.
+    pub newest_request_delay: DelaySchedule,
+    /// The number of attempts after which a request for a coord or parents is abandoned, it is
+    /// requested again only if the unit turns out to be missing again. `None` means requests are
+    /// repeated until they are resolved. Abandoned requests are reported to the application if it
+    /// passes a sender for them, see
+    /// [`SessionExtras::with_abandoned_requests`](crate::SessionExtras::with_abandoned_requests).
+    pub request_attempts_limit: Option<usize>,
+    /// The time for which requests and rebroadcasts are postponed after the network reports that
+    /// its queue is full, see [`SendError::QueueFull`](crate::SendError::QueueFull).
+    pub network_congestion_delay: Duration,
+    /// The number of rounds by which our unit has to be older than our newest one to no longer
+    /// be rebroadcast, the peers still missing it request it when they see its children. `None`
+    /// means units are rebroadcast until all the peers are known to have them.
+    pub unit_rebroadcast_horizon: Option<Round>,
     /// DelaySchedule(k) represents the delay between creating the (k-1)th and kth unit.
     pub unit_creation_delay: DelaySchedule,
+    /// The time for which received units are collected before their signatures are verified
+    /// together, see [`Verifier::verify_batch`](crate::Verifier::verify_batch). Zero, the
+    /// default, means that every unit is verified as soon as it arrives, which is best unless
+    /// the verifier is faster at verifying signatures in batches, e.g. with ed25519.
+    pub unit_verification_window: Duration,
+}
+
+/// The way newly created units are spread among the committee.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dissemination {
+    /// Every node sends its units directly to all the other nodes.
+    Broadcast,
+    /// Every node sends its units to `fanout` random peers, and every node relays each unit
+    /// it receives and validates for the first time to `fanout` random peers. Missing units are
+    /// still requested directly from their creators.
+    Gossip { fanout: usize },
 }
 
 /// Main configuration of the consensus. We refer to the documentation https://cardinal-cryptography.github.io/AlephBFT/aleph_bft_api.html
@@ -32,6 +72,15 @@ pub struct Config {
     pub delay_config: DelayConfig,
     /// Maximum allowable round of a unit.
     pub max_round: Round,
+    /// How newly created units are spread among the committee.
+    pub dissemination: Dissemination,
+    /// The number of tasks verifying signatures of received units, at least one is always run.
+    /// The workers are started with [`SpawnHandle::spawn`](crate::SpawnHandle::spawn) and do
+    /// CPU-bound work, so the [`SpawnHandle`](crate::SpawnHandle) should run them on a
+    /// multi-threaded or blocking pool, otherwise they stall the other tasks of the executor.
+    pub verification_workers: usize,
+    /// The source of time for all the delays, [`SystemClock`] by default.
+    pub clock: Arc<dyn Clock>,
 }
 
 pub fn exponential_slowdown(
@@ -54,6 +103,11 @@ pub fn exponential_slowdown(
     Duration::from_millis(delay)
 }
 
+fn request_delay(t: usize) -> Duration {
+    // 3000, 3000, 3000, 4500, 6750, ..., 30000, 30000, ...
+    exponential_slowdown(t, 3000.0, 2, 1.5).min(Duration::from_millis(30000))
+}
+
 /// A default configuration of what the creators of this package see as optimal parameters.
 pub fn default_config(n_members: NodeCount, node_ix: NodeIndex, session_id: SessionId) -> Config {
     let unit_creation_delay = Arc::new(|t| {
@@ -65,11 +119,20 @@ pub fn default_config(n_members: NodeCount, node_ix: NodeIndex, session_id: Sess
     });
     let delay_config = DelayConfig {
         tick_interval: Duration::from_millis(100),
-        requests_interval: Duration::from_millis(3000),
         unit_broadcast_delay: Arc::new(|t| exponential_slowdown(t, 4000.0, 0, 2.0)),
         // 4000, 8000, 16000, 32000, ...
+        coord_request_delay: Arc::new(request_delay),
+        coord_request_recipients: Arc::new(|t| 1 << t.min(5)),
+        // 1, 2, 4, 8, 16, 32, 32, ...
+        parent_request_delay: Arc::new(request_delay),
+        parent_request_recipients: Arc::new(|t| 1 << t.min(5)),
+        newest_request_delay: Arc::new(|t| exponential_slowdown(t, 4000.0, 0, 2.0)),
+        request_attempts_limit: None,
+        network_congestion_delay: Duration::from_millis(1000),
+        unit_rebroadcast_horizon: None,
         unit_creation_delay,
         // 5000, 500, 500, 500, ... (till step 3000), 500, 500*1.005, 500*(1.005)^2, 500*(1.005)^3, ..., 10742207 (last step)
+        unit_verification_window: Duration::from_secs(0),
     };
     Config {
         node_ix,
@@ -77,5 +140,8 @@ pub fn default_config(n_members: NodeCount, node_ix: NodeIndex, session_id: Sess
         n_members,
         delay_config,
         max_round: 5000,
+        dissemination: Dissemination::Broadcast,
+        verification_workers: 2,
+        clock: Arc::new(SystemClock),
     }
 }
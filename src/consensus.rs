@@ -4,22 +4,26 @@ use futures::{
     FutureExt,
 };
 use log::{debug, error, info};
+use std::sync::Arc;
 
 use crate::{
     config::Config,
     creator::Creator,
+    environment::Clock,
     extender::Extender,
     runway::{NotificationIn, NotificationOut},
     terminal::Terminal,
     Hasher, OrderedBatch, Receiver, Round, Sender, SpawnHandle,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn run<H: Hasher + 'static>(
     conf: Config,
     incoming_notifications: Receiver<NotificationIn<H>>,
     outgoing_notifications: Sender<NotificationOut<H>>,
     ordered_batch_tx: Sender<OrderedBatch<H::Hash>>,
     spawn_handle: impl SpawnHandle,
+    clock: Arc<dyn Clock>,
     starting_round: oneshot::Receiver<Round>,
     mut exit: oneshot::Receiver<()>,
 ) {
@@ -39,12 +43,21 @@ pub(crate) async fn run<H: Hasher + 'static>(
 
     let (parents_tx, parents_rx) = mpsc::unbounded();
     let new_units_tx = outgoing_notifications.clone();
-    let mut creator = Creator::new(conf.clone(), parents_rx, new_units_tx);
+    let mut creator = Creator::new(conf.clone(), parents_rx, new_units_tx, clock);
 
     let (creator_exit, exit_rx) = oneshot::channel();
     let mut creator_handle = spawn_handle
         .spawn_essential("consensus/creator", async move {
-            match starting_round.await {
+            let mut starting_round = starting_round;
+            let mut exit_rx = exit_rx;
+            let starting_round = futures::select_biased! {
+                starting_round = starting_round => starting_round,
+                _ = exit_rx => {
+                    debug!(target: "AlephBFT-creator", "{:?} Exiting before the starting round was provided.", index);
+                    return;
+                },
+            };
+            match starting_round {
                 Ok(round) => creator.create(round, exit_rx).await,
                 Err(e) => {
                     error!(target: "AlephBFT-creator", "Starting round not provided: {}", e);
@@ -77,7 +90,7 @@ pub(crate) async fn run<H: Hasher + 'static>(
         .fuse();
     info!(target: "AlephBFT", "{:?} All services started.", index);
 
-    futures::select! {
+    futures::select_biased! {
         _ = exit => {},
         _ = terminal_handle => {
             debug!(target: "AlephBFT-consensus", "{:?} terminal task terminated early.", index);
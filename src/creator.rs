@@ -1,14 +1,14 @@
 use crate::{
     config::{Config, DelaySchedule},
+    environment::Clock,
     nodes::{NodeCount, NodeIndex, NodeMap},
     runway::NotificationOut,
     units::{ControlHash, PreUnit, Unit},
     Hasher, Receiver, Round, Sender,
 };
 use futures::{channel::oneshot, FutureExt, StreamExt};
-use futures_timer::Delay;
 use log::{info, trace, warn};
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 /// A process responsible for creating new units. It receives all the units added locally to the Dag
 /// via the parents_rx channel endpoint. It creates units according to an internal strategy respecting
@@ -32,6 +32,7 @@ pub(crate) struct Creator<H: Hasher> {
     n_candidates_by_round: Vec<NodeCount>, // len of this - 1 is the highest round number of all known units
     create_lag: DelaySchedule,
     max_round: Round,
+    clock: Arc<dyn Clock>,
     exiting: bool,
 }
 
@@ -45,6 +46,7 @@ impl<H: Hasher> Creator<H> {
         conf: Config,
         parents_rx: Receiver<Unit<H>>,
         new_units_tx: Sender<NotificationOut<H>>,
+        clock: Arc<dyn Clock>,
     ) -> Self {
         let n_members = conf.n_members;
         Creator {
@@ -56,6 +58,7 @@ impl<H: Hasher> Creator<H> {
             n_candidates_by_round: vec![NodeCount(0)],
             create_lag: conf.delay_config.unit_creation_delay,
             max_round: conf.max_round,
+            clock,
             exiting: false,
         }
     }
@@ -106,7 +109,7 @@ impl<H: Hasher> Creator<H> {
     }
 
     async fn wait_until_ready(&mut self, round: Round) -> Result<(), CreatorError> {
-        let mut delay = Delay::new((self.create_lag)(round.into())).fuse();
+        let mut delay = self.clock.sleep((self.create_lag)(round.into())).fuse();
         loop {
             // We need to require a number higher by one then currently highest round
             // (by 2 then length) to prevent attack when a malicious node is creating
@@ -117,7 +120,7 @@ impl<H: Hasher> Creator<H> {
                 break;
             }
 
-            futures::select! {
+            futures::select_biased! {
                 unit = self.parents_rx.next() => {
                     if let Some(u) = unit {
                         self.add_unit(u.round(), u.creator(), u.hash());
@@ -158,9 +161,9 @@ impl<H: Hasher> Creator<H> {
     pub(crate) async fn create(&mut self, starting_round: Round, mut exit: oneshot::Receiver<()>) {
         log::debug!(target: "AlephBFT-creator", "Creator starting from round {}", starting_round);
         for round in starting_round..self.max_round {
-            let mut delay = Delay::new(Duration::from_secs(30 * 60)).fuse();
+            let mut delay = self.clock.sleep(Duration::from_secs(30 * 60)).fuse();
             loop {
-                futures::select! {
+                futures::select_biased! {
                     res = self.wait_until_ready(round).fuse() => {
                         if let Err(e) = res {
                             warn!(target: "AlephBFT-creator", "{:?} Impossible to create a unit, error {:?}, terminating Creator.", self.node_ix, e);
@@ -171,7 +174,7 @@ impl<H: Hasher> Creator<H> {
                     }
                     _ = &mut delay => {
                         warn!(target: "AlephBFT-creator", "{:?} more than half hour has passed since we created the previous unit.", self.node_ix);
-                        delay = Delay::new(Duration::from_secs(30 * 60)).fuse();
+                        delay = self.clock.sleep(Duration::from_secs(30 * 60)).fuse();
                     }
                     _ = &mut exit => {
                         info!(target: "AlephBFT-creator", "{:?} received exit signal.", self.node_ix);
@@ -193,6 +196,7 @@ impl<H: Hasher> Creator<H> {
 mod tests {
     use super::*;
     use crate::{
+        environment::SystemClock,
         testing::mock::{gen_config, Data, Hasher64},
         units::{FullUnit, UnitCoord},
     };
@@ -286,6 +290,7 @@ mod tests {
                 gen_config(node_ix.into(), (n_members + n_fallen_members).into()),
                 from_test_controller,
                 to_test_controller.clone(),
+                Arc::new(SystemClock),
             );
 
             test_controller.units_out.push(units_out);
@@ -376,6 +381,7 @@ mod tests {
                 gen_config(node_ix.into(), n_members.into()),
                 from_test_controller,
                 to_test_controller.clone(),
+                Arc::new(SystemClock),
             );
             creator.n_candidates_by_round = test_controller.n_candidates_by_round.clone();
             creator.candidates_by_round = test_controller.candidates_by_round.clone();
@@ -434,6 +440,7 @@ mod tests {
                 gen_config(node_ix.into(), (n_members + n_fallen_members).into()),
                 from_test_controller,
                 to_test_controller.clone(),
+                Arc::new(SystemClock),
             );
             creator.n_candidates_by_round = test_controller.n_candidates_by_round.clone();
             creator.candidates_by_round = test_controller.candidates_by_round.clone();
@@ -0,0 +1,544 @@
+//! A [`KeyBox`](crate::KeyBox) signing with ed25519, available with the `ed25519` feature.
+//!
+//! Every member of the committee generates its [`SecretKey`] with [`SecretKey::generate`] and
+//! publishes the corresponding [`PublicKey`]. Once the [`PublicKeys`] of the whole committee are
+//! known, every member creates its [`KeyBox`] from its index, its secret key and the public keys.
+//! All the keys, as well as the [`PublicKeys`] of the committee, are encoded with `codec`, so they
+//! can be stored and distributed. The [`KeyBox`] can be wrapped in a
+//! [`DefaultMultiKeychain`](crate::DefaultMultiKeychain) to obtain a
+//! [`MultiKeychain`](crate::MultiKeychain). Components which only check signatures can use the
+//! [`PublicKeys`] alone, as a [`Verifier`](crate::Verifier).
+
+use crate::{Index, KeyBox as KeyBoxT, NodeCount, NodeIndex, Verifier};
+use async_trait::async_trait;
+use codec::{Decode, Encode, Error, Input, Output};
+use ed25519_dalek::ExpandedSecretKey;
+use rand::{CryptoRng, RngCore};
+use std::fmt::{self, Debug};
+
+/// The length of an encoded [`SecretKey`].
+pub const SECRET_KEY_LENGTH: usize = ed25519_dalek::SECRET_KEY_LENGTH;
+/// The length of an encoded [`PublicKey`].
+pub const PUBLIC_KEY_LENGTH: usize = ed25519_dalek::PUBLIC_KEY_LENGTH;
+/// The length of an encoded [`Signature`].
+pub const SIGNATURE_LENGTH: usize = ed25519_dalek::SIGNATURE_LENGTH;
+
+/// The reason why key material was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyError {
+    /// The bytes do not encode a point of the curve.
+    InvalidPublicKey,
+    /// The bytes cannot be a signature, as its scalar is not reduced.
+    InvalidSignature,
+    /// The index of the owner of a [`KeyBox`] is not smaller than the size of the committee.
+    IndexOutOfRange(NodeIndex),
+    /// The secret key of a [`KeyBox`] does not correspond to the public key of its owner.
+    KeyMismatch(NodeIndex),
+}
+
+/// An ed25519 signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature(ed25519_dalek::Signature);
+
+impl Signature {
+    pub fn from_bytes(bytes: &[u8; SIGNATURE_LENGTH]) -> Result<Self, KeyError> {
+        ed25519_dalek::Signature::from_bytes(bytes)
+            .map(Signature)
+            .map_err(|_| KeyError::InvalidSignature)
+    }
+
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_LENGTH] {
+        self.0.to_bytes()
+    }
+}
+
+impl Encode for Signature {
+    fn size_hint(&self) -> usize {
+        SIGNATURE_LENGTH
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.to_bytes());
+    }
+}
+
+impl Decode for Signature {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let bytes = <[u8; SIGNATURE_LENGTH]>::decode(input)?;
+        Signature::from_bytes(&bytes).map_err(|_| "invalid ed25519 signature".into())
+    }
+}
+
+/// An ed25519 public key of a member.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey(ed25519_dalek::PublicKey);
+
+impl PublicKey {
+    pub fn from_bytes(bytes: &[u8; PUBLIC_KEY_LENGTH]) -> Result<Self, KeyError> {
+        ed25519_dalek::PublicKey::from_bytes(bytes)
+            .map(PublicKey)
+            .map_err(|_| KeyError::InvalidPublicKey)
+    }
+
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_LENGTH] {
+        self.0.to_bytes()
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Signature) -> bool {
+        self.0.verify_strict(msg, &sgn.0).is_ok()
+    }
+}
+
+impl Encode for PublicKey {
+    fn size_hint(&self) -> usize {
+        PUBLIC_KEY_LENGTH
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(self.0.as_bytes());
+    }
+}
+
+impl Decode for PublicKey {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let bytes = <[u8; PUBLIC_KEY_LENGTH]>::decode(input)?;
+        PublicKey::from_bytes(&bytes).map_err(|_| "invalid ed25519 public key".into())
+    }
+}
+
+/// An ed25519 secret key of a member. It is erased from memory when dropped, and never printed.
+pub struct SecretKey(ed25519_dalek::SecretKey);
+
+impl SecretKey {
+    /// Generates a new secret key using a cryptographically secure `rng`.
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut bytes = [0; SECRET_KEY_LENGTH];
+        rng.fill_bytes(&mut bytes);
+        SecretKey::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8; SECRET_KEY_LENGTH]) -> Self {
+        // Every sequence of bytes of the right length is a secret key.
+        SecretKey(ed25519_dalek::SecretKey::from_bytes(bytes).expect("the length is correct"))
+    }
+
+    pub fn to_bytes(&self) -> [u8; SECRET_KEY_LENGTH] {
+        self.0.to_bytes()
+    }
+
+    /// The public key corresponding to this secret key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey((&self.0).into())
+    }
+
+    fn sign(&self, msg: &[u8], public_key: &PublicKey) -> Signature {
+        Signature(ExpandedSecretKey::from(&self.0).sign(msg, &public_key.0))
+    }
+}
+
+impl Clone for SecretKey {
+    fn clone(&self) -> Self {
+        SecretKey::from_bytes(self.0.as_bytes())
+    }
+}
+
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_bytes() == other.0.as_bytes()
+    }
+}
+
+impl Eq for SecretKey {}
+
+impl Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SecretKey")
+            .field(&self.public_key())
+            .finish()
+    }
+}
+
+impl Encode for SecretKey {
+    fn size_hint(&self) -> usize {
+        SECRET_KEY_LENGTH
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(self.0.as_bytes());
+    }
+}
+
+impl Decode for SecretKey {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let bytes = <[u8; SECRET_KEY_LENGTH]>::decode(input)?;
+        Ok(SecretKey::from_bytes(&bytes))
+    }
+}
+
+/// The public keys of the whole committee, by the index of their owners.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct PublicKeys(Vec<PublicKey>);
+
+impl PublicKeys {
+    /// The public keys of a committee, the key of the member with index `i` at position `i`.
+    pub fn new(keys: Vec<PublicKey>) -> Self {
+        PublicKeys(keys)
+    }
+
+    /// The public key of the member, `None` if there is no member with this index.
+    pub fn get(&self, index: NodeIndex) -> Option<&PublicKey> {
+        self.0.get(index.0)
+    }
+}
+
+impl Verifier for PublicKeys {
+    type Signature = Signature;
+
+    fn node_count(&self) -> NodeCount {
+        NodeCount(self.0.len())
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Signature, index: NodeIndex) -> bool {
+        match self.get(index) {
+            Some(public_key) => public_key.verify(msg, sgn),
+            None => false,
+        }
+    }
+
+    /// Verifies all the signatures together with ed25519 batch verification, and only if that
+    /// fails finds the wrong ones by verifying them one by one. Batch verification does not
+    /// reject the few degenerate signatures that are rejected by [`Verifier::verify`], none of
+    /// which can be produced by honest signers.
+    fn verify_batch(&self, batch: &[(&[u8], &Signature, NodeIndex)]) -> Vec<bool> {
+        let public_keys: Option<Vec<_>> = batch
+            .iter()
+            .map(|(_, _, index)| self.get(*index).map(|public_key| public_key.0))
+            .collect();
+        if let Some(public_keys) = public_keys.filter(|keys| !keys.is_empty()) {
+            let messages: Vec<_> = batch.iter().map(|(msg, _, _)| *msg).collect();
+            let signatures: Vec<_> = batch.iter().map(|(_, sgn, _)| sgn.0).collect();
+            if ed25519_dalek::verify_batch(&messages, &signatures, &public_keys).is_ok() {
+                return vec![true; batch.len()];
+            }
+        }
+        batch
+            .iter()
+            .map(|(msg, sgn, index)| self.verify(msg, sgn, *index))
+            .collect()
+    }
+}
+
+impl std::iter::FromIterator<PublicKey> for PublicKeys {
+    fn from_iter<I: IntoIterator<Item = PublicKey>>(iter: I) -> Self {
+        PublicKeys(iter.into_iter().collect())
+    }
+}
+
+/// Generates the secret keys of a committee of `node_count` members, together with their public
+/// keys. Useful for tests and local deployments, in which a single party sets up the committee.
+pub fn generate_keys<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    node_count: NodeCount,
+) -> (Vec<SecretKey>, PublicKeys) {
+    let secret_keys: Vec<_> = (0..node_count.0)
+        .map(|_| SecretKey::generate(rng))
+        .collect();
+    let public_keys = secret_keys.iter().map(SecretKey::public_key).collect();
+    (secret_keys, public_keys)
+}
+
+/// A [`KeyBox`](crate::KeyBox) holding the secret key of one member and the public keys of the
+/// whole committee.
+#[derive(Clone, Debug)]
+pub struct KeyBox {
+    index: NodeIndex,
+    secret_key: SecretKey,
+    public_keys: PublicKeys,
+}
+
+impl KeyBox {
+    /// Creates the key box of the member with `index`, failing if `secret_key` does not belong to
+    /// the member according to `public_keys`.
+    pub fn new(
+        index: NodeIndex,
+        secret_key: SecretKey,
+        public_keys: PublicKeys,
+    ) -> Result<Self, KeyError> {
+        match public_keys.get(index) {
+            None => Err(KeyError::IndexOutOfRange(index)),
+            Some(public_key) if *public_key != secret_key.public_key() => {
+                Err(KeyError::KeyMismatch(index))
+            }
+            Some(_) => Ok(KeyBox {
+                index,
+                secret_key,
+                public_keys,
+            }),
+        }
+    }
+
+    pub fn public_keys(&self) -> &PublicKeys {
+        &self.public_keys
+    }
+}
+
+impl Index for KeyBox {
+    fn index(&self) -> NodeIndex {
+        self.index
+    }
+}
+
+impl Verifier for KeyBox {
+    type Signature = Signature;
+
+    fn node_count(&self) -> NodeCount {
+        self.public_keys.node_count()
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Signature, index: NodeIndex) -> bool {
+        self.public_keys.verify(msg, sgn, index)
+    }
+
+    fn verify_batch(&self, batch: &[(&[u8], &Signature, NodeIndex)]) -> Vec<bool> {
+        self.public_keys.verify_batch(batch)
+    }
+}
+
+#[async_trait]
+impl KeyBoxT for KeyBox {
+    async fn sign(&self, msg: &[u8]) -> Signature {
+        self.secret_key.sign(msg, &self.public_keys.0[self.index.0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        generate_keys, KeyBox, KeyError, PublicKey, PublicKeys, SecretKey, Signature,
+        PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH,
+    };
+    use crate::{
+        DefaultMultiKeychain, Index, KeyBox as KeyBoxT, MultiKeychain, NodeCount, NodeIndex,
+        PartialMultisignature, PartiallyMultisigned, Signable, Signed, UncheckedSigned, Verifier,
+    };
+    use codec::{Decode, Encode};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+    struct TestMessage(Vec<u8>);
+
+    impl Signable for TestMessage {
+        type Hash = Vec<u8>;
+        fn hash(&self) -> Self::Hash {
+            self.0.clone()
+        }
+    }
+
+    fn hello() -> TestMessage {
+        TestMessage(b"Hello".to_vec())
+    }
+
+    fn committee(node_count: usize) -> Vec<DefaultMultiKeychain<KeyBox>> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (secret_keys, public_keys) = generate_keys(&mut rng, NodeCount(node_count));
+        secret_keys
+            .into_iter()
+            .enumerate()
+            .map(|(index, secret_key)| {
+                let key_box = KeyBox::new(NodeIndex(index), secret_key, public_keys.clone())
+                    .expect("the keys belong to the member");
+                DefaultMultiKeychain::new(key_box)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn accepts_signatures_of_every_member() {
+        let keychains = committee(4);
+        for signer in keychains.iter() {
+            let unchecked = Signed::sign_with_index(hello(), signer)
+                .await
+                .into_unchecked();
+            for verifier in keychains.iter() {
+                assert!(unchecked.clone().check(verifier).is_ok());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn checks_signatures_with_public_keys_alone() {
+        let keychains = committee(4);
+        let (_, public_keys) = generate_keys(&mut StdRng::seed_from_u64(0), NodeCount(4));
+        let verifier = DefaultMultiKeychain::new(public_keys);
+        let mut partial = PartiallyMultisigned::sign(hello(), &keychains[0]).await;
+        for keychain in keychains.iter().skip(1).take(2) {
+            let signed = Signed::sign_with_index(hello(), keychain).await;
+            assert!(signed.clone().into_unchecked().check(&verifier).is_ok());
+            partial = partial.add_signature(signed, keychain);
+        }
+        assert!(partial.into_unchecked().check_multi(&verifier).is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_signature_of_other_member() {
+        let keychains = committee(4);
+        let signature = keychains[1].sign(&hello().hash()).await;
+        let unchecked = UncheckedSigned::new_with_index(hello(), NodeIndex(0), signature);
+        assert!(unchecked.check(&keychains[2]).is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_signature_of_other_message() {
+        let keychains = committee(4);
+        let signature = keychains[0].sign(&hello().hash()).await;
+        let goodbye = TestMessage(b"Goodbye".to_vec());
+        let unchecked = UncheckedSigned::new_with_index(goodbye, NodeIndex(0), signature);
+        assert!(unchecked.check(&keychains[1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_signature_of_unknown_member() {
+        let keychains = committee(4);
+        let signature = keychains[0].sign(&hello().hash()).await;
+        let unchecked = UncheckedSigned::new_with_index(hello(), NodeIndex(4), signature);
+        assert!(unchecked.check(&keychains[0]).is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_corrupted_signature() {
+        let keychains = committee(4);
+        let signature = keychains[0].sign(&hello().hash()).await;
+        let mut bytes = signature.to_bytes();
+        bytes[0] ^= 1;
+        let corrupted = Signature::from_bytes(&bytes).unwrap();
+        let unchecked = UncheckedSigned::new_with_index(hello(), NodeIndex(0), corrupted);
+        assert!(unchecked.check(&keychains[1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn verifies_batches_of_signatures() {
+        let keychains = committee(4);
+        let goodbye = TestMessage(b"Goodbye".to_vec());
+        let mut batch = Vec::new();
+        for keychain in keychains.iter() {
+            batch.push(
+                Signed::sign_with_index(hello(), keychain)
+                    .await
+                    .into_unchecked(),
+            );
+        }
+        let results = UncheckedSigned::check_batch(batch.clone(), &keychains[0]);
+        assert!(results.iter().all(|result| result.is_ok()));
+
+        let signature = keychains[1].sign(&hello().hash()).await;
+        batch.push(UncheckedSigned::new_with_index(
+            goodbye,
+            NodeIndex(1),
+            signature,
+        ));
+        batch.push(UncheckedSigned::new_with_index(
+            hello(),
+            NodeIndex(4),
+            signature,
+        ));
+        let results = UncheckedSigned::check_batch(batch, &keychains[0]);
+        let correct: Vec<_> = results.iter().map(|result| result.is_ok()).collect();
+        assert_eq!(correct, vec![true, true, true, true, false, false]);
+    }
+
+    #[tokio::test]
+    async fn accepts_complete_multisignature() {
+        let keychains = committee(4);
+        let mut partial = PartiallyMultisigned::sign(hello(), &keychains[0]).await;
+        for keychain in keychains.iter().skip(1).take(2) {
+            let signed = Signed::sign_with_index(hello(), keychain).await;
+            partial = partial.add_signature(signed, keychain);
+        }
+        assert!(partial.is_complete());
+        let unchecked = partial.into_unchecked();
+        let decoded =
+            UncheckedSigned::<TestMessage, _>::decode(&mut &unchecked.encode()[..]).unwrap();
+        assert!(decoded.check_multi(&keychains[3]).is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_multisignature_without_quorum() {
+        let keychains = committee(4);
+        let mut multisignature =
+            keychains[0].from_signature(&keychains[0].sign(&hello().hash()).await, NodeIndex(0));
+        multisignature =
+            multisignature.add_signature(&keychains[1].sign(&hello().hash()).await, NodeIndex(1));
+        let unchecked = UncheckedSigned::new(hello(), multisignature);
+        assert!(unchecked.check_multi(&keychains[2]).is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_multisignature_with_forged_signature() {
+        let keychains = committee(4);
+        let mut multisignature =
+            keychains[0].from_signature(&keychains[0].sign(&hello().hash()).await, NodeIndex(0));
+        for keychain in keychains.iter().skip(1).take(2) {
+            multisignature = multisignature
+                .add_signature(&keychain.sign(&hello().hash()).await, keychain.index());
+        }
+        // The last member signs on behalf of another one, so there are enough signatures, but
+        // one of them is invalid.
+        let forged = keychains[3].sign(&hello().hash()).await;
+        let unchecked =
+            UncheckedSigned::new(hello(), multisignature.add_signature(&forged, NodeIndex(1)));
+        assert!(unchecked.check_multi(&keychains[0]).is_err());
+    }
+
+    #[test]
+    fn rejects_key_of_other_member() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (mut secret_keys, public_keys) = generate_keys(&mut rng, NodeCount(3));
+        let secret_key = secret_keys.remove(1);
+        assert_eq!(
+            KeyBox::new(NodeIndex(0), secret_key.clone(), public_keys.clone()).unwrap_err(),
+            KeyError::KeyMismatch(NodeIndex(0))
+        );
+        assert_eq!(
+            KeyBox::new(NodeIndex(3), secret_key, public_keys).unwrap_err(),
+            KeyError::IndexOutOfRange(NodeIndex(3))
+        );
+    }
+
+    #[tokio::test]
+    async fn restores_keys_from_encoding() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (secret_keys, public_keys) = generate_keys(&mut rng, NodeCount(3));
+        let secret_key = SecretKey::decode(&mut &secret_keys[2].encode()[..]).unwrap();
+        assert_eq!(secret_key, secret_keys[2]);
+        let decoded_keys = PublicKeys::decode(&mut &public_keys.encode()[..]).unwrap();
+        assert_eq!(decoded_keys, public_keys);
+        let key_box = KeyBox::new(NodeIndex(2), secret_key, decoded_keys).unwrap();
+        let signature = key_box.sign(b"Hello").await;
+        let encoded = signature.encode();
+        assert_eq!(encoded.len(), SIGNATURE_LENGTH);
+        let decoded = Signature::decode(&mut &encoded[..]).unwrap();
+        assert!(key_box.verify(b"Hello", &decoded, NodeIndex(2)));
+    }
+
+    #[test]
+    fn rejects_invalid_public_key() {
+        // Not the encoding of a curve point, as y^2 - 1 over d y^2 + 1 has no square root for y = 2.
+        let mut bytes = [0; PUBLIC_KEY_LENGTH];
+        bytes[0] = 2;
+        assert_eq!(
+            PublicKey::from_bytes(&bytes),
+            Err(KeyError::InvalidPublicKey)
+        );
+        assert!(PublicKey::decode(&mut &bytes[..]).is_err());
+    }
+
+    #[test]
+    fn rejects_unreduced_signature() {
+        let bytes = [0xff; SIGNATURE_LENGTH];
+        assert_eq!(
+            Signature::from_bytes(&bytes),
+            Err(KeyError::InvalidSignature)
+        );
+        assert!(Signature::decode(&mut &bytes[..]).is_err());
+    }
+}
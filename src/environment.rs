@@ -0,0 +1,67 @@
+use futures::Future;
+use futures_timer::Delay;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// A future completing after the requested time has passed on a [`Clock`].
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The source of time of all the timed components of the consensus, see [`crate::Config::clock`].
+///
+/// Implementations other than [`SystemClock`] let applications run the consensus under simulated
+/// or paused time, and let tests fast-forward through delays.
+pub trait Clock: Send + Sync + 'static {
+    /// The current time.
+    fn now(&self) -> Instant;
+    /// Completes once `duration` has passed since the call, according to [`Clock::now`].
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+/// The wall clock, using [`Instant::now`] and timers running in the background.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(Delay::new(duration))
+    }
+}
+
+/// The sources of time and randomness used by a member. Members running in the same
+/// environment behave the same way given the same inputs. This relies on the tasks of a member
+/// picking between simultaneously ready events in a fixed order (`select_biased!`), never at
+/// random.
+#[derive(Clone)]
+pub(crate) struct Environment {
+    clock: Arc<dyn Clock>,
+    seed: u64,
+}
+
+impl Environment {
+    pub(crate) fn new(clock: Arc<dyn Clock>, seed: u64) -> Self {
+        Environment { clock, seed }
+    }
+
+    pub(crate) fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// A random number generator for the given component, seeded with the seed of the
+    /// environment, so that components do not share their random sequences.
+    pub(crate) fn rng(&self, component: &str) -> StdRng {
+        let mut hasher = DefaultHasher::new();
+        self.seed.hash(&mut hasher);
+        component.hash(&mut hasher);
+        StdRng::seed_from_u64(hasher.finish())
+    }
+}
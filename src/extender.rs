@@ -299,7 +299,7 @@ impl<H: Hasher> Extender<H> {
 
     pub(crate) async fn extend(&mut self, mut exit: oneshot::Receiver<()>) {
         loop {
-            futures::select! {
+            futures::select_biased! {
                 v = self.electors.next() => {
                     if let Some(v) = v {
                         let v_hash = v.hash;
@@ -0,0 +1,121 @@
+//! Standard [`Hasher`](crate::Hasher)s producing 32 byte hashes, each available with its own
+//! feature: [`Blake2b256`] with `blake2b`, [`Sha256`] with `sha256` and [`Keccak256`] with
+//! `keccak256`.
+
+/// The hash produced by all the hashers of this module.
+pub type Hash256 = [u8; 32];
+
+/// Hashes with BLAKE2b, using 256 bits of output.
+#[cfg(feature = "blake2b")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Blake2b256;
+
+#[cfg(feature = "blake2b")]
+impl crate::Hasher for Blake2b256 {
+    type Hash = Hash256;
+
+    fn hash(s: &[u8]) -> Self::Hash {
+        use blake2::{
+            digest::{Update, VariableOutput},
+            VarBlake2b,
+        };
+
+        let mut hasher = VarBlake2b::new(32).expect("32 bytes is a valid output size");
+        hasher.update(s);
+        let mut hash = [0; 32];
+        hasher.finalize_variable(|output| hash.copy_from_slice(output));
+        hash
+    }
+}
+
+/// Hashes with SHA-256.
+#[cfg(feature = "sha256")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sha256;
+
+#[cfg(feature = "sha256")]
+impl crate::Hasher for Sha256 {
+    type Hash = Hash256;
+
+    fn hash(s: &[u8]) -> Self::Hash {
+        use sha2::Digest;
+
+        sha2::Sha256::digest(s).into()
+    }
+}
+
+/// Hashes with Keccak-256, the variant used by Ethereum, which differs from the standardized
+/// SHA3-256 in padding.
+#[cfg(feature = "keccak256")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Keccak256;
+
+#[cfg(feature = "keccak256")]
+impl crate::Hasher for Keccak256 {
+    type Hash = Hash256;
+
+    fn hash(s: &[u8]) -> Self::Hash {
+        use sha3::Digest;
+
+        sha3::Keccak256::digest(s).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Hash256;
+    use crate::Hasher;
+
+    fn from_hex(hex: &str) -> Hash256 {
+        let mut hash = [0; 32];
+        for (i, byte) in hash.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).expect("valid hex");
+        }
+        hash
+    }
+
+    #[cfg(feature = "blake2b")]
+    #[test]
+    fn blake2b_matches_reference_vectors() {
+        use super::Blake2b256;
+
+        assert_eq!(
+            Blake2b256::hash(b""),
+            from_hex("0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8")
+        );
+        assert_eq!(
+            Blake2b256::hash(b"abc"),
+            from_hex("bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319")
+        );
+    }
+
+    #[cfg(feature = "sha256")]
+    #[test]
+    fn sha256_matches_reference_vectors() {
+        use super::Sha256;
+
+        assert_eq!(
+            Sha256::hash(b""),
+            from_hex("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+        assert_eq!(
+            Sha256::hash(b"abc"),
+            from_hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+    }
+
+    #[cfg(feature = "keccak256")]
+    #[test]
+    fn keccak256_matches_reference_vectors() {
+        use super::Keccak256;
+
+        assert_eq!(
+            Keccak256::hash(b""),
+            from_hex("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert_eq!(
+            Keccak256::hash(b"abc"),
+            from_hex("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")
+        );
+    }
+}
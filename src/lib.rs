@@ -8,15 +8,29 @@ use std::{fmt::Debug, hash::Hash as StdHash, pin::Pin};
 
 use crate::nodes::NodeMap;
 
-pub use config::{default_config, exponential_slowdown, Config, DelayConfig};
-pub use member::run_session;
-pub use network::{Network, NetworkData, Recipient};
+pub use config::{
+    default_config, exponential_slowdown, Config, DelayConfig, DelaySchedule, Dissemination,
+    RecipientCountSchedule,
+};
+pub use environment::{Clock, Sleep, SystemClock};
+pub use member::{run_session, run_session_with_extras, AbandonedRequest, SessionExtras};
+pub use network::{
+    ApplicationChannel, ApplicationChannels, ChannelClosed, ChannelId, DecodingLimits, Network,
+    NetworkData, PeerEvent, Recipient, SendError,
+};
 pub use nodes::{NodeCount, NodeIndex};
 
 mod alerts;
+#[cfg(feature = "bls")]
+pub mod bls;
 mod consensus;
 mod creator;
+#[cfg(feature = "ed25519")]
+pub mod ed25519;
+mod environment;
 mod extender;
+#[cfg(any(feature = "blake2b", feature = "sha256", feature = "keccak256"))]
+pub mod hashing;
 mod member;
 mod network;
 mod nodes;
@@ -26,7 +40,7 @@ pub use signed::*;
 mod config;
 pub mod rmc;
 mod terminal;
-#[cfg(test)]
+#[cfg(any(test, feature = "testing"))]
 pub mod testing;
 mod units;
 
@@ -87,6 +101,10 @@ pub type OrderedBatch<Data> = Vec<Data>;
 pub type TaskHandle = Pin<Box<dyn Future<Output = Result<(), ()>> + Send>>;
 
 /// An abstraction for an execution engine for Rust's asynchronous tasks.
+///
+/// Some of the spawned tasks, e.g. the ones verifying signatures, are CPU-bound, so they should
+/// be run on a multi-threaded or blocking pool rather than a single thread shared with the rest of
+/// the application.
 pub trait SpawnHandle: Clone + Send + 'static {
     /// Run a new task.
     fn spawn(&self, name: &'static str, task: impl Future<Output = ()> + Send + 'static);
@@ -1,29 +1,38 @@
 use crate::{
-    config::Config,
-    network::{self, Recipient},
+    config::{Config, Dissemination},
+    environment::{Clock, Environment},
+    network::{
+        self, decode_bounded_vec, ApplicationChannels, DecodeBounded, DecodingLimits, NetworkEvent,
+        NetworkIO, PeerEvent, Recipient, SendError,
+    },
+    nodes::NodeMap,
     runway::{self, Request, Response, RunwayIO, RunwayNotificationIn, RunwayNotificationOut},
-    signed::Signature,
+    signed::{DomainTag, Signature},
     units::{UncheckedSignedUnit, UnitCoord},
-    Data, DataIO, Hasher, MultiKeychain, Network, NodeCount, NodeIndex, Receiver, Sender, Signable,
-    SpawnHandle, UncheckedSigned,
+    Data, DataIO, Hasher, MultiKeychain, Network, NodeCount, NodeIndex, Receiver, Round, Sender,
+    Signable, SpawnHandle, UncheckedSigned,
 };
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, Error, Input};
 use futures::{
     channel::{mpsc, oneshot},
     future::FusedFuture,
     pin_mut, FutureExt, StreamExt,
 };
-use futures_timer::Delay;
 use log::{debug, error, info, trace, warn};
-use rand::Rng;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 use std::{
     cmp::Ordering,
-    collections::{BinaryHeap, HashSet},
+    collections::{BinaryHeap, HashMap, HashSet},
     convert::TryInto,
     fmt::Debug,
+    sync::Arc,
     time,
 };
 
+/// The time after which a peer for which sending failed is tried again, even if we did not hear
+/// from it in the meantime.
+const FAILED_PEER_TIMEOUT: time::Duration = time::Duration::from_secs(10);
+
 #[derive(Debug, Encode, Decode, Clone)]
 pub(crate) struct NewestUnitResponse<H: Hasher, D: Data, S: Signature> {
     pub(crate) requester: NodeIndex,
@@ -35,8 +44,10 @@ pub(crate) struct NewestUnitResponse<H: Hasher, D: Data, S: Signature> {
 impl<H: Hasher, D: Data, S: Signature> Signable for NewestUnitResponse<H, D, S> {
     type Hash = Vec<u8>;
 
+    /// The response carries a whole unit, so only its hash is signed, which keeps the signed
+    /// messages short.
     fn hash(&self) -> Self::Hash {
-        self.encode()
+        (DomainTag::NewestUnitResponse, H::hash(&self.encode())).encode()
     }
 }
 
@@ -46,6 +57,25 @@ impl<H: Hasher, D: Data, S: Signature> crate::Index for NewestUnitResponse<H, D,
     }
 }
 
+impl<H: Hasher, D: Data, S: Signature> DecodeBounded for NewestUnitResponse<H, D, S> {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let requester = NodeIndex::decode(input)?;
+        let responder = NodeIndex::decode(input)?;
+        let unit = match input.read_byte()? {
+            0 => None,
+            1 => Some(UncheckedSignedUnit::decode_bounded(input, limits)?),
+            _ => return Err(Error::from("Invalid Option in NewestUnitResponse.")),
+        };
+        let salt = u64::decode(input)?;
+        Ok(NewestUnitResponse {
+            requester,
+            responder,
+            unit,
+            salt,
+        })
+    }
+}
+
 /// A message concerning units, either about new units or some requests for them.
 #[derive(Debug, Encode, Decode, Clone)]
 pub(crate) enum UnitMessage<H: Hasher, D: Data, S: Signature> {
@@ -66,6 +96,15 @@ pub(crate) enum UnitMessage<H: Hasher, D: Data, S: Signature> {
 }
 
 impl<H: Hasher, D: Data, S: Signature> UnitMessage<H, D, S> {
+    pub(crate) fn is_request(&self) -> bool {
+        matches!(
+            self,
+            UnitMessage::RequestCoord(..)
+                | UnitMessage::RequestParents(..)
+                | UnitMessage::RequestNewest(..)
+        )
+    }
+
     pub(crate) fn included_data(&self) -> Vec<D> {
         match self {
             Self::NewUnit(uu) => vec![uu.as_signable().data().clone()],
@@ -87,10 +126,37 @@ impl<H: Hasher, D: Data, S: Signature> UnitMessage<H, D, S> {
     }
 }
 
+impl<H: Hasher, D: Data, S: Signature> DecodeBounded for UnitMessage<H, D, S> {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let message = match input.read_byte()? {
+            0 => Self::NewUnit(UncheckedSignedUnit::decode_bounded(input, limits)?),
+            1 => Self::RequestCoord(NodeIndex::decode(input)?, UnitCoord::decode(input)?),
+            2 => Self::ResponseCoord(UncheckedSignedUnit::decode_bounded(input, limits)?),
+            3 => Self::RequestParents(NodeIndex::decode(input)?, H::Hash::decode(input)?),
+            4 => {
+                let hash = H::Hash::decode(input)?;
+                let parents = decode_bounded_vec(input, limits.n_members.0, limits)?;
+                Self::ResponseParents(hash, parents)
+            }
+            5 => Self::RequestNewest(NodeIndex::decode(input)?, u64::decode(input)?),
+            6 => Self::ResponseNewest(UncheckedSigned::decode_bounded(input, limits)?),
+            _ => return Err(Error::from("Invalid variant of UnitMessage.")),
+        };
+        Ok(message)
+    }
+}
+
+// Randomly scales the delay by a factor between 0.75 and 1.25, so that requests of different
+// nodes do not synchronize.
+fn with_jitter(delay: time::Duration, rng: &mut StdRng) -> time::Duration {
+    delay.mul_f64(rng.gen_range(0.75..1.25))
+}
+
 #[derive(Eq, PartialEq)]
 enum Task<H: Hasher, D: Data, S: Signature> {
-    // Request the unit with the given (creator, round) coordinates.
-    CoordRequest(UnitCoord),
+    // Request the unit with the given (creator, round) coordinates from one of the given nodes
+    // known to have it.
+    CoordRequest(UnitCoord, Vec<NodeIndex>),
     // Request parents of the unit with the given hash and Recipient.
     ParentsRequest(H::Hash, Recipient),
     // Broadcast the given unit.
@@ -130,6 +196,16 @@ impl<H: Hasher, D: Data, S: Signature> PartialOrd for ScheduledTask<H, D, S> {
     }
 }
 
+struct MemberIO<H: Hasher, D: Data, S: Signature> {
+    unit_messages_for_network: Sender<(UnitMessage<H, D, S>, Recipient)>,
+    unit_messages_from_network: Receiver<UnitMessage<H, D, S>>,
+    notifications_for_runway: Sender<RunwayNotificationIn<H, D, S>>,
+    notifications_from_runway: Receiver<RunwayNotificationOut<H, D, S>>,
+    resolved_requests: Receiver<Request<H>>,
+    network_events: Receiver<NetworkEvent<H, D, S>>,
+    abandoned_requests: Option<Sender<AbandonedRequest<H>>>,
+}
+
 struct Member<H, D, S>
 where
     H: Hasher,
@@ -140,13 +216,33 @@ where
     task_queue: BinaryHeap<ScheduledTask<H, D, S>>,
     not_resolved_parents: HashSet<H::Hash>,
     not_resolved_coords: HashSet<UnitCoord>,
+    // For rounds of our units still being multicast, the peers known to have referenced them.
+    own_units_referenced_by: HashMap<Round, HashSet<NodeIndex>>,
+    // The round of the newest unit we created.
+    newest_own_round: Option<Round>,
     newest_unit_resolved: bool,
+    // Peers reported as disconnected by the network.
+    unreachable_peers: HashSet<NodeIndex>,
+    // Peers for which sending failed, with the time of the failure, until we hear from them again
+    // or FAILED_PEER_TIMEOUT passes.
+    failed_peers: HashMap<NodeIndex, time::Instant>,
+    // The number of failed sends to each peer.
+    send_failures: NodeMap<usize>,
+    // Set when the network is congested, tasks are not performed until then.
+    tasks_deferred_until: Option<time::Instant>,
+    // The highest round of a validated unit created by each peer.
+    highest_round_received: NodeMap<Option<Round>>,
     n_members: NodeCount,
+    clock: Arc<dyn Clock>,
+    rng: StdRng,
     unit_messages_for_network: Sender<(UnitMessage<H, D, S>, Recipient)>,
     unit_messages_from_network: Receiver<UnitMessage<H, D, S>>,
     notifications_for_runway: Sender<RunwayNotificationIn<H, D, S>>,
     notifications_from_runway: Receiver<RunwayNotificationOut<H, D, S>>,
     resolved_requests: Receiver<Request<H>>,
+    network_events: Receiver<NetworkEvent<H, D, S>>,
+    // Reports abandoned requests to the application, if it asked for them.
+    abandoned_requests: Option<Sender<AbandonedRequest<H>>>,
     exiting: bool,
 }
 
@@ -156,44 +252,154 @@ where
     D: Data,
     S: Signature,
 {
-    fn new(
-        config: Config,
-        unit_messages_for_network: Sender<(UnitMessage<H, D, S>, Recipient)>,
-        unit_messages_from_network: Receiver<UnitMessage<H, D, S>>,
-        notifications_for_runway: Sender<RunwayNotificationIn<H, D, S>>,
-        notifications_from_runway: Receiver<RunwayNotificationOut<H, D, S>>,
-        resolved_requests: Receiver<Request<H>>,
-    ) -> Self {
+    fn new(config: Config, environment: &Environment, io: MemberIO<H, D, S>) -> Self {
         let n_members = config.n_members;
         Self {
             config,
             task_queue: BinaryHeap::new(),
             not_resolved_parents: HashSet::new(),
             not_resolved_coords: HashSet::new(),
+            own_units_referenced_by: HashMap::new(),
+            newest_own_round: None,
             newest_unit_resolved: false,
+            unreachable_peers: HashSet::new(),
+            failed_peers: HashMap::new(),
+            send_failures: NodeMap::new_with_len(n_members),
+            tasks_deferred_until: None,
+            highest_round_received: NodeMap::new_with_len(n_members),
             n_members,
-            unit_messages_for_network,
-            unit_messages_from_network,
-            notifications_for_runway,
-            notifications_from_runway,
-            resolved_requests,
+            clock: environment.clock(),
+            rng: environment.rng("member"),
+            unit_messages_for_network: io.unit_messages_for_network,
+            unit_messages_from_network: io.unit_messages_from_network,
+            notifications_for_runway: io.notifications_for_runway,
+            notifications_from_runway: io.notifications_from_runway,
+            resolved_requests: io.resolved_requests,
+            network_events: io.network_events,
+            abandoned_requests: io.abandoned_requests,
             exiting: false,
         }
     }
 
+    fn on_peer_event(&mut self, event: PeerEvent) {
+        match event {
+            PeerEvent::Connected(node_id) => {
+                self.unreachable_peers.remove(&node_id);
+                self.failed_peers.remove(&node_id);
+            }
+            PeerEvent::Disconnected(node_id) => {
+                self.unreachable_peers.insert(node_id);
+            }
+        }
+    }
+
+    fn on_unit_validated(&mut self, creator: NodeIndex, round: Round) {
+        self.failed_peers.remove(&creator);
+        match self.highest_round_received[creator] {
+            Some(highest) if highest >= round => {}
+            _ => self.highest_round_received[creator] = Some(round),
+        }
+    }
+
+    fn on_send_failure(&mut self, message: Option<UnitMessage<H, D, S>>, error: SendError) {
+        match error {
+            SendError::PeerUnreachable(peers) => {
+                let peers: Vec<_> = peers
+                    .into_iter()
+                    .filter(|node_id| node_id.0 < self.n_members.0)
+                    .collect();
+                let now = self.clock.now();
+                self.failed_peers
+                    .retain(|_, failed_at| now < *failed_at + FAILED_PEER_TIMEOUT);
+                for node_id in &peers {
+                    self.send_failures[*node_id] += 1;
+                    self.failed_peers.insert(*node_id, now);
+                }
+                if let Some(message) = message.filter(|message| self.is_active_request(message)) {
+                    // No fallback here, it could resend to the failed peers right away.
+                    let alternatives = self.choose_candidates(&[], peers.len(), true);
+                    if !alternatives.is_empty() {
+                        self.send_unit_message(message, Recipient::Nodes(alternatives));
+                    }
+                }
+            }
+            SendError::QueueFull => {
+                // The network is congested, so we postpone further requests and rebroadcasts.
+                let deadline = self.clock.now() + self.config.delay_config.network_congestion_delay;
+                self.tasks_deferred_until = Some(deadline);
+            }
+            SendError::MessageTooLarge => {
+                // Sending it elsewhere or later would not help, the limits should be adjusted.
+                warn!(target: "AlephBFT-member", "{:?} Network refused a message which is too large.", self.index());
+            }
+            SendError::Closed => {
+                warn!(target: "AlephBFT-member", "{:?} Network reported it is closed.", self.index());
+            }
+        }
+    }
+
+    fn is_active_request(&self, message: &UnitMessage<H, D, S>) -> bool {
+        match message {
+            UnitMessage::RequestCoord(_, coord) => self.not_resolved_coords.contains(coord),
+            UnitMessage::RequestParents(_, hash) => self.not_resolved_parents.contains(hash),
+            _ => false,
+        }
+    }
+
+    fn is_reachable(&self, node_id: NodeIndex) -> bool {
+        let failed = match self.failed_peers.get(&node_id) {
+            Some(failed_at) => self.clock.now() < *failed_at + FAILED_PEER_TIMEOUT,
+            None => false,
+        };
+        node_id != self.index() && !self.unreachable_peers.contains(&node_id) && !failed
+    }
+
+    // All the peers are candidates when none of them is reachable, as sending to no one would
+    // stall the tasks.
+    fn is_candidate(&self, node_id: NodeIndex, only_reachable: bool) -> bool {
+        if only_reachable {
+            self.is_reachable(node_id)
+        } else {
+            node_id != self.index()
+        }
+    }
+
     fn on_create(&mut self, u: UncheckedSignedUnit<H, D, S>) {
-        let curr_time = time::Instant::now();
+        let round = u.as_signable().round();
+        if !matches!(self.newest_own_round, Some(newest) if newest >= round) {
+            self.newest_own_round = Some(round);
+            let horizon = self.rebroadcast_horizon();
+            self.own_units_referenced_by
+                .retain(|round, _| *round >= horizon);
+        }
+        let curr_time = self.clock.now();
         let task = ScheduledTask::new(Task::UnitMulticast(u), curr_time);
         self.task_queue.push(task);
     }
 
-    fn on_request_coord(&mut self, coord: UnitCoord) {
+    fn on_relay(&mut self, u: UncheckedSignedUnit<H, D, S>) {
+        let fanout = match self.config.dissemination {
+            Dissemination::Gossip { fanout } => fanout,
+            Dissemination::Broadcast => return,
+        };
+        let creator = u.as_signable().creator();
+        // The creator surely has the unit, so we pick one spare peer in case it gets chosen.
+        let peers: Vec<_> = self
+            .choose_peers(&[], fanout + 1)
+            .into_iter()
+            .filter(|node_id| *node_id != creator)
+            .take(fanout)
+            .collect();
+        self.send_unit_message(UnitMessage::NewUnit(u), Recipient::Nodes(peers));
+    }
+
+    fn on_request_coord(&mut self, coord: UnitCoord, known_holders: Vec<NodeIndex>) {
         trace!(target: "AlephBFT-member", "{:?} Dealing with missing coord notification {:?}.", self.index(), coord);
         if !self.not_resolved_coords.insert(coord) {
             return;
         }
-        let curr_time = time::Instant::now();
-        let task = ScheduledTask::new(Task::CoordRequest(coord), curr_time);
+        let curr_time = self.clock.now();
+        let task = ScheduledTask::new(Task::CoordRequest(coord, known_holders), curr_time);
         self.task_queue.push(task);
         self.trigger_tasks();
     }
@@ -202,14 +408,24 @@ where
         if !self.not_resolved_parents.insert(u_hash) {
             return;
         }
-        let curr_time = time::Instant::now();
+        let curr_time = self.clock.now();
         let task = ScheduledTask::new(Task::ParentsRequest(u_hash, recipient), curr_time);
         self.task_queue.push(task);
         self.trigger_tasks();
     }
 
+    fn on_own_unit_referenced(&mut self, round: Round, node_id: NodeIndex) {
+        if round < self.rebroadcast_horizon() {
+            return;
+        }
+        self.own_units_referenced_by
+            .entry(round)
+            .or_default()
+            .insert(node_id);
+    }
+
     fn on_request_newest(&mut self, salt: u64) {
-        let curr_time = time::Instant::now();
+        let curr_time = self.clock.now();
         let task = ScheduledTask::new(Task::RequestNewest(salt), curr_time);
         self.task_queue.push(task);
         self.trigger_tasks();
@@ -218,8 +434,14 @@ where
     // Pulls tasks from the priority queue (sorted by scheduled time) and sends them to random peers
     // as long as they are scheduled at time <= curr_time
     fn trigger_tasks(&mut self) {
+        if let Some(deadline) = self.tasks_deferred_until {
+            if self.clock.now() < deadline {
+                return;
+            }
+            self.tasks_deferred_until = None;
+        }
         while let Some(request) = self.task_queue.peek() {
-            let curr_time = time::Instant::now();
+            let curr_time = self.clock.now();
             if request.scheduled_time > curr_time {
                 break;
             }
@@ -227,7 +449,10 @@ where
             if let Some((message, recipient, delay)) =
                 self.task_details(&request.task, request.counter)
             {
-                self.send_unit_message(message, recipient);
+                // All the intended recipients might be unreachable at the moment.
+                if !matches!(&recipient, Recipient::Nodes(nodes) if nodes.is_empty()) {
+                    self.send_unit_message(message, recipient);
+                }
                 request.scheduled_time += delay;
                 request.counter += 1;
                 self.task_queue.push(request);
@@ -235,10 +460,82 @@ where
         }
     }
 
-    fn random_peer(&self) -> NodeIndex {
-        rand::thread_rng()
-            .gen_range(0..self.n_members.into())
-            .into()
+    // Picks up to `count` distinct reachable peers, preferring the ones in `preferred`. Falls back
+    // to all the peers if none of them is reachable.
+    fn choose_peers(&mut self, preferred: &[NodeIndex], count: usize) -> Vec<NodeIndex> {
+        let peers = self.choose_candidates(preferred, count, true);
+        if peers.is_empty() {
+            return self.choose_candidates(preferred, count, false);
+        }
+        peers
+    }
+
+    fn choose_candidates(
+        &mut self,
+        preferred: &[NodeIndex],
+        count: usize,
+        only_reachable: bool,
+    ) -> Vec<NodeIndex> {
+        let mut peers: Vec<_> = preferred
+            .iter()
+            .copied()
+            .filter(|node_id| self.is_candidate(*node_id, only_reachable))
+            .collect();
+        peers.sort_by_key(|node_id| node_id.0);
+        peers.dedup();
+        peers.shuffle(&mut self.rng);
+        peers.sort_by_key(|node_id| self.send_failures[*node_id]);
+        peers.truncate(count);
+        if peers.len() < count {
+            let mut others: Vec<_> = (0..self.n_members.0)
+                .map(NodeIndex)
+                .filter(|node_id| {
+                    self.is_candidate(*node_id, only_reachable) && !peers.contains(node_id)
+                })
+                .collect();
+            others.shuffle(&mut self.rng);
+            others.sort_by_key(|node_id| self.send_failures[*node_id]);
+            others.truncate(count - peers.len());
+            peers.extend(others);
+        }
+        peers
+    }
+
+    // Returns the peers which are likely to have the unit with the given coord: the known holders
+    // and the peers which sent us units of later rounds.
+    fn likely_holders(&self, coord: &UnitCoord, known_holders: &[NodeIndex]) -> Vec<NodeIndex> {
+        let mut holders = known_holders.to_vec();
+        holders.extend(
+            self.highest_round_received
+                .enumerate()
+                .filter(|(_, highest)| matches!(highest, Some(round) if *round > coord.round()))
+                .map(|(node_id, _)| node_id),
+        );
+        holders
+    }
+
+    // The oldest round of our units which are still rebroadcast.
+    fn rebroadcast_horizon(&self) -> Round {
+        match (
+            self.newest_own_round,
+            self.config.delay_config.unit_rebroadcast_horizon,
+        ) {
+            (Some(newest), Some(horizon)) => newest.saturating_sub(horizon),
+            _ => 0,
+        }
+    }
+
+    // Returns the peers which are not known to have our unit of the given round.
+    fn peers_missing_own_unit(&self, round: Round) -> Vec<NodeIndex> {
+        let referenced_by = self
+            .own_units_referenced_by
+            .get(&round)
+            .cloned()
+            .unwrap_or_default();
+        (0..self.n_members.0)
+            .map(NodeIndex)
+            .filter(|node_id| *node_id != self.index() && !referenced_by.contains(node_id))
+            .collect()
     }
 
     fn index(&self) -> NodeIndex {
@@ -265,64 +562,144 @@ where
         counter: usize,
     ) -> Option<(UnitMessage<H, D, S>, Recipient, time::Duration)> {
         // preferred_recipient is Everyone if the message is supposed to be broadcast,
-        // and Node(node_id) if the message should be send to one peer (node_id when
-        // the task is done for the first time or a random peer otherwise)
-        let (message, preferred_recipient) = match task {
-            Task::CoordRequest(coord) => {
+        // and Node(node_id) if the request should be sent to node_id when it is done for the first
+        // time. Otherwise requests are sent to n_recipients peers, preferably among known_holders.
+        let (message, preferred_recipient, known_holders, delay, n_recipients) = match task {
+            Task::CoordRequest(coord, known_holders) => {
                 if !self.not_resolved_coords.contains(coord) {
                     return None;
                 }
+                if self.is_request_expired(counter) {
+                    self.not_resolved_coords.remove(coord);
+                    self.on_request_abandoned(Request::Coord(*coord));
+                    return None;
+                }
                 let message = UnitMessage::RequestCoord(self.index(), *coord);
-                let preferred_recipient = Recipient::Node(coord.creator());
-                (message, preferred_recipient)
+                let delay_config = &self.config.delay_config;
+                (
+                    message,
+                    Recipient::Node(coord.creator()),
+                    self.likely_holders(coord, known_holders),
+                    (delay_config.coord_request_delay)(counter),
+                    (delay_config.coord_request_recipients)(counter),
+                )
             }
             Task::ParentsRequest(hash, preferred_recipient) => {
                 if !self.not_resolved_parents.contains(hash) {
                     return None;
                 }
+                if self.is_request_expired(counter) {
+                    self.not_resolved_parents.remove(hash);
+                    self.on_request_abandoned(Request::Parents(*hash));
+                    return None;
+                }
                 let message = UnitMessage::RequestParents(self.index(), *hash);
-                let preferred_recipient = preferred_recipient.clone();
-                (message, preferred_recipient)
+                let delay_config = &self.config.delay_config;
+                (
+                    message,
+                    preferred_recipient.clone(),
+                    Vec::new(),
+                    (delay_config.parent_request_delay)(counter),
+                    (delay_config.parent_request_recipients)(counter),
+                )
             }
             Task::UnitMulticast(signed_unit) => {
                 let message = UnitMessage::NewUnit(signed_unit.clone());
-                let preferred_recipient = Recipient::Everyone;
-                (message, preferred_recipient)
+                let delay = (self.config.delay_config.unit_broadcast_delay)(counter);
+                if counter == 0 {
+                    let recipient = match self.config.dissemination {
+                        Dissemination::Broadcast => Recipient::Everyone,
+                        Dissemination::Gossip { fanout } => {
+                            Recipient::Nodes(self.choose_peers(&[], fanout))
+                        }
+                    };
+                    return Some((message, recipient, delay));
+                }
+                // Rebroadcast only to the peers that did not reference our unit yet.
+                let round = signed_unit.as_signable().round();
+                if round < self.rebroadcast_horizon() {
+                    self.own_units_referenced_by.remove(&round);
+                    return None;
+                }
+                let mut peers = self.peers_missing_own_unit(round);
+                if peers.is_empty() {
+                    self.own_units_referenced_by.remove(&round);
+                    return None;
+                }
+                if peers.iter().any(|node_id| self.is_reachable(*node_id)) {
+                    peers.retain(|node_id| self.is_reachable(*node_id));
+                }
+                if let Dissemination::Gossip { fanout } = self.config.dissemination {
+                    peers.shuffle(&mut self.rng);
+                    peers.truncate(fanout);
+                }
+                return Some((message, Recipient::Nodes(peers), delay));
             }
             Task::RequestNewest(salt) => {
                 if self.newest_unit_resolved {
                     return None;
                 }
                 let message = UnitMessage::RequestNewest(self.index(), *salt);
-                let preferred_recipient = Recipient::Everyone;
-                (message, preferred_recipient)
+                let delay = (self.config.delay_config.newest_request_delay)(counter);
+                return Some((message, Recipient::Everyone, delay));
             }
         };
-        let (recipient, delay) = match preferred_recipient {
-            Recipient::Everyone => (
-                Recipient::Everyone,
-                (self.config.delay_config.unit_broadcast_delay)(counter),
-            ),
-            Recipient::Node(preferred_id) => {
-                let recipient = if counter == 0 {
-                    preferred_id
+        let recipient = match preferred_recipient {
+            Recipient::Everyone if counter == 0 => Recipient::Everyone,
+            Recipient::Node(preferred_id) if counter == 0 && self.is_reachable(preferred_id) => {
+                if n_recipients <= 1 {
+                    Recipient::Node(preferred_id)
                 } else {
-                    self.random_peer()
-                };
-                (
-                    Recipient::Node(recipient),
-                    self.config.delay_config.requests_interval,
-                )
+                    let mut recipients = vec![preferred_id];
+                    recipients.extend(
+                        self.choose_peers(&known_holders, n_recipients)
+                            .into_iter()
+                            .filter(|node_id| *node_id != preferred_id)
+                            .take(n_recipients - 1),
+                    );
+                    Recipient::Nodes(recipients)
+                }
             }
+            _ => Recipient::Nodes(self.choose_peers(&known_holders, n_recipients)),
         };
-        Some((message, recipient, delay))
+        Some((message, recipient, with_jitter(delay, &mut self.rng)))
+    }
+
+    fn is_request_expired(&self, counter: usize) -> bool {
+        match self.config.delay_config.request_attempts_limit {
+            Some(limit) => counter >= limit,
+            None => false,
+        }
+    }
+
+    fn on_request_abandoned(&mut self, request: Request<H>) {
+        debug!(target: "AlephBFT-member", "{:?} Giving up on a request.", self.index());
+        let abandoned = match &request {
+            Request::Coord(coord) => Some(AbandonedRequest::Coord(coord.round(), coord.creator())),
+            Request::Parents(u_hash) => Some(AbandonedRequest::Parents(*u_hash)),
+            Request::NewestUnit(_) => None,
+        };
+        if let (Some(abandoned), Some(abandoned_requests)) = (abandoned, &self.abandoned_requests) {
+            if abandoned_requests.unbounded_send(abandoned).is_err() {
+                debug!(target: "AlephBFT-member", "{:?} Nobody listens for abandoned requests anymore.", self.index());
+                self.abandoned_requests = None;
+            }
+        }
+        self.send_notification_to_runway(RunwayNotificationIn::RequestAbandoned(request));
     }
 
     fn on_unit_message_from_units(&mut self, message: RunwayNotificationOut<H, D, S>) {
         match message {
             RunwayNotificationOut::NewUnit(u) => self.on_create(u),
             RunwayNotificationOut::Request(request, recipient) => match request {
-                Request::Coord(coord) => self.on_request_coord(coord),
+                Request::Coord(coord) => {
+                    let known_holders = match recipient {
+                        Recipient::Node(node_id) => vec![node_id],
+                        Recipient::Nodes(node_ids) => node_ids,
+                        Recipient::Everyone => Vec::new(),
+                    };
+                    self.on_request_coord(coord, known_holders)
+                }
                 Request::Parents(u_hash) => self.on_request_parents(u_hash, recipient),
                 Request::NewestUnit(salt) => self.on_request_newest(salt),
             },
@@ -341,15 +718,22 @@ where
                     self.send_unit_message(message, Recipient::Node(requester))
                 }
             },
+            RunwayNotificationOut::OwnUnitReferenced(round, node_id) => {
+                self.on_own_unit_referenced(round, node_id)
+            }
+            RunwayNotificationOut::RelayUnit(u) => self.on_relay(u),
+            RunwayNotificationOut::UnitValidated(creator, round) => {
+                self.on_unit_validated(creator, round)
+            }
         }
     }
 
     async fn run(mut self, mut exit: oneshot::Receiver<()>) {
         let ticker_delay = self.config.delay_config.tick_interval;
-        let mut ticker = Delay::new(ticker_delay).fuse();
+        let mut ticker = self.clock.sleep(ticker_delay).fuse();
 
         loop {
-            futures::select! {
+            futures::select_biased! {
                 event = self.notifications_from_runway.next() => match event {
                     Some(message) => {
                         self.on_unit_message_from_units(message);
@@ -385,9 +769,18 @@ where
                     },
                 },
 
+                event = self.network_events.next() => match event {
+                    Some(NetworkEvent::Peer(event)) => self.on_peer_event(event),
+                    Some(NetworkEvent::SendFailed(message, error)) => self.on_send_failure(message, error),
+                    None => {
+                        error!(target: "AlephBFT-member", "{:?} Network events stream closed.", self.index());
+                        break;
+                    },
+                },
+
                 _ = &mut ticker => {
                     self.trigger_tasks();
-                    ticker = Delay::new(ticker_delay).fuse();
+                    ticker = self.clock.sleep(ticker_delay).fuse();
                 },
 
                 _ = &mut exit => {
@@ -415,6 +808,61 @@ where
     }
 }
 
+/// A request for missing units which the member gave up on after
+/// [`DelayConfig::request_attempts_limit`](crate::DelayConfig::request_attempts_limit) attempts.
+/// The units are requested again if they turn out to be missing later.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AbandonedRequest<H: Hasher> {
+    /// The unit of the given round created by the given member.
+    Coord(Round, NodeIndex),
+    /// The parents of the unit with the given hash.
+    Parents(H::Hash),
+}
+
+/// The optional parts of a session run with [`run_session_with_extras`]. The default adds
+/// nothing to what [`run_session`] does.
+pub struct SessionExtras<H: Hasher> {
+    application_channels: ApplicationChannels,
+    abandoned_requests: Option<mpsc::UnboundedSender<AbandonedRequest<H>>>,
+    seed: Option<u64>,
+}
+
+impl<H: Hasher> SessionExtras<H> {
+    /// Messages of the channels registered in `application_channels` are sent and received
+    /// through the same network as the consensus messages, see [`ApplicationChannels`].
+    pub fn with_application_channels(mut self, application_channels: ApplicationChannels) -> Self {
+        self.application_channels = application_channels;
+        self
+    }
+
+    /// The requests for missing units which were given up on are sent to `abandoned_requests`,
+    /// see [`AbandonedRequest`].
+    pub fn with_abandoned_requests(
+        mut self,
+        abandoned_requests: mpsc::UnboundedSender<AbandonedRequest<H>>,
+    ) -> Self {
+        self.abandoned_requests = Some(abandoned_requests);
+        self
+    }
+
+    /// All the randomness used by the member is derived from `seed`.
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+impl<H: Hasher> Default for SessionExtras<H> {
+    fn default() -> Self {
+        SessionExtras {
+            application_channels: ApplicationChannels::new(),
+            abandoned_requests: None,
+            seed: None,
+        }
+    }
+}
+
 /// Starts the consensus algorithm as an async task. It stops establishing consensus for new data items after
 /// reaching the threshold specified in [`Config::max_round`] or upon receiving a stop signal from `exit`.
 /// For a detailed description of the consensus implemented by `run_session` see
@@ -433,9 +881,44 @@ pub async fn run_session<
     data_io: DP,
     keybox: MK,
     spawn_handle: SH,
+    exit: oneshot::Receiver<()>,
+) {
+    run_session_with_extras(
+        config,
+        network,
+        data_io,
+        keybox,
+        spawn_handle,
+        SessionExtras::default(),
+        exit,
+    )
+    .await
+}
+
+/// Like [`run_session`], but with the optional parts of the session given in `extras`.
+pub async fn run_session_with_extras<
+    H: Hasher,
+    D: Data,
+    DP: DataIO<D>,
+    N: Network<H, D, MK::Signature, MK::PartialMultisignature> + 'static,
+    SH: SpawnHandle,
+    MK: MultiKeychain,
+>(
+    config: Config,
+    network: N,
+    data_io: DP,
+    keybox: MK,
+    spawn_handle: SH,
+    extras: SessionExtras<H>,
     mut exit: oneshot::Receiver<()>,
 ) {
+    let SessionExtras {
+        application_channels,
+        abandoned_requests,
+        seed,
+    } = extras;
     let index = config.node_ix;
+    let environment = Environment::new(config.clock.clone(), seed.unwrap_or_else(rand::random));
     info!(target: "AlephBFT-member", "{:?} Spawning party for a session.", index);
 
     let (alert_messages_for_alerter, alert_messages_from_network) = mpsc::unbounded();
@@ -445,20 +928,20 @@ pub async fn run_session<
     let (runway_messages_for_runway, runway_messages_from_network) = mpsc::unbounded();
     let (runway_messages_for_network, runway_messages_from_runway) = mpsc::unbounded();
     let (resolved_requests_tx, resolved_requests_rx) = mpsc::unbounded();
+    let (network_events_for_member, network_events_from_network) = mpsc::unbounded();
 
     info!(target: "AlephBFT-member", "{:?} Spawning network.", index);
     let (network_exit, exit_stream) = oneshot::channel();
 
+    let network_io = NetworkIO {
+        units_to_send: unit_messages_from_units,
+        units_received: unit_messages_for_units,
+        alerts_to_send: alert_messages_from_alerter,
+        alerts_received: alert_messages_for_alerter,
+        events_for_member: network_events_for_member,
+    };
     let network_handle = spawn_handle.spawn_essential("member/network", async move {
-        network::run(
-            network,
-            unit_messages_from_units,
-            unit_messages_for_units,
-            alert_messages_from_alerter,
-            alert_messages_for_alerter,
-            exit_stream,
-        )
-        .await
+        network::run(network, network_io, application_channels, exit_stream).await
     });
     let network_handle = network_handle.fuse();
     pin_mut!(network_handle);
@@ -478,6 +961,7 @@ pub async fn run_session<
         keybox.clone(),
         data_io,
         spawn_handle.clone(),
+        environment.clone(),
         runway_io,
         exit_stream,
     );
@@ -486,20 +970,22 @@ pub async fn run_session<
     info!(target: "AlephBFT-member", "{:?} Runway initialized.", index);
 
     info!(target: "AlephBFT-member", "{:?} Initializing Member.", index);
-    let member = Member::new(
-        config,
+    let member_io = MemberIO {
         unit_messages_for_network,
         unit_messages_from_network,
-        runway_messages_for_runway,
-        runway_messages_from_runway,
-        resolved_requests_rx,
-    );
+        notifications_for_runway: runway_messages_for_runway,
+        notifications_from_runway: runway_messages_from_runway,
+        resolved_requests: resolved_requests_rx,
+        network_events: network_events_from_network,
+        abandoned_requests,
+    };
+    let member = Member::new(config, &environment, member_io);
     let (member_exit, exit_stream) = oneshot::channel();
     let member_handle = member.run(exit_stream).fuse();
     pin_mut!(member_handle);
     info!(target: "AlephBFT-member", "{:?} Member initialized.", index);
 
-    futures::select! {
+    futures::select_biased! {
         _ = network_handle => {
             error!(target: "AlephBFT-member", "{:?} Network-hub terminated early.", index);
         },
@@ -542,3 +1028,256 @@ pub async fn run_session<
 
     info!(target: "AlephBFT-member", "{:?} Run ended.", index);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AbandonedRequest, Member, MemberIO, UnitMessage, FAILED_PEER_TIMEOUT};
+    use crate::{
+        environment::Environment,
+        nodes::BoolNodeMap,
+        runway::{Request, RunwayNotificationIn},
+        testing::mock::{gen_config, Data, Hasher64, Signature},
+        units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit, UnitCoord},
+        Hasher, NodeCount, NodeIndex, PeerEvent, Receiver, Recipient, Round, SendError,
+        UncheckedSigned,
+    };
+    use codec::Encode;
+    use futures::{channel::mpsc, FutureExt, StreamExt};
+    use std::{collections::HashSet, sync::Arc, time::Duration};
+
+    type TestMember = Member<Hasher64, Data, Signature>;
+    type OutgoingMessages = Receiver<(UnitMessage<Hasher64, Data, Signature>, Recipient)>;
+    type RunwayNotifications = Receiver<RunwayNotificationIn<Hasher64, Data, Signature>>;
+
+    fn member(node_ix: NodeIndex, n_members: NodeCount) -> TestMember {
+        member_with_outgoing(node_ix, n_members).0
+    }
+
+    fn member_with_outgoing(
+        node_ix: NodeIndex,
+        n_members: NodeCount,
+    ) -> (TestMember, OutgoingMessages, RunwayNotifications) {
+        let (unit_messages_for_network, outgoing) = mpsc::unbounded();
+        let (_, unit_messages_from_network) = mpsc::unbounded();
+        let (notifications_for_runway, for_runway) = mpsc::unbounded();
+        let (_, notifications_from_runway) = mpsc::unbounded();
+        let (_, resolved_requests) = mpsc::unbounded();
+        let (_, network_events) = mpsc::unbounded();
+        let io = MemberIO {
+            unit_messages_for_network,
+            unit_messages_from_network,
+            notifications_for_runway,
+            notifications_from_runway,
+            resolved_requests,
+            network_events,
+            abandoned_requests: None,
+        };
+        let config = gen_config(node_ix, n_members);
+        let environment = Environment::new(config.clock.clone(), 0);
+        let member = Member::new(config, &environment, io);
+        (member, outgoing, for_runway)
+    }
+
+    #[test]
+    fn chooses_distinct_peers_preferring_known_holders() {
+        let mut member = member(NodeIndex(0), NodeCount(10));
+        let preferred = [NodeIndex(0), NodeIndex(3), NodeIndex(7)];
+        let peers = member.choose_peers(&preferred, 2);
+        assert_eq!(peers.len(), 2);
+        assert!(peers
+            .iter()
+            .all(|p| *p == NodeIndex(3) || *p == NodeIndex(7)));
+        assert_ne!(peers[0], peers[1]);
+
+        let peers = member.choose_peers(&preferred, 5);
+        let distinct: HashSet<_> = peers.iter().collect();
+        assert_eq!(distinct.len(), 5);
+        assert!(!peers.contains(&NodeIndex(0)));
+        assert!(peers.contains(&NodeIndex(3)) && peers.contains(&NodeIndex(7)));
+
+        assert_eq!(member.choose_peers(&[], 20).len(), 9);
+    }
+
+    #[test]
+    fn chooses_only_reachable_peers() {
+        let mut member = member(NodeIndex(0), NodeCount(4));
+        member.on_peer_event(PeerEvent::Disconnected(NodeIndex(1)));
+        member.on_peer_event(PeerEvent::Disconnected(NodeIndex(2)));
+        assert_eq!(member.choose_peers(&[NodeIndex(1)], 3), vec![NodeIndex(3)]);
+        member.on_peer_event(PeerEvent::Connected(NodeIndex(1)));
+        assert_eq!(member.choose_peers(&[NodeIndex(1)], 1), vec![NodeIndex(1)]);
+    }
+
+    #[test]
+    fn falls_back_to_all_peers_when_none_is_reachable() {
+        let mut member = member(NodeIndex(0), NodeCount(3));
+        member.on_peer_event(PeerEvent::Disconnected(NodeIndex(1)));
+        member.on_send_failure(None, SendError::PeerUnreachable(vec![NodeIndex(2)]));
+        let peers: HashSet<_> = member.choose_peers(&[], 2).into_iter().collect();
+        assert_eq!(
+            peers,
+            [NodeIndex(1), NodeIndex(2)].iter().copied().collect()
+        );
+    }
+
+    #[test]
+    fn failed_peers_are_tried_again_after_a_timeout() {
+        let mut member = member(NodeIndex(0), NodeCount(4));
+        member.on_send_failure(None, SendError::PeerUnreachable(vec![NodeIndex(1)]));
+        assert!(!member.is_reachable(NodeIndex(1)));
+        let failed_at = member.clock.now() - FAILED_PEER_TIMEOUT;
+        member.failed_peers.insert(NodeIndex(1), failed_at);
+        assert!(member.is_reachable(NodeIndex(1)));
+        member.on_send_failure(None, SendError::PeerUnreachable(vec![NodeIndex(2)]));
+        assert!(!member.failed_peers.contains_key(&NodeIndex(1)));
+    }
+
+    #[test]
+    fn peers_with_later_units_are_likely_holders() {
+        let mut member = member(NodeIndex(0), NodeCount(4));
+        let coord = UnitCoord::new(3, NodeIndex(1));
+        assert_eq!(
+            member.likely_holders(&coord, &[NodeIndex(1)]),
+            vec![NodeIndex(1)]
+        );
+        member.on_unit_validated(NodeIndex(2), 3);
+        member.on_unit_validated(NodeIndex(3), 4);
+        member.on_unit_validated(NodeIndex(3), 2);
+        assert_eq!(
+            member.likely_holders(&coord, &[NodeIndex(1)]),
+            vec![NodeIndex(1), NodeIndex(3)]
+        );
+    }
+
+    #[test]
+    fn rebroadcasts_skip_peers_which_referenced_own_unit() {
+        let mut member = member(NodeIndex(1), NodeCount(4));
+        assert_eq!(
+            member.peers_missing_own_unit(3),
+            vec![NodeIndex(0), NodeIndex(2), NodeIndex(3)]
+        );
+        member.on_own_unit_referenced(3, NodeIndex(2));
+        member.on_own_unit_referenced(4, NodeIndex(0));
+        assert_eq!(
+            member.peers_missing_own_unit(3),
+            vec![NodeIndex(0), NodeIndex(3)]
+        );
+    }
+
+    fn own_unit(
+        creator: NodeIndex,
+        round: Round,
+    ) -> UncheckedSignedUnit<Hasher64, Data, Signature> {
+        let control_hash = ControlHash {
+            parents_mask: BoolNodeMap::with_capacity(4.into()),
+            combined_hash: 0.using_encoded(Hasher64::hash),
+        };
+        let pu = PreUnit::new(creator, round, control_hash);
+        let data = Data::new(UnitCoord::new(round, creator), 0);
+        UncheckedSigned::new(FullUnit::new(pu, data, 0), Signature {})
+    }
+
+    // Creates our units of rounds up to 5, referenced by all the peers but the silent NodeIndex(3),
+    // and returns the rounds of the units which are still rebroadcast.
+    fn rebroadcast_rounds(member: &mut TestMember) -> Vec<Round> {
+        for round in 0..=5 {
+            member.on_create(own_unit(NodeIndex(1), round));
+            member.on_own_unit_referenced(round, NodeIndex(0));
+            member.on_own_unit_referenced(round, NodeIndex(2));
+        }
+        member.on_own_unit_referenced(0, NodeIndex(0));
+        let mut rounds: Vec<_> = member.own_units_referenced_by.keys().copied().collect();
+        rounds.sort_unstable();
+        rounds
+    }
+
+    #[test]
+    fn own_units_are_rebroadcast_until_referenced_by_default() {
+        let mut member = member(NodeIndex(1), NodeCount(4));
+        assert_eq!(rebroadcast_rounds(&mut member), (0..=5).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn references_to_own_units_beyond_the_horizon_are_pruned() {
+        let mut member = member(NodeIndex(1), NodeCount(4));
+        member.config.delay_config.unit_rebroadcast_horizon = Some(3);
+        assert_eq!(rebroadcast_rounds(&mut member), (2..=5).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn unreachable_request_is_sent_to_other_peers() {
+        let (mut member, mut outgoing, _for_runway) =
+            member_with_outgoing(NodeIndex(0), NodeCount(4));
+        let coord = UnitCoord::new(3, NodeIndex(1));
+        member.on_request_coord(coord, vec![NodeIndex(1)]);
+        let (message, recipient) = outgoing.next().now_or_never().unwrap().unwrap();
+        assert_eq!(recipient, Recipient::Node(NodeIndex(1)));
+
+        member.on_send_failure(
+            Some(message),
+            SendError::PeerUnreachable(vec![NodeIndex(1)]),
+        );
+        let (message, recipient) = outgoing.next().now_or_never().unwrap().unwrap();
+        assert!(matches!(message, UnitMessage::RequestCoord(_, c) if c == coord));
+        match recipient {
+            Recipient::Nodes(nodes) => {
+                assert_eq!(nodes.len(), 1);
+                assert!(nodes[0] == NodeIndex(2) || nodes[0] == NodeIndex(3));
+            }
+            recipient => panic!("unexpected recipient {:?}", recipient),
+        }
+        assert_eq!(member.send_failures[NodeIndex(1)], 1);
+    }
+
+    #[test]
+    fn full_queue_defers_tasks() {
+        let (mut member, mut outgoing, _for_runway) =
+            member_with_outgoing(NodeIndex(0), NodeCount(4));
+        member.on_send_failure(None, SendError::QueueFull);
+        member.on_request_coord(UnitCoord::new(3, NodeIndex(1)), vec![NodeIndex(1)]);
+        assert!(outgoing.next().now_or_never().is_none());
+    }
+
+    #[test]
+    fn repeated_requests_fan_out_and_give_up() {
+        let (mut member, mut outgoing, mut for_runway) =
+            member_with_outgoing(NodeIndex(0), NodeCount(10));
+        member.config.delay_config.request_attempts_limit = Some(3);
+        member.config.delay_config.coord_request_delay = Arc::new(|_| Duration::ZERO);
+        let (abandoned_requests, mut abandoned) = mpsc::unbounded();
+        member.abandoned_requests = Some(abandoned_requests);
+        let coord = UnitCoord::new(3, NodeIndex(1));
+        member.on_request_coord(coord, vec![NodeIndex(1)]);
+        let recipients: Vec<_> = std::iter::from_fn(|| outgoing.next().now_or_never().flatten())
+            .map(|(_, recipient)| recipient)
+            .collect();
+        assert_eq!(recipients.len(), 3);
+        assert_eq!(recipients[0], Recipient::Node(NodeIndex(1)));
+        assert!(matches!(&recipients[1], Recipient::Nodes(nodes) if nodes.len() == 2));
+        assert!(matches!(&recipients[2], Recipient::Nodes(nodes) if nodes.len() == 4));
+        assert!(!member.not_resolved_coords.contains(&coord));
+        assert!(matches!(
+            for_runway.next().now_or_never().flatten(),
+            Some(RunwayNotificationIn::RequestAbandoned(Request::Coord(c))) if c == coord
+        ));
+        assert_eq!(
+            abandoned.next().now_or_never().flatten(),
+            Some(AbandonedRequest::Coord(3, NodeIndex(1)))
+        );
+    }
+
+    #[test]
+    fn abandoned_parents_requests_report_the_hash() {
+        let (mut member, _outgoing, _for_runway) = member_with_outgoing(NodeIndex(0), NodeCount(4));
+        member.config.delay_config.request_attempts_limit = Some(1);
+        member.config.delay_config.parent_request_delay = Arc::new(|_| Duration::ZERO);
+        let (abandoned_requests, mut abandoned) = mpsc::unbounded();
+        member.abandoned_requests = Some(abandoned_requests);
+        let u_hash = Hasher64::hash(b"unit");
+        member.on_request_parents(u_hash, Recipient::Node(NodeIndex(1)));
+        assert_eq!(
+            abandoned.next().now_or_never().flatten(),
+            Some(AbandonedRequest::Parents(u_hash))
+        );
+    }
+}
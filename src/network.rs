@@ -1,20 +1,48 @@
 use crate::{
     alerts::AlertMessage,
     member::UnitMessage,
-    nodes::NodeIndex,
+    nodes::{NodeCount, NodeIndex},
     signed::{PartialMultisignature, Signature},
-    Data, Hasher, Receiver, Sender,
+    Config, Data, Hasher, Receiver, Round, Sender,
 };
-use codec::{Decode, Encode};
-use futures::{channel::oneshot, FutureExt, StreamExt};
-use log::{error, info, warn};
-use std::fmt::Debug;
+use codec::{Compact, Decode, Encode, Error, Input};
+use futures::{
+    channel::{mpsc, oneshot},
+    FutureExt, StreamExt,
+};
+use log::{debug, error, info, warn};
+use std::{collections::HashMap, fmt::Debug, marker::PhantomData};
 
-/// A recipient of a message, either a specific node or everyone.
+/// A recipient of a message, either a specific node, a subset of nodes or everyone.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Recipient {
     Everyone,
     Node(NodeIndex),
+    /// The message should be delivered to every node in the list.
+    Nodes(Vec<NodeIndex>),
+}
+
+/// A change in the connectivity to a peer, as observed by the [`Network`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PeerEvent {
+    /// The peer became reachable.
+    Connected(NodeIndex),
+    /// The peer is no longer reachable.
+    Disconnected(NodeIndex),
+}
+
+/// The reason why [`Network::send`] failed to queue a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendError {
+    /// The message could not be queued for the given peers, e.g. because they are unknown or not
+    /// connected. It was queued for the remaining recipients, if any.
+    PeerUnreachable(Vec<NodeIndex>),
+    /// The outgoing queue is full, the message was dropped.
+    QueueFull,
+    /// The message is larger than the network can carry, it was dropped.
+    MessageTooLarge,
+    /// The network is no longer operational.
+    Closed,
 }
 
 /// Network represents an interface for sending and receiving NetworkData.
@@ -40,20 +68,32 @@ pub enum Recipient {
 /// Section 3.1.2 for a discussion of the required guarantees of this trait's implementation.
 #[async_trait::async_trait]
 pub trait Network<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature>: Send {
-    /// Send a message to a single node or everyone, depending on the value of the recipient
-    /// argument.
+    /// Send a message to a single node, some nodes or everyone, depending on the value of the
+    /// recipient argument. Returns `Ok(())` if the message was queued for all the recipients.
+    /// Errors are used by AlephBFT to send requests to other peers and to slow down, but
+    /// messages which were queued are not assumed to be delivered anyway.
     ///
     /// Note on the implementation: this function should be implemented in a non-blocking manner.
     /// Otherwise, the performance might be affected negatively or the execution may end up in a deadlock.
-    fn send(&self, data: NetworkData<H, D, S, MS>, recipient: Recipient);
+    fn send(&self, data: NetworkData<H, D, S, MS>, recipient: Recipient) -> Result<(), SendError>;
     /// Receive a message from the network.
     async fn next_event(&mut self) -> Option<NetworkData<H, D, S, MS>>;
+    /// Called once when the session starts. Networks which track the connectivity to peers may
+    /// return a stream of [`PeerEvent`]s, which is then used to send requests and rebroadcasts
+    /// only to reachable peers. All peers are assumed to be reachable until reported otherwise.
+    fn peer_events(&mut self) -> Option<mpsc::UnboundedReceiver<PeerEvent>> {
+        None
+    }
 }
 
+/// The identifier of an application channel multiplexed over the consensus [`Network`].
+pub type ChannelId = u16;
+
 #[derive(Encode, Decode, Clone, Debug)]
 pub(crate) enum NetworkDataInner<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> {
     Units(UnitMessage<H, D, S>),
     Alert(AlertMessage<H, D, S, MS>),
+    Application(ChannelId, Vec<u8>),
 }
 
 impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> NetworkDataInner<H, D, S, MS> {
@@ -61,6 +101,7 @@ impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> NetworkDataInn
         match self {
             Self::Units(message) => message.included_data(),
             Self::Alert(message) => message.included_data(),
+            Self::Application(_, _) => Vec::new(),
         }
     }
 }
@@ -107,6 +148,210 @@ impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> NetworkData<H,
     pub fn included_data(&self) -> Vec<D> {
         self.0.included_data()
     }
+
+    /// Decodes NetworkData received from an untrusted source, rejecting it as soon as some part
+    /// of it exceeds the given [`DecodingLimits`]. Unlike [`Decode::decode`], this never
+    /// allocates space for more units, node maps entries or data bytes than the limits allow,
+    /// so it should be preferred by Network implementations before passing data to AlephBFT.
+    pub fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        Ok(Self(NetworkDataInner::decode_bounded(input, limits)?))
+    }
+}
+
+/// Bounds on the sizes of objects accepted by [`NetworkData::decode_bounded`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodingLimits {
+    /// The size of the committee. No list of units or node map may be longer than that.
+    pub n_members: NodeCount,
+    /// The maximal round of a unit. No alert may commit to more than `max_round + 1` units.
+    pub max_round: Round,
+    /// The maximal size in bytes of a single encoded data item or application message.
+    pub max_data_size: usize,
+}
+
+impl DecodingLimits {
+    /// Limits matching the committee size and the maximal round of the given configuration.
+    pub fn from_config(config: &Config, max_data_size: usize) -> Self {
+        DecodingLimits {
+            n_members: config.n_members,
+            max_round: config.max_round,
+            max_data_size,
+        }
+    }
+}
+
+/// Decoding which respects [`DecodingLimits`], implemented for all the types carried by
+/// [`NetworkData`] that contain collections or data items.
+pub(crate) trait DecodeBounded: Sized {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error>;
+}
+
+/// Decodes a vector of at most `max_len` elements, failing before any allocation if the encoded
+/// length is larger.
+pub(crate) fn decode_bounded_vec<T: DecodeBounded, I: Input>(
+    input: &mut I,
+    max_len: usize,
+    limits: &DecodingLimits,
+) -> Result<Vec<T>, Error> {
+    let len = <Compact<u32>>::decode(input)?.0 as usize;
+    if len > max_len {
+        return Err(Error::from("Encoded vector is longer than allowed."));
+    }
+    let mut result = Vec::with_capacity(len);
+    for _ in 0..len {
+        result.push(T::decode_bounded(input, limits)?);
+    }
+    Ok(result)
+}
+
+/// Decodes a data item, failing as soon as it tries to read more than `limits.max_data_size` bytes.
+pub(crate) fn decode_bounded_data<D: Data, I: Input>(
+    input: &mut I,
+    limits: &DecodingLimits,
+) -> Result<D, Error> {
+    D::decode(&mut LimitedInput {
+        input,
+        remaining: limits.max_data_size,
+    })
+}
+
+// An Input which reports at most `remaining` bytes left, so that the codec refuses to preallocate
+// more, and fails reads beyond that.
+struct LimitedInput<'a, I: Input> {
+    input: &'a mut I,
+    remaining: usize,
+}
+
+impl<'a, I: Input> Input for LimitedInput<'a, I> {
+    fn remaining_len(&mut self) -> Result<Option<usize>, Error> {
+        let remaining = match self.input.remaining_len()? {
+            Some(len) => len.min(self.remaining),
+            None => self.remaining,
+        };
+        Ok(Some(remaining))
+    }
+
+    fn read(&mut self, into: &mut [u8]) -> Result<(), Error> {
+        if into.len() > self.remaining {
+            return Err(Error::from("Encoded data item is larger than allowed."));
+        }
+        self.remaining -= into.len();
+        self.input.read(into)
+    }
+
+    fn descend_ref(&mut self) -> Result<(), Error> {
+        self.input.descend_ref()
+    }
+
+    fn ascend_ref(&mut self) {
+        self.input.ascend_ref()
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> DecodeBounded
+    for NetworkDataInner<H, D, S, MS>
+{
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        match input.read_byte()? {
+            0 => Ok(Self::Units(UnitMessage::decode_bounded(input, limits)?)),
+            1 => Ok(Self::Alert(AlertMessage::decode_bounded(input, limits)?)),
+            2 => {
+                let channel = ChannelId::decode(input)?;
+                let payload = decode_bounded_data(input, limits)?;
+                Ok(Self::Application(channel, payload))
+            }
+            _ => Err(Error::from("Invalid variant of NetworkData.")),
+        }
+    }
+}
+
+type ApplicationMessage = (ChannelId, Vec<u8>, Recipient);
+
+/// A registry of application channels, which are multiplexed together with the consensus traffic
+/// over the [`Network`] passed to [`run_session_with_extras`](crate::run_session_with_extras), see
+/// [`SessionExtras::with_application_channels`](crate::SessionExtras::with_application_channels).
+///
+/// Every channel carries messages of a single type, encoded with [`Encode`], and is identified by
+/// a [`ChannelId`] which has to be the same on all nodes. Messages for channels that were not
+/// registered on the receiving node are dropped.
+pub struct ApplicationChannels {
+    outgoing_tx: Sender<ApplicationMessage>,
+    outgoing_rx: Receiver<ApplicationMessage>,
+    incoming_txs: HashMap<ChannelId, Sender<Vec<u8>>>,
+}
+
+impl ApplicationChannels {
+    /// Creates a registry without any channels.
+    pub fn new() -> Self {
+        let (outgoing_tx, outgoing_rx) = mpsc::unbounded();
+        ApplicationChannels {
+            outgoing_tx,
+            outgoing_rx,
+            incoming_txs: HashMap::new(),
+        }
+    }
+
+    /// Registers a channel with the given id carrying messages of type `M`. Returns `None` if
+    /// a channel with this id is already registered.
+    pub fn register<M: Encode + Decode>(&mut self, id: ChannelId) -> Option<ApplicationChannel<M>> {
+        if self.incoming_txs.contains_key(&id) {
+            return None;
+        }
+        let (incoming_tx, incoming_rx) = mpsc::unbounded();
+        self.incoming_txs.insert(id, incoming_tx);
+        Some(ApplicationChannel {
+            id,
+            outgoing_tx: self.outgoing_tx.clone(),
+            incoming_rx,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl Default for ApplicationChannels {
+    fn default() -> Self {
+        ApplicationChannels::new()
+    }
+}
+
+/// Error returned when a message cannot be passed to the network, because the session has ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelClosed;
+
+/// The application side of a channel registered with [`ApplicationChannels::register`].
+pub struct ApplicationChannel<M: Encode + Decode> {
+    id: ChannelId,
+    outgoing_tx: Sender<ApplicationMessage>,
+    incoming_rx: Receiver<Vec<u8>>,
+    _phantom: PhantomData<M>,
+}
+
+impl<M: Encode + Decode> ApplicationChannel<M> {
+    /// The id of this channel.
+    pub fn id(&self) -> ChannelId {
+        self.id
+    }
+
+    /// Sends a message to the given recipient over the consensus network.
+    pub fn send(&self, message: M, recipient: Recipient) -> Result<(), ChannelClosed> {
+        self.outgoing_tx
+            .unbounded_send((self.id, message.encode(), recipient))
+            .map_err(|_| ChannelClosed)
+    }
+
+    /// Receives the next message from the network. Messages which fail to decode as `M` are
+    /// skipped. Returns `None` once the session has ended.
+    pub async fn next(&mut self) -> Option<M> {
+        loop {
+            let payload = self.incoming_rx.next().await?;
+            match M::decode(&mut &payload[..]) {
+                Ok(message) => return Some(message),
+                Err(e) => {
+                    warn!(target: "AlephBFT-network-hub", "Undecodable message on application channel {:?}: {:?}", self.id, e);
+                }
+            }
+        }
+    }
 }
 
 struct NetworkHub<
@@ -121,6 +366,9 @@ struct NetworkHub<
     units_received: Sender<UnitMessage<H, D, S>>,
     alerts_to_send: Receiver<(AlertMessage<H, D, S, MS>, Recipient)>,
     alerts_received: Sender<AlertMessage<H, D, S, MS>>,
+    application_messages_to_send: Receiver<ApplicationMessage>,
+    application_messages_received: HashMap<ChannelId, Sender<Vec<u8>>>,
+    events_for_member: Sender<NetworkEvent<H, D, S>>,
 }
 
 impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature, N: Network<H, D, S, MS>>
@@ -128,25 +376,66 @@ impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature, N: Network<H,
 {
     fn new(
         network: N,
-        units_to_send: Receiver<(UnitMessage<H, D, S>, Recipient)>,
-        units_received: Sender<UnitMessage<H, D, S>>,
-        alerts_to_send: Receiver<(AlertMessage<H, D, S, MS>, Recipient)>,
-        alerts_received: Sender<AlertMessage<H, D, S, MS>>,
+        network_io: NetworkIO<H, D, S, MS>,
+        application_channels: ApplicationChannels,
     ) -> Self {
+        // The sender kept by the registry is dropped here, so that the outgoing stream ends
+        // once the application drops all its channels.
+        let ApplicationChannels {
+            outgoing_rx,
+            incoming_txs,
+            ..
+        } = application_channels;
         NetworkHub {
             network,
-            units_to_send,
-            units_received,
-            alerts_to_send,
-            alerts_received,
+            units_to_send: network_io.units_to_send,
+            units_received: network_io.units_received,
+            alerts_to_send: network_io.alerts_to_send,
+            alerts_received: network_io.alerts_received,
+            application_messages_to_send: outgoing_rx,
+            application_messages_received: incoming_txs,
+            events_for_member: network_io.events_for_member,
+        }
+    }
+
+    fn send_event_to_member(&mut self, event: NetworkEvent<H, D, S>) {
+        if let Err(e) = self.events_for_member.unbounded_send(event) {
+            warn!(target: "AlephBFT-network-hub", "Error when sending network events to member {:?}", e);
         }
     }
 
     fn send(&self, data: NetworkData<H, D, S, MS>, recipient: Recipient) {
-        self.network.send(data, recipient);
+        if let Err(e) = self.network.send(data, recipient) {
+            debug!(target: "AlephBFT-network-hub", "Failed to send a message: {:?}.", e);
+        }
     }
 
-    fn handle_incoming(&self, network_data: NetworkData<H, D, S, MS>) {
+    fn send_units(&mut self, unit_message: UnitMessage<H, D, S>, recipient: Recipient) {
+        // Requests are small, so we keep a copy to let the member retry them elsewhere.
+        let request = unit_message.is_request().then(|| unit_message.clone());
+        let data = NetworkData(NetworkDataInner::Units(unit_message));
+        if let Err(e) = self.network.send(data, recipient) {
+            debug!(target: "AlephBFT-network-hub", "Failed to send a unit message: {:?}.", e);
+            self.send_event_to_member(NetworkEvent::SendFailed(request, e));
+        }
+    }
+
+    fn handle_incoming_application(&mut self, channel: ChannelId, payload: Vec<u8>) {
+        let incoming_txs = &mut self.application_messages_received;
+        match incoming_txs.get(&channel) {
+            Some(tx) => {
+                if tx.unbounded_send(payload).is_err() {
+                    debug!(target: "AlephBFT-network-hub", "Application channel {:?} closed.", channel);
+                    incoming_txs.remove(&channel);
+                }
+            }
+            None => {
+                debug!(target: "AlephBFT-network-hub", "Message for an unknown application channel {:?}.", channel);
+            }
+        }
+    }
+
+    fn handle_incoming(&mut self, network_data: NetworkData<H, D, S, MS>) {
         let NetworkData(network_data) = network_data;
         use NetworkDataInner::*;
         match network_data {
@@ -161,15 +450,23 @@ impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature, N: Network<H,
                     warn!(target: "AlephBFT-network-hub", "Error when sending alerts to consensus {:?}", e);
                 }
             }
+
+            Application(channel, payload) => self.handle_incoming_application(channel, payload),
         }
     }
 
     async fn run(mut self, mut exit: oneshot::Receiver<()>) {
+        // If the network does not report peer events, this stream terminates right away and
+        // is not polled afterwards.
+        let mut peer_events = self
+            .network
+            .peer_events()
+            .unwrap_or_else(|| mpsc::unbounded().1);
         loop {
             use NetworkDataInner::*;
-            futures::select! {
+            futures::select_biased! {
                 unit_message = self.units_to_send.next() => match unit_message {
-                    Some((unit_message, recipient)) => self.send(NetworkData(Units(unit_message)), recipient),
+                    Some((unit_message, recipient)) => self.send_units(unit_message, recipient),
                     None => {
                         error!(target: "AlephBFT-network-hub", "Outgoing units stream closed.");
                         break;
@@ -182,6 +479,10 @@ impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature, N: Network<H,
                         break;
                     }
                 },
+                // Ends when the application drops all its channels, which is not an error.
+                (channel, payload, recipient) = self.application_messages_to_send.select_next_some() => {
+                    self.send(NetworkData(Application(channel, payload)), recipient)
+                },
                 incoming_message = self.network.next_event().fuse() => match incoming_message {
                     Some(incoming_message) => self.handle_incoming(incoming_message),
                     None => {
@@ -189,6 +490,7 @@ impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature, N: Network<H,
                         break;
                     }
                 },
+                peer_event = peer_events.select_next_some() => self.send_event_to_member(NetworkEvent::Peer(peer_event)),
                 _ = &mut exit => break,
             }
         }
@@ -196,6 +498,21 @@ impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature, N: Network<H,
     }
 }
 
+pub(crate) struct NetworkIO<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> {
+    pub(crate) units_to_send: Receiver<(UnitMessage<H, D, S>, Recipient)>,
+    pub(crate) units_received: Sender<UnitMessage<H, D, S>>,
+    pub(crate) alerts_to_send: Receiver<(AlertMessage<H, D, S, MS>, Recipient)>,
+    pub(crate) alerts_received: Sender<AlertMessage<H, D, S, MS>>,
+    pub(crate) events_for_member: Sender<NetworkEvent<H, D, S>>,
+}
+
+/// Events observed by the network hub which concern the member.
+pub(crate) enum NetworkEvent<H: Hasher, D: Data, S: Signature> {
+    Peer(PeerEvent),
+    /// Sending a unit message failed, the message is included if it was a request.
+    SendFailed(Option<UnitMessage<H, D, S>>, SendError),
+}
+
 pub(crate) async fn run<
     H: Hasher,
     D: Data,
@@ -204,28 +521,22 @@ pub(crate) async fn run<
     N: Network<H, D, S, MS>,
 >(
     network: N,
-    units_to_send: Receiver<(UnitMessage<H, D, S>, Recipient)>,
-    units_received: Sender<UnitMessage<H, D, S>>,
-    alerts_to_send: Receiver<(AlertMessage<H, D, S, MS>, Recipient)>,
-    alerts_received: Sender<AlertMessage<H, D, S, MS>>,
+    network_io: NetworkIO<H, D, S, MS>,
+    application_channels: ApplicationChannels,
     exit: oneshot::Receiver<()>,
 ) {
-    NetworkHub::new(
-        network,
-        units_to_send,
-        units_received,
-        alerts_to_send,
-        alerts_received,
-    )
-    .run(exit)
-    .await
+    NetworkHub::new(network, network_io, application_channels)
+        .run(exit)
+        .await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        member::NewestUnitResponse,
         nodes::BoolNodeMap,
+        rmc,
         testing::mock::{self, Data, Hasher64, PartialMultisignature, Signature},
         units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit, UnitCoord},
         Round, UncheckedSigned,
@@ -397,6 +708,199 @@ mod tests {
         }
     }
 
+    fn test_limits() -> DecodingLimits {
+        DecodingLimits {
+            n_members: 7.into(),
+            max_round: 20,
+            max_data_size: 100,
+        }
+    }
+
+    fn test_network_data_samples() -> Vec<mock::NetworkData> {
+        use crate::alerts::{Alert as AlertT, AlertMessage::*};
+        use NetworkDataInner::{Alert, Application, Units};
+        use UnitMessage::*;
+
+        let uu = test_unchecked_unit(5.into(), 43, 1729);
+        let parents = (0..3)
+            .map(|i| test_unchecked_unit(i.into(), 42, 0))
+            .collect();
+        let alert = AlertT::new(
+            7.into(),
+            0,
+            (
+                test_unchecked_unit(2.into(), 10, 0),
+                test_unchecked_unit(2.into(), 10, 1),
+            ),
+            vec![test_unchecked_unit(2.into(), 11, 0)],
+        );
+        let newest_response = NewestUnitResponse {
+            requester: 7.into(),
+            responder: 5.into(),
+            unit: Some(uu.clone()),
+            salt: 1729,
+        };
+        let h = 43.using_encoded(Hasher64::hash);
+        vec![
+            NetworkData(Units(NewUnit(uu.clone()))),
+            NetworkData(Units(RequestCoord(7.into(), UnitCoord::new(3, 13.into())))),
+            NetworkData(Units(ResponseCoord(uu))),
+            NetworkData(Units(RequestParents(7.into(), h))),
+            NetworkData(Units(ResponseParents(h, parents))),
+            NetworkData(Units(RequestNewest(7.into(), 1729))),
+            NetworkData(Units(ResponseNewest(UncheckedSigned::new(
+                newest_response,
+                Signature {},
+            )))),
+            NetworkData(Alert(ForkAlert(UncheckedSigned::new(alert, Signature {})))),
+            NetworkData(Alert(RmcMessage(
+                7.into(),
+                rmc::Message::SignedHash(UncheckedSigned::new_with_index(
+                    crate::alerts::AlertHash::new(0, h),
+                    7.into(),
+                    Signature {},
+                )),
+            ))),
+            NetworkData(Alert(AlertRequest(7.into(), h))),
+            NetworkData(Application(3, vec![1, 7, 2, 9])),
+        ]
+    }
+
+    #[test]
+    fn registering_application_channel_twice_fails() {
+        let mut channels = ApplicationChannels::new();
+        assert!(channels.register::<u64>(3).is_some());
+        assert!(channels.register::<u64>(4).is_some());
+        assert!(channels.register::<Vec<u8>>(3).is_none());
+    }
+
+    #[test]
+    fn decoding_bounded_agrees_with_decoding() {
+        for nd in test_network_data_samples() {
+            let encoded = nd.encode();
+            let decoded = mock::NetworkData::decode_bounded(&mut &encoded[..], &test_limits())
+                .expect("data within limits should decode");
+            assert_eq!(decoded.encode(), encoded, "decoded should equal encoded");
+        }
+    }
+
+    #[test]
+    fn decoding_bounded_rejects_too_many_parents() {
+        use NetworkDataInner::Units;
+        use UnitMessage::ResponseParents;
+
+        let h = 43.using_encoded(Hasher64::hash);
+        let parents = (0..8)
+            .map(|i| test_unchecked_unit(i.into(), 42, 0))
+            .collect();
+        let nd = NetworkData::<Hasher64, Data, Signature, PartialMultisignature>(Units(
+            ResponseParents(h, parents),
+        ));
+        let encoded = nd.encode();
+        assert!(mock::NetworkData::decode_bounded(&mut &encoded[..], &test_limits()).is_err());
+        let limits = DecodingLimits {
+            n_members: 8.into(),
+            ..test_limits()
+        };
+        assert!(mock::NetworkData::decode_bounded(&mut &encoded[..], &limits).is_ok());
+    }
+
+    #[test]
+    fn decoding_bounded_rejects_too_many_alerted_units() {
+        use crate::alerts::{Alert as AlertT, AlertMessage::ForkAlert};
+        use NetworkDataInner::Alert;
+
+        let forker = 2.into();
+        let proof = (
+            test_unchecked_unit(forker, 0, 0),
+            test_unchecked_unit(forker, 0, 1),
+        );
+        let legit_units = (0..3)
+            .map(|round| test_unchecked_unit(forker, round, 0))
+            .collect();
+        let alert = AlertT::new(7.into(), 0, proof, legit_units);
+        let nd = NetworkData::<Hasher64, Data, Signature, PartialMultisignature>(Alert(ForkAlert(
+            UncheckedSigned::new(alert, Signature {}),
+        )));
+        let encoded = nd.encode();
+        let limits = DecodingLimits {
+            max_round: 1,
+            ..test_limits()
+        };
+        assert!(mock::NetworkData::decode_bounded(&mut &encoded[..], &limits).is_err());
+        let limits = DecodingLimits {
+            max_round: 2,
+            ..test_limits()
+        };
+        assert!(mock::NetworkData::decode_bounded(&mut &encoded[..], &limits).is_ok());
+    }
+
+    #[test]
+    fn decoding_bounded_rejects_too_large_committee() {
+        use NetworkDataInner::Units;
+        use UnitMessage::NewUnit;
+
+        let nd = NetworkData::<Hasher64, Data, Signature, PartialMultisignature>(Units(NewUnit(
+            test_unchecked_unit(5.into(), 43, 1729),
+        )));
+        let encoded = nd.encode();
+        let limits = DecodingLimits {
+            n_members: 6.into(),
+            ..test_limits()
+        };
+        assert!(mock::NetworkData::decode_bounded(&mut &encoded[..], &limits).is_err());
+    }
+
+    #[test]
+    fn decoding_bounded_rejects_too_large_multisignature() {
+        use crate::{
+            alerts::{AlertHash, AlertMessage::RmcMessage},
+            PartialMultisignature as _,
+        };
+        use NetworkDataInner::Alert;
+
+        let multisignature = (0..7).fold(PartialMultisignature::default(), |multisignature, i| {
+            multisignature.add_signature(&Signature {}, i.into())
+        });
+        let nd =
+            NetworkData::<Hasher64, Data, Signature, PartialMultisignature>(Alert(RmcMessage(
+                7.into(),
+                rmc::Message::MultisignedHash(UncheckedSigned::new(
+                    AlertHash::new(0, 0.using_encoded(Hasher64::hash)),
+                    multisignature,
+                )),
+            )));
+        let encoded = nd.encode();
+        assert!(mock::NetworkData::decode_bounded(&mut &encoded[..], &test_limits()).is_ok());
+        let limits = DecodingLimits {
+            n_members: 6.into(),
+            ..test_limits()
+        };
+        assert!(mock::NetworkData::decode_bounded(&mut &encoded[..], &limits).is_err());
+    }
+
+    #[test]
+    fn decoding_bounded_rejects_too_large_data() {
+        use NetworkDataInner::Units;
+        use UnitMessage::NewUnit;
+
+        let uu = test_unchecked_unit(5.into(), 43, 1729);
+        let data_size = uu.as_signable().data().encoded_size();
+        let nd =
+            NetworkData::<Hasher64, Data, Signature, PartialMultisignature>(Units(NewUnit(uu)));
+        let encoded = nd.encode();
+        let limits = DecodingLimits {
+            max_data_size: data_size - 1,
+            ..test_limits()
+        };
+        assert!(mock::NetworkData::decode_bounded(&mut &encoded[..], &limits).is_err());
+        let limits = DecodingLimits {
+            max_data_size: data_size,
+            ..test_limits()
+        };
+        assert!(mock::NetworkData::decode_bounded(&mut &encoded[..], &limits).is_ok());
+    }
+
     #[test]
     fn decoding_network_data_alert_fork_alert() {
         use AlertMessage::ForkAlert;
@@ -411,7 +915,7 @@ mod tests {
             lu1.as_signable().data().clone(),
             lu2.as_signable().data().clone(),
         ];
-        let alert = crate::alerts::Alert::new(7.into(), (f1, f2), vec![lu1, lu2]);
+        let alert = crate::alerts::Alert::new(7.into(), 0, (f1, f2), vec![lu1, lu2]);
 
         let nd = NetworkData::<Hasher64, Data, Signature, PartialMultisignature>(Alert(ForkAlert(
             UncheckedSigned::new(alert.clone(), Signature {}),
@@ -1,4 +1,5 @@
-use codec::{Decode, Encode, Error, Input, Output};
+use crate::network::{DecodeBounded, DecodingLimits};
+use codec::{Compact, Decode, Encode, Error, Input, Output};
 use derive_more::{Add, AddAssign, From, Into, Sub, SubAssign, Sum};
 use std::{
     iter::FromIterator,
@@ -113,7 +114,7 @@ impl<T> IndexMut<NodeIndex> for NodeMap<T> {
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub(crate) struct BoolNodeMap(bit_vec::BitVec<u32>);
 
-#[cfg(test)]
+#[cfg(any(test, feature = "bls"))]
 impl BoolNodeMap {
     pub(crate) fn with_capacity(capacity: NodeCount) -> Self {
         BoolNodeMap(bit_vec::BitVec::from_elem(capacity.0, false))
@@ -147,14 +148,29 @@ impl Encode for BoolNodeMap {
 impl Decode for BoolNodeMap {
     fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
         let capacity = u32::decode(input)? as usize;
-        let bytes = Vec::decode(input)?;
-        let mut bv = bit_vec::BitVec::from_bytes(&bytes);
-        // Length should be capacity rounded up to the closest multiple of 8
-        if bv.len() != 8 * ((capacity + 7) / 8) {
+        Self::decode_with_capacity(input, capacity)
+    }
+}
+
+impl BoolNodeMap {
+    fn decode_with_capacity<I: Input>(input: &mut I, capacity: usize) -> Result<Self, Error> {
+        // Length should be capacity rounded up to the closest multiple of 8. The capacity may come
+        // from an untrusted source, so we read the bytes in chunks and allocate only as much as
+        // the input actually contains.
+        let n_bytes = <Compact<u32>>::decode(input)?.0 as usize;
+        if n_bytes != (capacity + 7) / 8 {
             return Err(Error::from(
                 "Length of bitvector inconsistent with encoded capacity.",
             ));
         }
+        let mut bytes = Vec::new();
+        let mut chunk = [0u8; 256];
+        while bytes.len() < n_bytes {
+            let len = chunk.len().min(n_bytes - bytes.len());
+            input.read(&mut chunk[..len])?;
+            bytes.extend_from_slice(&chunk[..len]);
+        }
+        let mut bv = bit_vec::BitVec::from_bytes(&bytes);
         while bv.len() > capacity {
             if bv.pop() != Some(false) {
                 return Err(Error::from(
@@ -167,6 +183,18 @@ impl Decode for BoolNodeMap {
     }
 }
 
+impl DecodeBounded for BoolNodeMap {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let capacity = u32::decode(input)? as usize;
+        if capacity > limits.n_members.0 {
+            return Err(Error::from(
+                "BoolNodeMap capacity larger than the committee.",
+            ));
+        }
+        Self::decode_with_capacity(input, capacity)
+    }
+}
+
 impl FromIterator<bool> for BoolNodeMap {
     fn from_iter<T: IntoIterator<Item = bool>>(iter: T) -> Self {
         BoolNodeMap(bit_vec::BitVec::from_iter(iter))
@@ -185,7 +213,7 @@ impl Index<NodeIndex> for BoolNodeMap {
 mod tests {
 
     use crate::nodes::{BoolNodeMap, NodeIndex};
-    use codec::{Decode, Encode};
+    use codec::{Compact, Decode, Encode};
     #[test]
     fn decoding_node_index_works() {
         for i in 0..1000 {
@@ -228,6 +256,14 @@ mod tests {
         assert!(BoolNodeMap::decode(&mut encoded.as_slice()).is_err());
     }
 
+    #[test]
+    fn bool_node_map_decoding_does_not_trust_capacity() {
+        let mut encoded = u32::MAX.encode();
+        encoded.extend(Compact(u32::MAX / 8 + 1).encode());
+        encoded.extend([0u8; 4]);
+        assert!(BoolNodeMap::decode(&mut encoded.as_slice()).is_err());
+    }
+
     #[test]
     fn bool_node_map_decoding_deals_with_too_long_bitvec() {
         let mut encoded = vec![1, 0, 0, 0];
@@ -1,22 +1,23 @@
 //! Reliable MultiCast - a primitive for Reliable Broadcast protocol.
 use crate::{
+    network::{DecodeBounded, DecodingLimits},
     nodes::NodeCount,
     signed::{PartiallyMultisigned, Signable, Signed, UncheckedSigned},
-    Indexed, MultiKeychain, Multisigned, PartialMultisignature, Signature,
+    Clock, Indexed, MultiKeychain, Multisigned, PartialMultisignature, Signature, SystemClock,
 };
 use async_trait::async_trait;
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, Error, Input};
 use core::fmt::Debug;
 use futures::{
     channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
     FutureExt, StreamExt,
 };
-use futures_timer::Delay;
 use log::{debug, warn};
 use std::{
     cmp::Reverse,
     collections::{BinaryHeap, HashMap},
     hash::Hash,
+    sync::Arc,
     time,
     time::Duration,
 };
@@ -40,6 +41,20 @@ impl<H: Signable, S: Signature, M: PartialMultisignature> Message<H, S, M> {
     }
 }
 
+impl<H: Signable + Decode, S: Signature, M: PartialMultisignature> DecodeBounded
+    for Message<H, S, M>
+{
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        match input.read_byte()? {
+            0 => Ok(Message::SignedHash(UncheckedSigned::decode(input)?)),
+            1 => Ok(Message::MultisignedHash(
+                UncheckedSigned::decode_multisigned_bounded(input, limits)?,
+            )),
+            _ => Err(Error::from("Invalid variant of rmc::Message.")),
+        }
+    }
+}
+
 /// A task of brodcasting a message.
 #[derive(Clone)]
 pub enum Task<H: Signable, MK: MultiKeychain> {
@@ -74,8 +89,8 @@ impl<T> ScheduledTask<T> {
 struct IndexedInstant(time::Instant, usize);
 
 impl IndexedInstant {
-    fn now(i: usize) -> Self {
-        let curr_time = time::Instant::now();
+    fn now(clock: &dyn Clock, i: usize) -> Self {
+        let curr_time = clock.now();
         IndexedInstant(curr_time, i)
     }
 }
@@ -92,10 +107,16 @@ pub struct DoublingDelayScheduler<T> {
     scheduled_tasks: Vec<ScheduledTask<T>>,
     on_new_task_tx: UnboundedSender<T>,
     on_new_task_rx: UnboundedReceiver<T>,
+    clock: Arc<dyn Clock>,
 }
 
 impl<T> DoublingDelayScheduler<T> {
     pub fn new(initial_delay: time::Duration) -> Self {
+        Self::with_clock(initial_delay, Arc::new(SystemClock))
+    }
+
+    /// Like [`DoublingDelayScheduler::new`], but takes the time from the given clock.
+    pub fn with_clock(initial_delay: time::Duration, clock: Arc<dyn Clock>) -> Self {
         let (on_new_task_tx, on_new_task_rx) = unbounded();
         DoublingDelayScheduler {
             initial_delay,
@@ -103,6 +124,7 @@ impl<T> DoublingDelayScheduler<T> {
             scheduled_tasks: Vec::new(),
             on_new_task_tx,
             on_new_task_rx,
+            clock,
         }
     }
 }
@@ -118,22 +140,22 @@ impl<T: Send + Sync + Clone> TaskScheduler<T> for DoublingDelayScheduler<T> {
     async fn next_task(&mut self) -> Option<T> {
         let mut delay: futures::future::Fuse<_> = match self.scheduled_instants.peek() {
             Some(&Reverse(IndexedInstant(instant, _))) => {
-                let now = time::Instant::now();
+                let now = self.clock.now();
                 if now > instant {
-                    Delay::new(Duration::new(0, 0)).fuse()
+                    self.clock.sleep(Duration::new(0, 0)).fuse()
                 } else {
-                    Delay::new(instant - now).fuse()
+                    self.clock.sleep(instant - now).fuse()
                 }
             }
             None => futures::future::Fuse::terminated(),
         };
         // wait until either the scheduled time of the peeked task or a next call of add_task
-        futures::select! {
+        futures::select_biased! {
             _ = delay => {},
             task = self.on_new_task_rx.next() => {
                 if let Some(task) = task {
                     let i = self.scheduled_tasks.len();
-                    let indexed_instant = IndexedInstant::now(i);
+                    let indexed_instant = IndexedInstant::now(self.clock.as_ref(), i);
                     self.scheduled_instants.push(Reverse(indexed_instant));
                     let scheduled_task = ScheduledTask::new(task, self.initial_delay);
                     self.scheduled_tasks.push(scheduled_task);
@@ -160,11 +182,11 @@ impl<T: Send + Sync + Clone> TaskScheduler<T> for DoublingDelayScheduler<T> {
 
 /// Reliable Multicast Box
 ///
-/// The instance of [`ReliableMulticast<'a, H, MK>`] reliably broadcasts hashes of type `H`,
-/// and when a hash is successfully broadcasted, the multisigned hash `Multisigned<'a, H, MK>`
+/// The instance of [`ReliableMulticast<H, MK>`] reliably broadcasts hashes of type `H`,
+/// and when a hash is successfully broadcasted, the multisigned hash `Multisigned<H, MK>`
 /// is asynchronously returned.
 ///
-/// A node with an instance of [`ReliableMulticast<'a, H, MK>`] can initiate broadcasting
+/// A node with an instance of [`ReliableMulticast<H, MK>`] can initiate broadcasting
 /// a message `msg: H` by calling the [`ReliableMulticast::start_rmc`] method. As a result,
 /// the node signs `msg` and starts broadcasting the signed message via the network.
 /// When sufficintly many nodes call [`ReliableMulticast::start_rmc`] with the same message `msg`
@@ -172,23 +194,26 @@ impl<T: Send + Sync + Clone> TaskScheduler<T> for DoublingDelayScheduler<T> {
 /// the multisigned message is yielded by the instance of [`ReliableMulticast`].
 /// The multisigned messages can be polled by calling [`ReliableMulticast::next_multisigned_hash`].
 ///
+/// Only the bytes returned by [`Signable::hash`] are signed, so they should identify the instance
+/// of the protocol the hash is multicast in, e.g. the session, as well as the hash itself.
+///
 /// We refer to the documentation https://cardinal-cryptography.github.io/AlephBFT/reliable_broadcast.html
 /// for a high-level description of this protocol and how it is used for fork alerts.
-pub struct ReliableMulticast<'a, H: Signable + Hash, MK: MultiKeychain> {
-    hash_states: HashMap<H, PartiallyMultisigned<'a, H, MK>>,
+pub struct ReliableMulticast<H: Signable + Hash, MK: MultiKeychain> {
+    hash_states: HashMap<H, PartiallyMultisigned<H, MK>>,
     network_rx: UnboundedReceiver<Message<H, MK::Signature, MK::PartialMultisignature>>,
     network_tx: UnboundedSender<Message<H, MK::Signature, MK::PartialMultisignature>>,
-    keychain: &'a MK,
+    keychain: MK,
     scheduler: Box<dyn TaskScheduler<Task<H, MK>>>,
-    multisigned_hashes_tx: UnboundedSender<Multisigned<'a, H, MK>>,
-    multisigned_hashes_rx: UnboundedReceiver<Multisigned<'a, H, MK>>,
+    multisigned_hashes_tx: UnboundedSender<Multisigned<H, MK>>,
+    multisigned_hashes_rx: UnboundedReceiver<Multisigned<H, MK>>,
 }
 
-impl<'a, H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> ReliableMulticast<'a, H, MK> {
+impl<H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> ReliableMulticast<H, MK> {
     pub fn new(
         network_rx: UnboundedReceiver<Message<H, MK::Signature, MK::PartialMultisignature>>,
         network_tx: UnboundedSender<Message<H, MK::Signature, MK::PartialMultisignature>>,
-        keychain: &'a MK,
+        keychain: MK,
         //kept for compatibility
         _node_count: NodeCount,
         scheduler: impl TaskScheduler<Task<H, MK>> + 'static,
@@ -208,7 +233,7 @@ impl<'a, H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> ReliableMul
     /// Initiate a new instance of RMC for `hash`.
     pub async fn start_rmc(&mut self, hash: H) {
         debug!(target: "AlephBFT-rmc", "starting rmc for {:?}", hash);
-        let signed_hash = Signed::sign_with_index(hash, self.keychain).await;
+        let signed_hash = Signed::sign_with_index(hash, &self.keychain).await;
 
         let message = Message::SignedHash(signed_hash.into_unchecked());
         self.handle_message(message.clone());
@@ -217,7 +242,7 @@ impl<'a, H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> ReliableMul
         self.scheduler.add_task(task);
     }
 
-    fn on_complete_multisignature(&mut self, multisigned: Multisigned<'a, H, MK>) {
+    fn on_complete_multisignature(&mut self, multisigned: Multisigned<H, MK>) {
         let hash = multisigned.as_signable().clone();
         self.hash_states.insert(
             hash,
@@ -240,7 +265,7 @@ impl<'a, H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> ReliableMul
             return;
         }
         match message {
-            Message::MultisignedHash(unchecked) => match unchecked.check_multi(self.keychain) {
+            Message::MultisignedHash(unchecked) => match unchecked.check_multi(&self.keychain) {
                 Ok(multisigned) => {
                     self.on_complete_multisignature(multisigned);
                 }
@@ -249,7 +274,7 @@ impl<'a, H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> ReliableMul
                 }
             },
             Message::SignedHash(unchecked) => {
-                let signed_hash = match unchecked.check(self.keychain) {
+                let signed_hash = match unchecked.check(&self.keychain) {
                     Ok(signed_hash) => signed_hash,
                     Err(_) => {
                         warn!(target: "AlephBFT-rmc", "Received a hash with a bad signature");
@@ -258,8 +283,8 @@ impl<'a, H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> ReliableMul
                 };
 
                 let new_state = match self.hash_states.remove(&hash) {
-                    None => signed_hash.into_partially_multisigned(self.keychain),
-                    Some(partial) => partial.add_signature(signed_hash, self.keychain),
+                    None => signed_hash.into_partially_multisigned(&self.keychain),
+                    Some(partial) => partial.add_signature(signed_hash, &self.keychain),
                 };
                 match new_state {
                     PartiallyMultisigned::Complete { multisigned } => {
@@ -281,7 +306,7 @@ impl<'a, H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> ReliableMul
     }
 
     /// Fetches final multisignature.
-    pub fn get_multisigned(&self, hash: &H) -> Option<Multisigned<'a, H, MK>> {
+    pub fn get_multisigned(&self, hash: &H) -> Option<Multisigned<H, MK>> {
         match self.hash_states.get(hash)? {
             PartiallyMultisigned::Complete { multisigned } => Some(multisigned.clone()),
             _ => None,
@@ -289,9 +314,9 @@ impl<'a, H: Signable + Hash + Eq + Clone + Debug, MK: MultiKeychain> ReliableMul
     }
 
     /// Perform underlying tasks until the multisignature for the hash of this instance is collected.
-    pub async fn next_multisigned_hash(&mut self) -> Multisigned<'a, H, MK> {
+    pub async fn next_multisigned_hash(&mut self) -> Multisigned<H, MK> {
         loop {
-            futures::select! {
+            futures::select_biased! {
                 multisigned_hash = self.multisigned_hashes_rx.next() => {
                     return multisigned_hash.expect("We own the tx, so it is not closed");
                 }
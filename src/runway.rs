@@ -1,28 +1,30 @@
-use std::{collections::HashSet, convert::TryFrom};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    convert::TryFrom,
+};
 
 use crate::{
     alerts::{self, Alert, AlertConfig, AlertMessage, ForkProof, ForkingNotification},
     consensus,
+    environment::{Clock, Environment},
     member::{NewestUnitResponse, UnitMessage},
     network::Recipient,
     nodes::NodeMap,
     units::{
         ControlHash, FullUnit, PreUnit, SignedUnit, UncheckedSignedUnit, Unit, UnitCoord, UnitStore,
     },
-    Config, Data, DataIO, Hasher, Index, MultiKeychain, NodeCount, NodeIndex, OrderedBatch,
-    Receiver, Round, Sender, SessionId, Signature, Signed, SpawnHandle, UncheckedSigned,
+    Config, Data, DataIO, Dissemination, Hasher, Index, MultiKeychain, NodeCount, NodeIndex,
+    OrderedBatch, Receiver, Round, Sender, SessionId, Signature, Signed, SpawnHandle,
+    UncheckedSigned, Verifier,
 };
 use futures::{
     channel::{mpsc, oneshot},
-    future::FusedFuture,
+    future::{Fuse, FusedFuture},
     pin_mut, FutureExt, StreamExt,
 };
 use log::{debug, error, info, trace, warn};
-use std::{
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher as _},
-    time::Duration,
-};
+use rand::Rng;
+use std::{sync::Arc, time::Duration};
 
 /// Type for incoming notifications: Runway to Consensus.
 #[derive(Clone, PartialEq)]
@@ -65,12 +67,41 @@ pub(crate) enum RunwayNotificationOut<H: Hasher, D: Data, S: Signature> {
     NewUnit(UncheckedSignedUnit<H, D, S>),
     Request(Request<H>, Recipient),
     Response(Response<H, D, S>, NodeIndex),
+    /// Our unit of the given round was referenced as a parent by the given node.
+    OwnUnitReferenced(Round, NodeIndex),
+    /// A unit received for the first time which should be relayed further.
+    RelayUnit(UncheckedSignedUnit<H, D, S>),
+    /// A received unit of the given round created by the given node passed validation.
+    UnitValidated(NodeIndex, Round),
 }
 
 pub(crate) enum RunwayNotificationIn<H: Hasher, D: Data, S: Signature> {
     NewUnit(UncheckedSignedUnit<H, D, S>),
     Request(Request<H>, NodeIndex),
     Response(Response<H, D, S>),
+    /// Our request was not resolved in the configured number of attempts.
+    RequestAbandoned(Request<H>),
+}
+
+// A batch of units sent for verification, with its id.
+type UnitBatch<H, D, S> = (u64, Vec<UncheckedSignedUnit<H, D, S>>);
+
+// Units after checking their signatures, the ones with wrong signatures as errors.
+type CheckedUnits<H, D, MK> =
+    Vec<Result<SignedUnit<H, D, MK>, UncheckedSignedUnit<H, D, <MK as Verifier>::Signature>>>;
+
+// A batch of units after checking their signatures, with its id.
+type CheckedBatch<H, D, MK> = (u64, CheckedUnits<H, D, MK>);
+
+// Received units waiting for their signatures to be verified together, and whether to relay them.
+type PendingUnits<H, D, S> = Vec<(UncheckedSignedUnit<H, D, S>, bool)>;
+
+// What to do with the units of a batch sent for verification once they are verified.
+enum PendingBatch<H: Hasher> {
+    // Units received on their own, together with whether to relay them.
+    Units(Vec<bool>),
+    // Parents of the unit with the given hash.
+    Parents(H::Hash),
 }
 
 impl<H: Hasher, D: Data, S: Signature> TryFrom<UnitMessage<H, D, S>>
@@ -102,7 +133,7 @@ impl<H: Hasher, D: Data, S: Signature> TryFrom<UnitMessage<H, D, S>>
     }
 }
 
-struct Runway<'a, H, D, MK, DP>
+struct Runway<H, D, MK, DP>
 where
     H: Hasher,
     D: Data,
@@ -115,8 +146,8 @@ where
     session_id: SessionId,
     n_members: NodeCount,
     threshold: NodeCount,
-    store: UnitStore<'a, H, D, MK>,
-    keybox: &'a MK,
+    store: UnitStore<H, D, MK>,
+    keybox: MK,
     alerts_for_alerter: Sender<Alert<H, D, MK::Signature>>,
     notifications_from_alerter: Receiver<ForkingNotification<H, D, MK::Signature>>,
     unit_messages_from_network: Receiver<RunwayNotificationIn<H, D, MK::Signature>>,
@@ -131,15 +162,29 @@ where
     starting_round_value: Round,
     newest_unit_responders: HashSet<NodeIndex>,
     salt: u64,
+    relay_units: bool,
+    pending_units: PendingUnits<H, D, MK::Signature>,
+    // Hashes of the received units waiting for their signatures to be verified, so that copies
+    // arriving from other peers in the meantime are not verified again.
+    units_in_verification: HashSet<H::Hash>,
+    verification_window: Duration,
+    units_for_verification: Vec<Sender<UnitBatch<H, D, MK::Signature>>>,
+    verified_units: Receiver<CheckedBatch<H, D, MK>>,
+    // Batches sent for verification, in the order of their ids starting from `next_verified_batch`.
+    pending_batches: VecDeque<PendingBatch<H>>,
+    // Batches verified before some of the earlier ones, which have to be processed first.
+    verified_batches: HashMap<u64, CheckedUnits<H, D, MK>>,
+    next_verified_batch: u64,
+    clock: Arc<dyn Clock>,
     exiting: bool,
 }
 
-struct RunwayConfig<'a, H: Hasher, D: Data, DP: DataIO<D>, MK: MultiKeychain> {
+struct RunwayConfig<H: Hasher, D: Data, DP: DataIO<D>, MK: MultiKeychain> {
     node_ix: NodeIndex,
     session_id: SessionId,
     n_members: NodeCount,
     max_round: Round,
-    keychain: &'a MK,
+    keychain: MK,
     data_io: DP,
     alerts_for_alerter: Sender<Alert<H, D, MK::Signature>>,
     notifications_from_alerter: Receiver<ForkingNotification<H, D, MK::Signature>>,
@@ -151,16 +196,21 @@ struct RunwayConfig<'a, H: Hasher, D: Data, DP: DataIO<D>, MK: MultiKeychain> {
     resolved_requests: Sender<Request<H>>,
     starting_round_sender: oneshot::Sender<Round>,
     salt: u64,
+    relay_units: bool,
+    verification_window: Duration,
+    units_for_verification: Vec<Sender<UnitBatch<H, D, MK::Signature>>>,
+    verified_units: Receiver<CheckedBatch<H, D, MK>>,
+    clock: Arc<dyn Clock>,
 }
 
-impl<'a, H, D, MK, DP> Runway<'a, H, D, MK, DP>
+impl<H, D, MK, DP> Runway<H, D, MK, DP>
 where
     H: Hasher,
     D: Data,
     MK: MultiKeychain,
     DP: DataIO<D>,
 {
-    fn new(config: RunwayConfig<'a, H, D, DP, MK>) -> Self {
+    fn new(config: RunwayConfig<H, D, DP, MK>) -> Self {
         let n_members = config.n_members;
         let threshold = (n_members * 2) / 3 + NodeCount(1);
         let max_round = config.max_round;
@@ -189,6 +239,16 @@ where
             n_members: config.n_members,
             newest_unit_responders: HashSet::new(),
             salt: config.salt,
+            relay_units: config.relay_units,
+            pending_units: Vec::new(),
+            units_in_verification: HashSet::new(),
+            verification_window: config.verification_window,
+            units_for_verification: config.units_for_verification,
+            verified_units: config.verified_units,
+            pending_batches: VecDeque::new(),
+            verified_batches: HashMap::new(),
+            next_verified_batch: 0,
+            clock: config.clock,
             exiting: false,
         }
     }
@@ -201,7 +261,7 @@ where
         match message {
             RunwayNotificationIn::NewUnit(u) => {
                 trace!(target: "AlephBFT-runway", "{:?} New unit received {:?}.", self.index(), &u);
-                self.on_unit_received(u, false)
+                self.on_new_unit_received(u)
             }
             RunwayNotificationIn::Request(request, node_id) => match request {
                 Request::Coord(coord) => {
@@ -220,7 +280,7 @@ where
             RunwayNotificationIn::Response(res) => match res {
                 Response::Coord(u) => {
                     trace!(target: "AlephBFT-runway", "{:?} Fetch response received {:?}.", self.index(), &u);
-                    self.on_unit_received(u, false)
+                    self.queue_unit(u, false)
                 }
                 Response::Parents(u_hash, parents) => {
                     trace!(target: "AlephBFT-runway", "{:?} Response parents received {:?}.", self.index(), u_hash);
@@ -232,6 +292,22 @@ where
                     self.on_newest_response(response);
                 }
             },
+            RunwayNotificationIn::RequestAbandoned(request) => self.on_request_abandoned(request),
+        }
+    }
+
+    fn on_request_abandoned(&mut self, request: Request<H>) {
+        // Forgetting the request lets us request the data again if it turns out to be missing later.
+        match request {
+            Request::Coord(coord) => {
+                debug!(target: "AlephBFT-runway", "{:?} Request for coord {:?} abandoned.", self.index(), coord);
+                self.missing_coords.remove(&coord);
+            }
+            Request::Parents(u_hash) => {
+                debug!(target: "AlephBFT-runway", "{:?} Request for parents abandoned.", self.index());
+                self.missing_parents.remove(&u_hash);
+            }
+            Request::NewestUnit(_) => {}
         }
     }
 
@@ -246,6 +322,112 @@ where
         }
     }
 
+    fn on_new_unit_received(&mut self, uu: UncheckedSignedUnit<H, D, MK::Signature>) {
+        let hash = uu.as_signable().hash();
+        // Units reach us from many peers, especially when gossiped, but one copy is enough.
+        if self.store.contains_hash(&hash) || !self.units_in_verification.insert(hash) {
+            trace!(target: "AlephBFT-runway", "{:?} Ignoring a copy of a known unit {:?}.", self.index(), hash);
+            return;
+        }
+        self.queue_unit(uu, self.relay_units)
+    }
+
+    fn queue_unit(&mut self, uu: UncheckedSignedUnit<H, D, MK::Signature>, relay: bool) {
+        self.pending_units.push((uu, relay));
+        if self.verification_window == Duration::from_secs(0) {
+            self.on_verification_window_end();
+        }
+    }
+
+    fn on_verification_window_end(&mut self) {
+        let (units, relay): (Vec<_>, Vec<_>) = self.pending_units.drain(..).unzip();
+        self.send_for_verification(units, PendingBatch::Units(relay));
+    }
+
+    fn send_for_verification(
+        &mut self,
+        units: Vec<UncheckedSignedUnit<H, D, MK::Signature>>,
+        batch: PendingBatch<H>,
+    ) {
+        let id = self.next_verified_batch + self.pending_batches.len() as u64;
+        let worker = (id % self.units_for_verification.len() as u64) as usize;
+        trace!(target: "AlephBFT-runway", "{:?} Sending a batch of {} units for verification.", self.index(), units.len());
+        if self.units_for_verification[worker]
+            .unbounded_send((id, units))
+            .is_err()
+        {
+            warn!(target: "AlephBFT-runway", "{:?} Channel to verification worker should be open", self.index());
+            self.exiting = true;
+            return;
+        }
+        self.pending_batches.push_back(batch);
+    }
+
+    fn on_verified_units(&mut self, id: u64, checked: CheckedUnits<H, D, MK>) {
+        self.verified_batches.insert(id, checked);
+        // Batches are processed in the order in which they were sent, regardless of which worker
+        // verified them first.
+        while let Some(checked) = self.verified_batches.remove(&self.next_verified_batch) {
+            self.next_verified_batch += 1;
+            let batch = self
+                .pending_batches
+                .pop_front()
+                .expect("every verified batch was sent for verification");
+            for unit in &checked {
+                let hash = match unit {
+                    Ok(su) => su.as_signable().hash(),
+                    Err(uu) => uu.as_signable().hash(),
+                };
+                self.units_in_verification.remove(&hash);
+            }
+            let units = checked
+                .into_iter()
+                .map(|checked| match checked {
+                    Ok(su) => self.validate_signed_unit(su),
+                    Err(uu) => {
+                        warn!(target: "AlephBFT-runway", "{:?} Wrong signature received {:?}.", self.index(), &uu);
+                        None
+                    }
+                })
+                .collect();
+            match batch {
+                PendingBatch::Units(relay) => self.on_validated_units(units, relay),
+                PendingBatch::Parents(u_hash) => self.on_validated_parents(u_hash, units),
+            }
+        }
+    }
+
+    fn on_validated_units(&mut self, units: Vec<Option<SignedUnit<H, D, MK>>>, relay: Vec<bool>) {
+        for (su, relay) in units.into_iter().zip(relay) {
+            if let Some(su) = &su {
+                let full_unit = su.as_signable();
+                self.send_message_for_network(RunwayNotificationOut::UnitValidated(
+                    full_unit.creator(),
+                    full_unit.round(),
+                ));
+            }
+            match (su, relay) {
+                (Some(su), true) => self.add_unit_to_store_and_relay(su),
+                (Some(su), false) => self.add_unit_to_store_unless_fork(su),
+                (None, _) => {}
+            }
+        }
+    }
+
+    fn add_unit_to_store_and_relay(&mut self, su: SignedUnit<H, D, MK>) {
+        let hash = su.as_signable().hash();
+        if self.store.contains_hash(&hash) {
+            // We have seen this unit already, so we have relayed it already if needed.
+            return;
+        }
+        let uu = su.clone().into();
+        self.add_unit_to_store_unless_fork(su);
+        // Units which did not make it to the store, e.g. forks, are not relayed.
+        if self.store.contains_hash(&hash) {
+            self.send_message_for_network(RunwayNotificationOut::RelayUnit(uu));
+        }
+    }
+
     fn resolve_missing_coord(&mut self, coord: &UnitCoord) {
         if self.missing_coords.remove(coord) {
             self.send_resolved_request_notification(Request::Coord(*coord));
@@ -256,14 +438,17 @@ where
     fn validate_unit(
         &self,
         uu: UncheckedSignedUnit<H, D, MK::Signature>,
-    ) -> Option<SignedUnit<'a, H, D, MK>> {
-        let su = match uu.check(self.keybox) {
-            Ok(su) => su,
+    ) -> Option<SignedUnit<H, D, MK>> {
+        match uu.check(&self.keybox) {
+            Ok(su) => self.validate_signed_unit(su),
             Err(uu) => {
                 warn!(target: "AlephBFT-runway", "{:?} Wrong signature received {:?}.", self.index(), &uu);
-                return None;
+                None
             }
-        };
+        }
+    }
+
+    fn validate_signed_unit(&self, su: SignedUnit<H, D, MK>) -> Option<SignedUnit<H, D, MK>> {
         let full_unit = su.as_signable();
         if full_unit.session_id() != self.session_id {
             // NOTE: this implies malicious behavior as the unit's session_id
@@ -286,7 +471,7 @@ where
         Some(su)
     }
 
-    fn add_unit_to_store_unless_fork(&mut self, su: SignedUnit<'a, H, D, MK>) {
+    fn add_unit_to_store_unless_fork(&mut self, su: SignedUnit<H, D, MK>) {
         let full_unit = su.as_signable();
         trace!(target: "AlephBFT-member", "{:?} Adding member unit to store {:?}", self.index(), full_unit);
         if self.store.is_forker(full_unit.creator()) {
@@ -304,9 +489,52 @@ where
             // There is no point in keeping this unit in any kind of buffer.
             return;
         }
+        let round = full_unit.round();
+        let creator = full_unit.creator();
+        if round > 0
+            && creator != self.index()
+            && !self.store.contains_hash(&full_unit.hash())
+            && full_unit
+                .control_hash()
+                .parents()
+                .any(|p| p == self.index())
+        {
+            self.send_message_for_network(RunwayNotificationOut::OwnUnitReferenced(
+                round - 1,
+                creator,
+            ));
+        }
         self.store.add_unit(su, false);
     }
 
+    // Returns the creator of the unit at `coord` followed by the creators of units in our store
+    // which reference it as a parent, all of which should be able to provide the unit.
+    fn known_holders(&self, coord: UnitCoord) -> Vec<NodeIndex> {
+        let mut holders = vec![coord.creator()];
+        let next_round = match coord.round().checked_add(1) {
+            Some(round) => round,
+            None => return holders,
+        };
+        holders.extend(
+            (0..self.n_members.0)
+                .map(NodeIndex)
+                .filter(|node_id| *node_id != coord.creator())
+                .filter_map(|node_id| {
+                    self.store
+                        .unit_by_coord(UnitCoord::new(next_round, node_id))
+                })
+                .map(|su| su.as_signable())
+                .filter(|full_unit| {
+                    full_unit
+                        .control_hash()
+                        .parents()
+                        .any(|p| p == coord.creator())
+                })
+                .map(|full_unit| full_unit.creator()),
+        );
+        holders
+    }
+
     fn validate_unit_parents(&self, su: &SignedUnit<H, D, MK>) -> bool {
         // NOTE: at this point we cannot validate correctness of the control hash, in principle it could be
         // just a random hash, but we still would not be able to deduce that by looking at the unit only.
@@ -354,6 +582,7 @@ where
     ) -> Alert<H, D, MK::Signature> {
         Alert::new(
             self.node_ix,
+            self.session_id,
             proof,
             units.into_iter().map(|signed| signed.into()).collect(),
         )
@@ -412,7 +641,7 @@ where
             salt,
         };
 
-        let signed_response = Signed::sign(response, self.keybox).await.into_unchecked();
+        let signed_response = Signed::sign(response, &self.keybox).await.into_unchecked();
 
         if let Err(e) =
             self.unit_messages_for_network
@@ -429,6 +658,18 @@ where
         &mut self,
         u_hash: H::Hash,
         parents: Vec<UncheckedSignedUnit<H, D, MK::Signature>>,
+    ) {
+        if self.store.get_parents(u_hash).is_some() {
+            trace!(target: "AlephBFT-runway", "{:?} We got parents response but already know the parents.", self.index());
+            return;
+        }
+        self.send_for_verification(parents, PendingBatch::Parents(u_hash));
+    }
+
+    fn on_validated_parents(
+        &mut self,
+        u_hash: H::Hash,
+        parents: Vec<Option<SignedUnit<H, D, MK>>>,
     ) {
         if self.store.get_parents(u_hash).is_some() {
             trace!(target: "AlephBFT-runway", "{:?} We got parents response but already know the parents.", self.index());
@@ -456,8 +697,8 @@ where
         }
 
         let mut p_hashes_node_map: NodeMap<Option<H::Hash>> = NodeMap::new_with_len(self.n_members);
-        for (i, uu) in parents.into_iter().enumerate() {
-            let su = match self.validate_unit(uu) {
+        for (i, su) in parents.into_iter().enumerate() {
+            let su = match su {
                 None => {
                     warn!(target: "AlephBFT-runway", "{:?} In received parent response received a unit that does not pass validation.", self.index());
                     return;
@@ -499,7 +740,7 @@ where
             log::debug!(target: "AlephBFT-member", "Starting round already sent, ignoring newest unit response");
             return;
         }
-        let response = match unchecked_response.check(self.keybox) {
+        let response = match unchecked_response.check(&self.keybox) {
             Ok(checked) => checked.into_signable(),
             Err(e) => {
                 log::debug!(target: "AlephBFT-member", "incorrectly signed response: {:?}", e);
@@ -524,16 +765,16 @@ where
                 log::debug!(target: "AlephBFT-member", "Not our unit in a response:  {:?}", checked_unit.into_signable());
                 return;
             }
+            let starting_round_candidate = checked_unit.as_signable().round() + 1;
+            if starting_round_candidate > self.starting_round_value {
+                self.starting_round_value = starting_round_candidate;
+            }
             if self
                 .store
                 .unit_by_hash(&checked_unit.as_signable().hash())
                 .is_none()
             {
-                let starting_round_candidate = checked_unit.as_signable().round() + 1;
                 self.on_unit_received(checked_unit.into(), false);
-                if starting_round_candidate > self.starting_round_value {
-                    self.starting_round_value = starting_round_candidate;
-                }
             }
         }
         self.newest_unit_responders.insert(response.responder);
@@ -554,6 +795,13 @@ where
                 return;
             }
         };
+        // Our units may also reach us in other ways before the responses, e.g. as parents of the
+        // units we fetch, and creating units for their rounds again would be equivocating.
+        if let Some(unit) = self.store.newest_unit(self.index()) {
+            self.starting_round_value = self
+                .starting_round_value
+                .max(unit.as_signable().round() + 1);
+        }
         if starting_round_sender
             .send(self.starting_round_value)
             .is_err()
@@ -581,7 +829,7 @@ where
         let data = self.data_io.get_data();
         let full_unit = FullUnit::new(u, data, self.session_id);
         let hash: <H as Hasher>::Hash = full_unit.hash();
-        let signed_unit = Signed::sign(full_unit, self.keybox).await;
+        let signed_unit = Signed::sign(full_unit, &self.keybox).await;
         self.store.add_unit(signed_unit.clone(), false);
 
         trace!(target: "AlephBFT-runway", "{:?} Sending a unit {:?}.", self.index(), hash);
@@ -636,7 +884,7 @@ where
             if self.missing_coords.insert(coord) {
                 self.send_message_for_network(RunwayNotificationOut::Request(
                     Request::Coord(coord),
-                    Recipient::Node(coord.creator()),
+                    Recipient::Nodes(self.known_holders(coord)),
                 ));
             }
         }
@@ -737,11 +985,12 @@ where
             self.exiting = true
         };
 
-        let mut catch_up_delay = futures_timer::Delay::new(Duration::from_secs(5)).fuse();
+        let mut catch_up_delay = self.clock.sleep(Duration::from_secs(5)).fuse();
+        let mut verification_delay = Fuse::terminated();
 
         info!(target: "AlephBFT-runway", "{:?} Runway started.", index);
         loop {
-            futures::select! {
+            futures::select_biased! {
                 notification = self.rx_consensus.next() => match notification {
                         Some(notification) => self.on_consensus_notification(notification).await,
                         None => {
@@ -776,6 +1025,16 @@ where
                     }
                 },
 
+                _ = verification_delay => self.on_verification_window_end(),
+
+                verified = self.verified_units.next() => match verified {
+                    Some((id, checked)) => self.on_verified_units(id, checked),
+                    None => {
+                        error!(target: "AlephBFT-runway", "{:?} Verified units stream closed.", index);
+                        break;
+                    }
+                },
+
                 _ = catch_up_delay => {
                     self.after_catch_up_delay = true;
                     if self.is_starting_round_ready() {
@@ -788,6 +1047,9 @@ where
                     self.exiting = true;
                 }
             };
+            if !self.pending_units.is_empty() && verification_delay.is_terminated() {
+                verification_delay = self.clock.sleep(self.verification_window).fuse();
+            }
             self.move_units_to_consensus();
 
             if self.exiting {
@@ -800,6 +1062,31 @@ where
     }
 }
 
+// Checks the signatures of batches of units, until the channel of batches is closed.
+async fn verify_units<H: Hasher, D: Data, MK: MultiKeychain>(
+    keychain: MK,
+    mut units_for_verification: Receiver<UnitBatch<H, D, MK::Signature>>,
+    verified_units: Sender<CheckedBatch<H, D, MK>>,
+) {
+    while let Some((id, units)) = units_for_verification.next().await {
+        let checked = check_units(units, &keychain);
+        if verified_units.unbounded_send((id, checked)).is_err() {
+            debug!(target: "AlephBFT-runway", "Runway stopped, ending verification.");
+            return;
+        }
+    }
+}
+
+fn check_units<H: Hasher, D: Data, MK: MultiKeychain>(
+    units: Vec<UncheckedSignedUnit<H, D, MK::Signature>>,
+    keychain: &MK,
+) -> CheckedUnits<H, D, MK> {
+    UncheckedSigned::check_batch(units, keychain)
+        .into_iter()
+        .map(|checked| checked.map_err(|e| e.unchecked))
+        .collect()
+}
+
 pub(crate) struct RunwayIO<H: Hasher, D: Data, MK: MultiKeychain> {
     pub(crate) alert_messages_for_network: Sender<(
         AlertMessage<H, D, MK::Signature, MK::PartialMultisignature>,
@@ -817,6 +1104,7 @@ pub(crate) async fn run<H, D, MK, DP, SH>(
     keychain: MK,
     data_io: DP,
     spawn_handle: SH,
+    environment: Environment,
     runway_io: RunwayIO<H, D, MK>,
     mut exit: oneshot::Receiver<()>,
 ) where
@@ -835,6 +1123,7 @@ pub(crate) async fn run<H, D, MK, DP, SH>(
     let alert_config = AlertConfig {
         session_id: config.session_id,
         n_members: config.n_members,
+        clock: environment.clock(),
     };
     let (alerter_exit, exit_stream) = oneshot::channel();
     let alerter_keychain = keychain.clone();
@@ -857,6 +1146,7 @@ pub(crate) async fn run<H, D, MK, DP, SH>(
     let (consensus_exit, exit_stream) = oneshot::channel();
     let consensus_config = config.clone();
     let consensus_spawner = spawn_handle.clone();
+    let consensus_clock = environment.clock();
     let (starting_round_sender, starting_round) = oneshot::channel();
 
     let consensus_handle = spawn_handle.spawn_essential("runway/consensus", async move {
@@ -866,6 +1156,7 @@ pub(crate) async fn run<H, D, MK, DP, SH>(
             consensus_sink,
             ordered_batch_tx,
             consensus_spawner,
+            consensus_clock,
             starting_round,
             exit_stream,
         )
@@ -875,14 +1166,20 @@ pub(crate) async fn run<H, D, MK, DP, SH>(
 
     let index = config.node_ix;
 
-    let salt = {
-        let mut hasher = DefaultHasher::new();
-        std::time::Instant::now().hash(&mut hasher);
-        hasher.finish()
-    };
+    let salt = environment.rng("runway").gen();
+
+    let (verified_units_tx, verified_units) = mpsc::unbounded();
+    let mut units_for_verification = Vec::new();
+    for _ in 0..config.verification_workers.max(1) {
+        let (units_tx, units_rx) = mpsc::unbounded();
+        let worker = verify_units(keychain.clone(), units_rx, verified_units_tx.clone());
+        spawn_handle.spawn("runway/verifier", worker);
+        units_for_verification.push(units_tx);
+    }
+    drop(verified_units_tx);
 
     let runway_config = RunwayConfig {
-        keychain: &keychain,
+        keychain,
         data_io,
         alerts_for_alerter,
         notifications_from_alerter,
@@ -898,13 +1195,18 @@ pub(crate) async fn run<H, D, MK, DP, SH>(
         n_members: config.n_members,
         max_round: config.max_round,
         salt,
+        relay_units: matches!(config.dissemination, Dissemination::Gossip { .. }),
+        verification_window: config.delay_config.unit_verification_window,
+        units_for_verification,
+        verified_units,
+        clock: environment.clock(),
     };
     let (runway_exit, exit_stream) = oneshot::channel();
     let runway = Runway::new(runway_config);
     let runway_handle = runway.run(exit_stream).fuse();
     pin_mut!(runway_handle);
 
-    futures::select! {
+    futures::select_biased! {
         _ = runway_handle => {
             debug!(target: "AlephBFT-runway", "{:?} Runway task terminated early.", index);
         },
@@ -946,3 +1248,286 @@ pub(crate) async fn run<H, D, MK, DP, SH>(
 
     info!(target: "AlephBFT-runway", "{:?} Runway ended.", index);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_units, CheckedUnits, NotificationIn, Runway, RunwayConfig, RunwayNotificationOut,
+        UnitBatch,
+    };
+    use crate::{
+        environment::SystemClock,
+        member::NewestUnitResponse,
+        testing::mock::{Data, DataIO, Hasher64, KeyBox, Signature},
+        units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit, UnitCoord},
+        NodeCount, NodeIndex, NodeMap, Receiver, Round, Signed, UncheckedSigned,
+    };
+    use futures::{
+        channel::{mpsc, oneshot},
+        executor::block_on,
+        FutureExt, StreamExt,
+    };
+    use std::{sync::Arc, time::Duration};
+
+    const N_MEMBERS: NodeCount = NodeCount(4);
+
+    type TestRunway = Runway<Hasher64, Data, KeyBox, DataIO>;
+    type TestUnit = UncheckedSignedUnit<Hasher64, Data, Signature>;
+
+    // The ends of the channels of a Runway which the tests look at.
+    struct TestIO {
+        workers: Vec<Receiver<UnitBatch<Hasher64, Data, Signature>>>,
+        for_network: Receiver<RunwayNotificationOut<Hasher64, Data, Signature>>,
+        for_consensus: Receiver<NotificationIn<Hasher64>>,
+        starting_round: oneshot::Receiver<Round>,
+        // Kept so that the channels stay open.
+        _senders: Vec<Box<dyn std::any::Any>>,
+    }
+
+    fn runway(n_workers: usize, verification_window: Duration) -> (TestRunway, TestIO) {
+        let (alerts_for_alerter, alerts_from_runway) = mpsc::unbounded();
+        let (alerter_notifications, notifications_from_alerter) = mpsc::unbounded();
+        let (tx_consensus, for_consensus) = mpsc::unbounded();
+        let (consensus_notifications, rx_consensus) = mpsc::unbounded();
+        let (network_messages, unit_messages_from_network) = mpsc::unbounded();
+        let (unit_messages_for_network, for_network) = mpsc::unbounded();
+        let (ordered_batch_tx, ordered_batch_rx) = mpsc::unbounded();
+        let (resolved_requests, resolved_requests_rx) = mpsc::unbounded();
+        let (starting_round_sender, starting_round) = oneshot::channel();
+        let (verified_units_tx, verified_units) = mpsc::unbounded();
+        let (units_for_verification, workers) = (0..n_workers).map(|_| mpsc::unbounded()).unzip();
+        let runway = Runway::new(RunwayConfig {
+            node_ix: NodeIndex(0),
+            session_id: 0,
+            n_members: N_MEMBERS,
+            max_round: 100,
+            keychain: KeyBox::new(N_MEMBERS, NodeIndex(0)),
+            data_io: DataIO::new(NodeIndex(0)),
+            alerts_for_alerter,
+            notifications_from_alerter,
+            tx_consensus,
+            rx_consensus,
+            unit_messages_from_network,
+            unit_messages_for_network,
+            ordered_batch_rx,
+            resolved_requests,
+            starting_round_sender,
+            salt: 0,
+            relay_units: false,
+            verification_window,
+            units_for_verification,
+            verified_units,
+            clock: Arc::new(SystemClock),
+        });
+        let io = TestIO {
+            workers,
+            for_network,
+            for_consensus,
+            starting_round,
+            _senders: vec![
+                Box::new(alerts_from_runway),
+                Box::new(alerter_notifications),
+                Box::new(consensus_notifications),
+                Box::new(network_messages),
+                Box::new(ordered_batch_tx),
+                Box::new(resolved_requests_rx),
+                Box::new(verified_units_tx),
+            ],
+        };
+        (runway, io)
+    }
+
+    fn unit(creator: NodeIndex, round: Round, parents: &[&TestUnit]) -> TestUnit {
+        let mut parent_map = NodeMap::new_with_len(N_MEMBERS);
+        for parent in parents {
+            let parent = parent.as_signable();
+            parent_map[parent.creator()] = Some(parent.hash());
+        }
+        let full_unit = FullUnit::new(
+            PreUnit::new(creator, round, ControlHash::new(&parent_map)),
+            Data::new(UnitCoord::new(round, creator), 0),
+            0,
+        );
+        block_on(Signed::sign(full_unit, &KeyBox::new(N_MEMBERS, creator))).into()
+    }
+
+    fn newest_response(
+        responder: NodeIndex,
+        unit: Option<TestUnit>,
+    ) -> UncheckedSigned<NewestUnitResponse<Hasher64, Data, Signature>, Signature> {
+        let response = NewestUnitResponse {
+            requester: NodeIndex(0),
+            responder,
+            unit,
+            salt: 0,
+        };
+        block_on(Signed::sign(response, &KeyBox::new(N_MEMBERS, responder))).into()
+    }
+
+    // Does what a verification worker does with the next batch it got.
+    fn verify_next_batch(
+        runway: &TestRunway,
+        io: &mut TestIO,
+        worker: usize,
+    ) -> (u64, CheckedUnits<Hasher64, Data, KeyBox>) {
+        let (id, units) = io.workers[worker]
+            .next()
+            .now_or_never()
+            .flatten()
+            .expect("the worker got a batch");
+        (id, check_units(units, &runway.keybox))
+    }
+
+    fn validated_units(io: &mut TestIO) -> Vec<(NodeIndex, Round)> {
+        std::iter::from_fn(|| io.for_network.next().now_or_never().flatten())
+            .filter_map(|notification| match notification {
+                RunwayNotificationOut::UnitValidated(creator, round) => Some((creator, round)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn processes_batches_in_order_when_workers_finish_out_of_order() {
+        let (mut runway, mut io) = runway(2, Duration::from_secs(0));
+        let round_zero: Vec<_> = (0..N_MEMBERS.0)
+            .map(|creator| unit(NodeIndex(creator), 0, &[]))
+            .collect();
+        let parents: Vec<_> = round_zero.iter().collect();
+        let unit_one = unit(NodeIndex(1), 1, &parents);
+        let unit_one_hash = unit_one.as_signable().hash();
+        let unit_two = unit(NodeIndex(2), 1, &parents);
+
+        // Three batches, spread between the two workers: units, parents of the first unit, units.
+        runway.queue_unit(unit_one, false);
+        runway.on_parents_response(unit_one_hash, round_zero.clone());
+        runway.queue_unit(unit_two, false);
+        let (first, first_checked) = verify_next_batch(&runway, &mut io, 0);
+        let (second, second_checked) = verify_next_batch(&runway, &mut io, 1);
+        let (third, third_checked) = verify_next_batch(&runway, &mut io, 0);
+        assert_eq!((first, second, third), (0, 1, 2));
+
+        // The parents can only be accepted after their child is in the store, so they have to
+        // wait for the first batch, and so does the third one.
+        runway.on_verified_units(third, third_checked);
+        runway.on_verified_units(second, second_checked);
+        assert!(validated_units(&mut io).is_empty());
+        assert!(!runway.store.contains_hash(&unit_one_hash));
+        assert!(runway.store.get_parents(unit_one_hash).is_none());
+
+        runway.on_verified_units(first, first_checked);
+        assert_eq!(
+            validated_units(&mut io),
+            vec![(NodeIndex(1), 1), (NodeIndex(2), 1)]
+        );
+        assert!(runway.store.contains_hash(&unit_one_hash));
+        assert!(runway.store.get_parents(unit_one_hash).is_some());
+        assert!(
+            std::iter::from_fn(|| io.for_consensus.next().now_or_never().flatten()).any(
+                |notification| matches!(
+                    notification,
+                    NotificationIn::UnitParents(hash, parents)
+                        if hash == unit_one_hash && parents.len() == N_MEMBERS.0
+                )
+            )
+        );
+        assert!(runway.pending_batches.is_empty() && runway.verified_batches.is_empty());
+    }
+
+    #[test]
+    fn verifies_units_received_within_the_window_together() {
+        let (mut runway, mut io) = runway(2, Duration::from_secs(1));
+        let units: Vec<_> = (1..N_MEMBERS.0)
+            .map(|creator| unit(NodeIndex(creator), 0, &[]))
+            .collect();
+        for unit in units.iter().cloned() {
+            runway.queue_unit(unit, false);
+        }
+        assert!(io.workers[0].next().now_or_never().is_none());
+
+        runway.on_verification_window_end();
+        let (id, checked) = verify_next_batch(&runway, &mut io, 0);
+        assert_eq!(checked.len(), units.len());
+        assert!(runway.pending_units.is_empty());
+        runway.on_verified_units(id, checked);
+        assert_eq!(
+            validated_units(&mut io),
+            vec![(NodeIndex(1), 0), (NodeIndex(2), 0), (NodeIndex(3), 0)]
+        );
+    }
+
+    #[test]
+    fn verifies_units_at_once_without_a_window() {
+        let (mut runway, mut io) = runway(2, Duration::from_secs(0));
+        runway.queue_unit(unit(NodeIndex(1), 0, &[]), false);
+        runway.queue_unit(unit(NodeIndex(2), 0, &[]), false);
+        let (_, first) = verify_next_batch(&runway, &mut io, 0);
+        let (_, second) = verify_next_batch(&runway, &mut io, 1);
+        assert_eq!((first.len(), second.len()), (1, 1));
+    }
+
+    #[test]
+    fn verifies_copies_of_a_received_unit_once() {
+        let (mut runway, mut io) = runway(2, Duration::from_secs(0));
+        let received = unit(NodeIndex(1), 0, &[]);
+        runway.on_new_unit_received(received.clone());
+        runway.on_new_unit_received(received.clone());
+        let (id, checked) = verify_next_batch(&runway, &mut io, 0);
+        assert_eq!(checked.len(), 1);
+        assert!(io.workers[1].next().now_or_never().is_none());
+
+        // Once the unit is in the store, its copies are dropped as well.
+        runway.on_verified_units(id, checked);
+        assert_eq!(validated_units(&mut io), vec![(NodeIndex(1), 0)]);
+        runway.on_new_unit_received(received);
+        assert!(io.workers[1].next().now_or_never().is_none());
+        assert!(runway.units_in_verification.is_empty());
+    }
+
+    #[test]
+    fn verifies_copies_of_a_unit_again_after_a_wrong_signature() {
+        let (mut runway, mut io) = runway(1, Duration::from_secs(0));
+        let received = unit(NodeIndex(1), 0, &[]);
+        runway.on_new_unit_received(received.clone());
+        let (id, units) = io.workers[0]
+            .next()
+            .now_or_never()
+            .flatten()
+            .expect("the worker got a batch");
+        // The first copy might have come with a forged signature.
+        runway.on_verified_units(id, units.into_iter().map(Err).collect());
+        assert!(validated_units(&mut io).is_empty());
+
+        runway.on_new_unit_received(received);
+        let (id, checked) = verify_next_batch(&runway, &mut io, 0);
+        runway.on_verified_units(id, checked);
+        assert_eq!(validated_units(&mut io), vec![(NodeIndex(1), 0)]);
+    }
+
+    #[test]
+    fn starts_after_own_units_received_before_the_responses() {
+        let (mut runway, mut io) = runway(1, Duration::from_secs(0));
+        let round_zero: Vec<_> = (0..N_MEMBERS.0)
+            .map(|creator| unit(NodeIndex(creator), 0, &[]))
+            .collect();
+        let parents: Vec<_> = round_zero.iter().collect();
+        let own_unit = unit(NodeIndex(0), 1, &parents);
+
+        // Our newest unit arrives, e.g. as a parent of a fetched unit, before the responses do.
+        runway.on_unit_received(own_unit.clone(), false);
+        runway.after_catch_up_delay = true;
+        runway.on_newest_response(newest_response(NodeIndex(1), Some(own_unit)));
+        runway.on_newest_response(newest_response(NodeIndex(2), None));
+        assert_eq!(io.starting_round.try_recv(), Ok(Some(2)));
+    }
+
+    #[test]
+    fn starts_after_own_units_nobody_reported() {
+        let (mut runway, mut io) = runway(1, Duration::from_secs(0));
+        runway.on_unit_received(unit(NodeIndex(0), 0, &[]), false);
+        runway.after_catch_up_delay = true;
+        runway.on_newest_response(newest_response(NodeIndex(1), None));
+        runway.on_newest_response(newest_response(NodeIndex(2), None));
+        assert_eq!(io.starting_round.try_recv(), Ok(Some(1)));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
-    nodes::{NodeCount, NodeIndex, NodeMap},
-    Index,
+    network::{DecodeBounded, DecodingLimits},
+    nodes::{BoolNodeMap, NodeCount, NodeIndex, NodeMap},
+    Index, Round, SessionId,
 };
 use async_trait::async_trait;
 use codec::{Decode, Encode, Error, Input, Output};
@@ -13,23 +14,44 @@ pub trait Signature: Debug + Clone + Encode + Decode + Send + Sync + Eq + 'stati
 
 impl<T: Debug + Clone + Encode + Decode + Send + Sync + Eq + 'static> Signature for T {}
 
+/// Abstraction of verifying signatures.
+///
+/// A typical implementation of Verifier would be a collection of `N` public keys, and
+/// `verify(msg, s, j)` is to verify whether the signature s under the message msg is
+/// correct with respect to the public key of the jth node. Components which only check signed
+/// data, and never sign anything themselves, should require a Verifier rather than a [`KeyBox`].
+pub trait Verifier: Clone + Send + Sync + 'static {
+    type Signature: Signature;
+
+    /// Returns the total number of known public keys.
+    fn node_count(&self) -> NodeCount;
+    /// Verifies whether a node with `index` correctly signed the message `msg`.
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool;
+    /// Verifies many signatures at once, returning for each of them, in order, whether it is
+    /// correct. By default the signatures are verified one by one, implementations for signature
+    /// schemes with faster batch verification should override it.
+    fn verify_batch(&self, batch: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+        batch
+            .iter()
+            .map(|(msg, sgn, index)| self.verify(msg, sgn, *index))
+            .collect()
+    }
+}
+
 /// Abstraction of the signing data and verifying signatures.
 ///
 /// A typical implementation of KeyBox would be a collection of `N` public keys,
 /// an index `i` and a single private key corresponding to the public key number `i`.
 /// The meaning of sign is then to produce a signature `s` using the given private key,
-/// and `verify(msg, s, j)` is to verify whether the signature s under the message msg is
-/// correct with respect to the public key of the jth node.
+/// which the [`Verifier`] of any node accepts as a signature of the node number `i`.
 #[async_trait]
-pub trait KeyBox: Index + Clone + Send + Sync + 'static {
-    type Signature: Signature;
-
-    /// Returns the total number of known public keys.
-    fn node_count(&self) -> NodeCount;
+pub trait KeyBox: Index + Verifier {
     /// Signs a message `msg`.
+    ///
+    /// When `msg` is signed to create one of our units, [`unit_signing_round`] returns its session
+    /// and round, which implementations may use to refuse signing two different units of the
+    /// same round.
     async fn sign(&self, msg: &[u8]) -> Self::Signature;
-    /// Verifies whether a node with `index` correctly signed the message `msg`.
-    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool;
 }
 
 /// A type to which signatures can be aggregated.
@@ -37,28 +59,40 @@ pub trait KeyBox: Index + Clone + Send + Sync + 'static {
 /// Any signature can be added to multisignature.
 /// After adding sufficiently many signatures, the partial multisignature becomes a "complete"
 /// multisignature.
-/// Whether a multisignature is complete, can be verified with [`MultiKeychain::is_complete`] method.
+/// Whether a multisignature is complete, can be verified with [`MultiVerifier::is_complete`] method.
 /// The signature and the index passed to the `add_signature` method are required to be valid.
 pub trait PartialMultisignature: Signature {
     type Signature: Signature;
     /// Adds the signature.
     fn add_signature(self, signature: &Self::Signature, index: NodeIndex) -> Self;
+    /// Decodes a partial multisignature received from the network, failing as soon as it
+    /// exceeds the given [`DecodingLimits`], e.g. by having room for more signers than
+    /// `limits.n_members`. Implementations whose size depends on the committee should override
+    /// it, by default it is the same as [`Decode::decode`].
+    fn decode_bounded<I: Input>(input: &mut I, _limits: &DecodingLimits) -> Result<Self, Error> {
+        Self::decode(input)
+    }
 }
 
-/// Extends KeyBox with multisigning functionalities.
+/// Extends Verifier with verifying multisignatures.
 ///
-/// A single Signature can be rised to a Multisignature.
 /// Allows to verify whether a partial multisignature is complete (and valid).
-pub trait MultiKeychain: KeyBox {
+pub trait MultiVerifier: Verifier {
     type PartialMultisignature: PartialMultisignature<Signature = Self::Signature>;
+    /// Checks if enough signatures have beed added.
+    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool;
+}
+
+/// Extends KeyBox with multisigning functionalities.
+///
+/// A single Signature can be rised to a Multisignature.
+pub trait MultiKeychain: KeyBox + MultiVerifier {
     /// Transform a single signature to a multisignature consisting of the signature.
     fn from_signature(
         &self,
         signature: &Self::Signature,
         index: NodeIndex,
     ) -> Self::PartialMultisignature;
-    /// Checks if enough signatures have beed added.
-    fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool;
 }
 
 /// Data which can be signed.
@@ -79,10 +113,43 @@ impl<T: AsRef<[u8]> + Clone> Signable for T {
     }
 }
 
+/// The kind of a message signed by the protocol, prepended to the signed bytes, so that a
+/// signature of a message of one kind is never valid for a message of another kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub(crate) enum DomainTag {
+    /// Followed by [`UNIT_MAGIC`], the session id and the round of the unit, and its hash.
+    Unit,
+    NewestUnitResponse,
+    Alert,
+    AlertMulticast,
+}
+
+/// Follows the tag of the messages signed to create units, so that no message signed by other
+/// means, e.g. a hash signed in the reliable multicast, is mistaken for one by accident.
+const UNIT_MAGIC: [u8; 8] = *b"AlephBFT";
+
+/// The message signed to create the unit of the given session and round with the given hash.
+pub fn unit_signing_message(session_id: SessionId, round: Round, hash: &[u8]) -> Vec<u8> {
+    (DomainTag::Unit, UNIT_MAGIC, session_id, round, hash).encode()
+}
+
+/// Returns the session id and the round of the unit if `msg` is the message signed to create it,
+/// and `None` if `msg` is any other message signed by the protocol.
+pub fn unit_signing_round(msg: &[u8]) -> Option<(SessionId, Round)> {
+    let input = &mut &msg[..];
+    match <(DomainTag, [u8; 8], SessionId, Round, Vec<u8>)>::decode(input) {
+        // The message has to end right after the hash.
+        Ok((DomainTag::Unit, UNIT_MAGIC, session_id, round, _)) if input.is_empty() => {
+            Some((session_id, round))
+        }
+        _ => None,
+    }
+}
+
 /// A pair consisting of an instance of the `Signable` trait and an (arbitrary) signature.
 ///
 /// The method `[UncheckedSigned::check]` can be used to upgrade this `struct` to
-/// `[Signed<'a, T, KB>]` which ensures that the signature matches the signed object.
+/// `[Signed<T, KB>]` which ensures that the signature matches the signed object.
 #[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, Hash)]
 pub struct UncheckedSigned<T: Signable, S: Signature> {
     signable: T,
@@ -103,6 +170,32 @@ impl<T: Signable, S: Signature> UncheckedSigned<T, S> {
     }
 }
 
+impl<T: Signable + DecodeBounded, S: Signature> DecodeBounded for UncheckedSigned<T, S> {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let signable = T::decode_bounded(input, limits)?;
+        let signature = S::decode(input)?;
+        Ok(UncheckedSigned {
+            signable,
+            signature,
+        })
+    }
+}
+
+impl<T: Signable + Decode, MS: PartialMultisignature> UncheckedSigned<T, MS> {
+    /// Decodes multisigned data, bounding the multisignature by the given limits.
+    pub(crate) fn decode_multisigned_bounded<I: Input>(
+        input: &mut I,
+        limits: &DecodingLimits,
+    ) -> Result<Self, Error> {
+        let signable = T::decode(input)?;
+        let signature = MS::decode_bounded(input, limits)?;
+        Ok(UncheckedSigned {
+            signable,
+            signature,
+        })
+    }
+}
+
 impl<T: Signable, S: Signature> UncheckedSigned<Indexed<T>, S> {
     pub(crate) fn as_signable_strip_index(&self) -> &T {
         &self.signable.signable
@@ -140,19 +233,61 @@ pub struct SignatureError<T: Signable, S: Signature> {
 
 impl<T: Signable + Index, S: Signature> UncheckedSigned<T, S> {
     /// Verifies whether the signature matches the key with the index as in the signed data.
-    pub fn check<KB: KeyBox<Signature = S>>(
+    pub fn check<V: Verifier<Signature = S>>(
         self,
-        key_box: &KB,
-    ) -> Result<Signed<T, KB>, SignatureError<T, S>> {
+        verifier: &V,
+    ) -> Result<Signed<T, V>, SignatureError<T, S>> {
         let index = self.signable.index();
-        if !key_box.verify(self.signable.hash().as_ref(), &self.signature, index) {
+        if !verifier.verify(self.signable.hash().as_ref(), &self.signature, index) {
             return Err(SignatureError { unchecked: self });
         }
         Ok(Signed {
             unchecked: self,
-            marker: PhantomData,
+            proof: Verified::new(),
         })
     }
+
+    /// Verifies the signatures of many objects at once with [`Verifier::verify_batch`], as
+    /// [`UncheckedSigned::check`] does for a single one. The results are in the order of `batch`.
+    pub fn check_batch<V: Verifier<Signature = S>>(
+        batch: Vec<Self>,
+        verifier: &V,
+    ) -> Vec<Result<Signed<T, V>, SignatureError<T, S>>> {
+        let hashes: Vec<_> = batch
+            .iter()
+            .map(|unchecked| unchecked.signable.hash())
+            .collect();
+        let items: Vec<_> = batch
+            .iter()
+            .zip(hashes.iter())
+            .map(|(unchecked, hash)| {
+                (
+                    hash.as_ref(),
+                    &unchecked.signature,
+                    unchecked.signable.index(),
+                )
+            })
+            .collect();
+        let correct = verifier.verify_batch(&items);
+        assert_eq!(
+            correct.len(),
+            batch.len(),
+            "the verifier should return a result for every signature"
+        );
+        batch
+            .into_iter()
+            .zip(correct)
+            .map(|(unchecked, correct)| {
+                if !correct {
+                    return Err(SignatureError { unchecked });
+                }
+                Ok(Signed {
+                    unchecked,
+                    proof: Verified::new(),
+                })
+            })
+            .collect()
+    }
 }
 
 impl<T: Signable + Index, S: Signature> Index for UncheckedSigned<T, S> {
@@ -163,16 +298,16 @@ impl<T: Signable + Index, S: Signature> Index for UncheckedSigned<T, S> {
 
 impl<T: Signable, S: PartialMultisignature> UncheckedSigned<T, S> {
     /// Verifies whether the multisignature matches the signed data.
-    pub fn check_multi<MK: MultiKeychain<PartialMultisignature = S>>(
+    pub fn check_multi<MV: MultiVerifier<PartialMultisignature = S>>(
         self,
-        keychain: &MK,
-    ) -> Result<Multisigned<T, MK>, SignatureError<T, S>> {
-        if !(keychain.is_complete(self.signable.hash().as_ref(), &self.signature)) {
+        verifier: &MV,
+    ) -> Result<Multisigned<T, MV>, SignatureError<T, S>> {
+        if !(verifier.is_complete(self.signable.hash().as_ref(), &self.signature)) {
             return Err(SignatureError { unchecked: self });
         }
         Ok(Multisigned {
             unchecked: self,
-            marker: PhantomData,
+            proof: Verified::new(),
         })
     }
 }
@@ -192,28 +327,54 @@ impl<T: Signable, S: Signature> From<UncheckedSigned<Indexed<T>, S>> for Uncheck
     }
 }
 
+/// Proof that a signature was created, or checked, by a verifier of type `V`.
+///
+/// Only this module creates proofs, each time it signs or successfully checks an object, so every
+/// [`Signed`] or [`Multisigned`] holding one is correctly signed. The proof records only the type
+/// of the verifier, not the instance, so verifiers of the same type, e.g. keychains of different
+/// committees, produce indistinguishable proofs. It does not borrow the verifier.
+#[derive(Debug)]
+pub struct Verified<V> {
+    verifier: PhantomData<V>,
+}
+
+impl<V> Verified<V> {
+    fn new() -> Self {
+        Verified {
+            verifier: PhantomData,
+        }
+    }
+}
+
+impl<V> Clone for Verified<V> {
+    fn clone(&self) -> Self {
+        Verified::new()
+    }
+}
+
 /// A correctly signed object of type `T`.
 ///
-/// The correctness is guaranteed by storing a (phantom) reference to the `KeyBox` that verified
-/// the signature.
+/// Objects of this type can only be created by checking or creating a signature, with some
+/// verifier of type `V`, and hold the resulting [`Verified`] proof. They own their data, so they
+/// can be stored, cloned and sent to other tasks freely.
 #[derive(Debug)]
-pub struct Signed<'a, T: Signable + Index, KB: KeyBox> {
-    unchecked: UncheckedSigned<T, KB::Signature>,
-    marker: PhantomData<&'a KB>,
+pub struct Signed<T: Signable + Index, V: Verifier> {
+    unchecked: UncheckedSigned<T, V::Signature>,
+    proof: Verified<V>,
 }
 
-impl<'a, T: Signable + Clone + Index, KB: KeyBox> Clone for Signed<'a, T, KB> {
+impl<T: Signable + Clone + Index, V: Verifier> Clone for Signed<T, V> {
     fn clone(&self) -> Self {
         Signed {
             unchecked: self.unchecked.clone(),
-            marker: PhantomData,
+            proof: self.proof.clone(),
         }
     }
 }
 
-impl<'a, T: Signable + Index, KB: KeyBox> Signed<'a, T, KB> {
+impl<T: Signable + Index, KB: KeyBox> Signed<T, KB> {
     /// Create a signed object from a signable. The index of `signable` must match the index of the `key_box`.
-    pub async fn sign(signable: T, key_box: &'a KB) -> Signed<'a, T, KB> {
+    pub async fn sign(signable: T, key_box: &KB) -> Signed<T, KB> {
         assert_eq!(signable.index(), key_box.index());
         let signature = key_box.sign(signable.hash().as_ref()).await;
         Signed {
@@ -221,10 +382,12 @@ impl<'a, T: Signable + Index, KB: KeyBox> Signed<'a, T, KB> {
                 signable,
                 signature,
             },
-            marker: PhantomData,
+            proof: Verified::new(),
         }
     }
+}
 
+impl<T: Signable + Index, V: Verifier> Signed<T, V> {
     /// Get a reference to the signed object.
     pub fn as_signable(&self) -> &T {
         &self.unchecked.signable
@@ -234,22 +397,27 @@ impl<'a, T: Signable + Index, KB: KeyBox> Signed<'a, T, KB> {
         self.unchecked.signable
     }
 
-    pub(crate) fn into_unchecked(self) -> UncheckedSigned<T, KB::Signature> {
+    /// Get the proof that the signature was verified.
+    pub fn proof(&self) -> &Verified<V> {
+        &self.proof
+    }
+
+    pub(crate) fn into_unchecked(self) -> UncheckedSigned<T, V::Signature> {
         self.unchecked
     }
 }
 
-impl<'a, T: Signable, KB: KeyBox> Signed<'a, Indexed<T>, KB> {
+impl<T: Signable, KB: KeyBox> Signed<Indexed<T>, KB> {
     /// Create a signed object from a signable. The index is added based on the index of the `key_box`.
-    pub async fn sign_with_index(signable: T, key_box: &'a KB) -> Signed<'a, Indexed<T>, KB> {
+    pub async fn sign_with_index(signable: T, key_box: &KB) -> Signed<Indexed<T>, KB> {
         Signed::sign(Indexed::new(signable, key_box.index()), key_box).await
     }
 }
 
-impl<'a, T: Signable, MK: MultiKeychain> Signed<'a, Indexed<T>, MK> {
+impl<T: Signable, MK: MultiKeychain> Signed<Indexed<T>, MK> {
     /// Transform a singly signed object into a partially multisigned consisting of just the signed object.
     /// Note that depending on the setup, it may yield a complete signature.
-    pub fn into_partially_multisigned(self, keychain: &'a MK) -> PartiallyMultisigned<'a, T, MK> {
+    pub fn into_partially_multisigned(self, keychain: &MK) -> PartiallyMultisigned<T, MK> {
         let multisignature =
             keychain.from_signature(&self.unchecked.signature, self.unchecked.signable.index);
         let unchecked = UncheckedSigned {
@@ -260,7 +428,7 @@ impl<'a, T: Signable, MK: MultiKeychain> Signed<'a, Indexed<T>, MK> {
             PartiallyMultisigned::Complete {
                 multisigned: Multisigned {
                     unchecked,
-                    marker: PhantomData,
+                    proof: Verified::new(),
                 },
             }
         } else {
@@ -269,10 +437,8 @@ impl<'a, T: Signable, MK: MultiKeychain> Signed<'a, Indexed<T>, MK> {
     }
 }
 
-impl<'a, T: Signable + Index, KB: KeyBox> From<Signed<'a, T, KB>>
-    for UncheckedSigned<T, KB::Signature>
-{
-    fn from(signed: Signed<'a, T, KB>) -> Self {
+impl<T: Signable + Index, V: Verifier> From<Signed<T, V>> for UncheckedSigned<T, V::Signature> {
+    fn from(signed: Signed<T, V>) -> Self {
         signed.into_unchecked()
     }
 }
@@ -284,7 +450,7 @@ impl<'a, T: Signable + Index, KB: KeyBox> From<Signed<'a, T, KB>>
 /// implement the [`Index`] trait, it should use the `Signed::sign_with_index` method which will
 /// use this wrapper transparently. Note that in the implementation of `Signable` for `Indexed<T>`,
 /// the hash is the hash of the underlying data `T`. Therefore, instances of the type
-/// [`Signed<'a, Indexed<T>, MK>`] can be aggregated into `Multisigned<'a, T, MK>`
+/// [`Signed<Indexed<T>, MK>`] can be aggregated into `Multisigned<T, MK>`
 #[derive(Clone, Encode, Decode, Debug, PartialEq, Eq, Hash)]
 pub struct Indexed<T: Signable> {
     signable: T,
@@ -317,47 +483,52 @@ impl<T: Signable> Index for Indexed<T> {
 
 /// Signable data together with a complete multisignature.
 ///
-/// An instance of `Multisigned<'a, T: Signable, MK: MultiKeychain>` consists of a data of type `T`
-/// together with a multisignature which is valid and complete according to a multikeychain
-/// reference `&'a MK`. The lifetime parameter ensures that the data with a multisignature do not
-/// outlive the session.
+/// An instance of `Multisigned<T: Signable, MV: MultiVerifier>` consists of a data of type `T`
+/// together with a multisignature which is valid and complete according to a multiverifier of
+/// type `MV`, as its [`Verified`] proof shows. Like [`Signed`], it does not borrow the
+/// multiverifier, so it can be persisted and handed to other tasks.
 #[derive(Debug)]
-pub struct Multisigned<'a, T: Signable, MK: MultiKeychain> {
-    unchecked: UncheckedSigned<T, MK::PartialMultisignature>,
-    marker: PhantomData<&'a MK>,
+pub struct Multisigned<T: Signable, MV: MultiVerifier> {
+    unchecked: UncheckedSigned<T, MV::PartialMultisignature>,
+    proof: Verified<MV>,
 }
 
-impl<'a, T: Signable, MK: MultiKeychain> Multisigned<'a, T, MK> {
+impl<T: Signable, MV: MultiVerifier> Multisigned<T, MV> {
     /// Get a reference to the multisigned object.
     pub fn as_signable(&self) -> &T {
         &self.unchecked.signable
     }
 
-    pub fn into_unchecked(self) -> UncheckedSigned<T, MK::PartialMultisignature> {
+    /// Get the proof that the multisignature was verified.
+    pub fn proof(&self) -> &Verified<MV> {
+        &self.proof
+    }
+
+    pub fn into_unchecked(self) -> UncheckedSigned<T, MV::PartialMultisignature> {
         self.unchecked
     }
 }
 
-impl<'a, T: Signable, MK: MultiKeychain> From<Multisigned<'a, T, MK>>
-    for UncheckedSigned<T, MK::PartialMultisignature>
+impl<T: Signable, MV: MultiVerifier> From<Multisigned<T, MV>>
+    for UncheckedSigned<T, MV::PartialMultisignature>
 {
-    fn from(signed: Multisigned<'a, T, MK>) -> Self {
+    fn from(signed: Multisigned<T, MV>) -> Self {
         signed.into_unchecked()
     }
 }
 
-impl<'a, T: Signable + Clone, MK: MultiKeychain> Clone for Multisigned<'a, T, MK> {
+impl<T: Signable + Clone, MV: MultiVerifier> Clone for Multisigned<T, MV> {
     fn clone(&self) -> Self {
         Multisigned {
             unchecked: self.unchecked.clone(),
-            marker: self.marker,
+            proof: self.proof.clone(),
         }
     }
 }
 
 #[derive(Debug)]
-pub struct IncompleteMultisignatureError<'a, T: Signable, MK: MultiKeychain> {
-    pub partial: PartiallyMultisigned<'a, T, MK>,
+pub struct IncompleteMultisignatureError<T: Signable, MK: MultiKeychain> {
+    pub partial: PartiallyMultisigned<T, MK>,
 }
 
 /// Signable data together with a valid partial multisignature.
@@ -366,18 +537,18 @@ pub struct IncompleteMultisignatureError<'a, T: Signable, MK: MultiKeychain> {
 /// If the multisignature is complete, you can get [`Multisigned`] by pattern matching
 /// against the variant [`PartiallyMultisigned::Complete`].
 #[derive(Debug)]
-pub enum PartiallyMultisigned<'a, T: Signable, MK: MultiKeychain> {
+pub enum PartiallyMultisigned<T: Signable, MK: MultiKeychain> {
     Incomplete {
         unchecked: UncheckedSigned<T, MK::PartialMultisignature>,
     },
     Complete {
-        multisigned: Multisigned<'a, T, MK>,
+        multisigned: Multisigned<T, MK>,
     },
 }
 
-impl<'a, T: Signable, MK: MultiKeychain> PartiallyMultisigned<'a, T, MK> {
+impl<T: Signable, MK: MultiKeychain> PartiallyMultisigned<T, MK> {
     /// Create a partially multisigned object.
-    pub async fn sign(signable: T, keychain: &'a MK) -> PartiallyMultisigned<'a, T, MK> {
+    pub async fn sign(signable: T, keychain: &MK) -> PartiallyMultisigned<T, MK> {
         Signed::sign_with_index(signable, keychain)
             .await
             .into_partially_multisigned(keychain)
@@ -408,7 +579,7 @@ impl<'a, T: Signable, MK: MultiKeychain> PartiallyMultisigned<'a, T, MK> {
     }
 
     /// Adds a signature and checks if multisignature is complete.
-    pub fn add_signature(self, signed: Signed<'a, Indexed<T>, MK>, keychain: &'a MK) -> Self {
+    pub fn add_signature(self, signed: Signed<Indexed<T>, MK>, keychain: &MK) -> Self {
         if self.as_signable().hash().as_ref() != signed.as_signable().hash().as_ref() {
             warn!(target: "AlephBFT-signed", "Tried to add a signature of a different object");
             return self;
@@ -422,7 +593,7 @@ impl<'a, T: Signable, MK: MultiKeychain> PartiallyMultisigned<'a, T, MK> {
                     PartiallyMultisigned::Complete {
                         multisigned: Multisigned {
                             unchecked,
-                            marker: PhantomData,
+                            proof: Verified::new(),
                         },
                     }
                 } else {
@@ -440,28 +611,38 @@ pub struct SignatureSet<S: Signature> {
     signatures: NodeMap<Option<S>>,
 }
 
+// Encoded as the bitmap of the nodes whose signatures are present, followed by these signatures,
+// so that the size of the encoding depends on the number of signatures rather than the committee.
 impl<S: Signature> Encode for SignatureSet<S> {
     fn size_hint(&self) -> usize {
-        self.signatures.size_hint()
+        self.signers().size_hint()
+            + self
+                .signatures
+                .iter()
+                .flatten()
+                .map(Encode::size_hint)
+                .sum::<usize>()
     }
 
     fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
-        self.signatures.encode_to(dest);
-    }
-
-    fn encode(&self) -> Vec<u8> {
-        self.signatures.encode()
-    }
-
-    fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
-        self.signatures.using_encoded(f)
+        self.signers().encode_to(dest);
+        for signature in self.signatures.iter().flatten() {
+            signature.encode_to(dest);
+        }
     }
 }
 
 impl<S: Signature> Decode for SignatureSet<S> {
     fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
-        let signatures = NodeMap::decode(input)?;
-        Ok(SignatureSet { signatures })
+        let signers = BoolNodeMap::decode(input)?;
+        Self::decode_signatures(input, signers)
+    }
+}
+
+impl<S: Signature> DecodeBounded for SignatureSet<S> {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let signers = BoolNodeMap::decode_bounded(input, limits)?;
+        Self::decode_signatures(input, signers)
     }
 }
 
@@ -472,6 +653,27 @@ impl<S: Signature> SignatureSet<S> {
             signatures: NodeMap::new_with_len(len),
         }
     }
+
+    fn signers(&self) -> BoolNodeMap {
+        self.signatures.iter().map(Option::is_some).collect()
+    }
+
+    // The bitmap of signers is already decoded, so it was backed by a byte of input for every
+    // eight nodes, and the signatures are only read for the nodes in it.
+    fn decode_signatures<I: Input>(input: &mut I, signers: BoolNodeMap) -> Result<Self, Error> {
+        let mut signatures = Vec::with_capacity(signers.capacity());
+        for i in 0..signers.capacity() {
+            let signature = if signers[NodeIndex(i)] {
+                Some(S::decode(input)?)
+            } else {
+                None
+            };
+            signatures.push(signature);
+        }
+        Ok(SignatureSet {
+            signatures: signatures.into(),
+        })
+    }
 }
 
 impl<S: Signature> PartialMultisignature for SignatureSet<S> {
@@ -481,19 +683,24 @@ impl<S: Signature> PartialMultisignature for SignatureSet<S> {
         self.signatures[index] = Some(signature.clone());
         self
     }
+
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        <Self as DecodeBounded>::decode_bounded(input, limits)
+    }
 }
 
 /// Keybox wrapper which implements MultiKeychain such that a partial multisignature is a list of
 /// signatures and a partial multisignature is considered complete if it contains more than 2N/3 signatures.
+/// Wrapping a [`Verifier`] instead of a [`KeyBox`] gives the corresponding [`MultiVerifier`].
 ///
 /// Note: this way of multisigning is very inefficient, and should be used only for testing.
 #[derive(Debug, Clone)]
-pub struct DefaultMultiKeychain<KB: KeyBox> {
+pub struct DefaultMultiKeychain<KB: Verifier> {
     key_box: KB,
 }
 
-impl<KB: KeyBox> DefaultMultiKeychain<KB> {
-    // Create a new `DefaultMultiKeychain` using the provided `KeyBox`.
+impl<KB: Verifier> DefaultMultiKeychain<KB> {
+    // Create a new `DefaultMultiKeychain` using the provided `KeyBox` or `Verifier`.
     pub fn new(key_box: KB) -> Self {
         DefaultMultiKeychain { key_box }
     }
@@ -509,14 +716,9 @@ impl<KB: KeyBox> Index for DefaultMultiKeychain<KB> {
     }
 }
 
-#[async_trait::async_trait]
-impl<KB: KeyBox> KeyBox for DefaultMultiKeychain<KB> {
+impl<KB: Verifier> Verifier for DefaultMultiKeychain<KB> {
     type Signature = KB::Signature;
 
-    async fn sign(&self, msg: &[u8]) -> Self::Signature {
-        self.key_box.sign(msg).await
-    }
-
     fn node_count(&self) -> NodeCount {
         self.key_box.node_count()
     }
@@ -524,18 +726,21 @@ impl<KB: KeyBox> KeyBox for DefaultMultiKeychain<KB> {
     fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
         self.key_box.verify(msg, sgn, index)
     }
-}
 
-impl<KB: KeyBox> MultiKeychain for DefaultMultiKeychain<KB> {
-    type PartialMultisignature = SignatureSet<KB::Signature>;
+    fn verify_batch(&self, batch: &[(&[u8], &Self::Signature, NodeIndex)]) -> Vec<bool> {
+        self.key_box.verify_batch(batch)
+    }
+}
 
-    fn from_signature(
-        &self,
-        signature: &Self::Signature,
-        index: NodeIndex,
-    ) -> Self::PartialMultisignature {
-        SignatureSet::add_signature(SignatureSet::new(self.node_count()), signature, index)
+#[async_trait::async_trait]
+impl<KB: KeyBox> KeyBox for DefaultMultiKeychain<KB> {
+    async fn sign(&self, msg: &[u8]) -> Self::Signature {
+        self.key_box.sign(msg).await
     }
+}
+
+impl<KB: Verifier> MultiVerifier for DefaultMultiKeychain<KB> {
+    type PartialMultisignature = SignatureSet<KB::Signature>;
 
     fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool {
         let signature_count = partial.signatures.iter().flatten().count();
@@ -548,3 +753,13 @@ impl<KB: KeyBox> MultiKeychain for DefaultMultiKeychain<KB> {
         })
     }
 }
+
+impl<KB: KeyBox> MultiKeychain for DefaultMultiKeychain<KB> {
+    fn from_signature(
+        &self,
+        signature: &Self::Signature,
+        index: NodeIndex,
+    ) -> Self::PartialMultisignature {
+        SignatureSet::add_signature(SignatureSet::new(self.node_count()), signature, index)
+    }
+}
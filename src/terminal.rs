@@ -351,7 +351,7 @@ impl<H: Hasher> Terminal<H> {
 
     pub(crate) async fn run(&mut self, mut exit: oneshot::Receiver<()>) {
         loop {
-            futures::select! {
+            futures::select_biased! {
                 n = self.ntfct_rx.next() => {
                     match n {
                         Some(NotificationIn::NewUnits(units)) => {
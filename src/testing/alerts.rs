@@ -1,12 +1,16 @@
 use crate::{
-    alerts::{run, Alert, AlertConfig, AlertMessage, ForkProof, ForkingNotification},
+    alerts::{run, Alert, AlertConfig, AlertHash, AlertMessage, ForkProof, ForkingNotification},
+    environment::SystemClock,
     network::Recipient,
     nodes::{NodeCount, NodeIndex},
     rmc::Message as RmcMessage,
-    signed::KeyBox as _,
-    testing::mock::{Data, Hasher64, KeyBox, PartialMultisignature, Signature},
+    signed::Verifier as _,
+    testing::{
+        mock::{Data, Hasher64, KeyBox, PartialMultisignature, Signature},
+        signed::{test_multi_keychains, TestMultiKeychain, TestSignature},
+    },
     units::{ControlHash, FullUnit, PreUnit, UnitCoord},
-    Index, Indexed, NodeMap, Round, Signable, Signed, UncheckedSigned,
+    Index, Indexed, NodeMap, Round, SessionId, Signable, Signed, UncheckedSigned,
 };
 use futures::{
     channel::{mpsc, oneshot},
@@ -17,6 +21,7 @@ use log::trace;
 use std::{
     collections::{HashMap, HashSet},
     hash::Hash,
+    sync::Arc,
     time::Duration,
 };
 
@@ -25,6 +30,10 @@ type TestAlert = Alert<Hasher64, Data, Signature>;
 type TestNotification = ForkingNotification<Hasher64, Data, Signature>;
 type TestForkProof = ForkProof<Hasher64, Data, Signature>;
 type TestFullUnit = FullUnit<Hasher64, Data>;
+type TestAlertHash = AlertHash<<Hasher64 as crate::Hasher>::Hash>;
+
+// The session in which the tested alerter runs.
+const SESSION_ID: SessionId = 0;
 
 enum Input {
     Incoming(TestMessage),
@@ -106,6 +115,16 @@ impl TestCase {
     }
 
     fn full_unit(&self, forker: NodeIndex, round: Round, variant: u32) -> TestFullUnit {
+        self.full_unit_in_session(forker, round, variant, SESSION_ID)
+    }
+
+    fn full_unit_in_session(
+        &self,
+        forker: NodeIndex,
+        round: Round,
+        variant: u32,
+        session_id: SessionId,
+    ) -> TestFullUnit {
         FullUnit::new(
             PreUnit::new(
                 forker,
@@ -115,7 +134,7 @@ impl TestCase {
                 )),
             ),
             Data::new(UnitCoord::new(round, forker), variant),
-            0,
+            session_id,
         )
     }
 
@@ -130,9 +149,21 @@ impl TestCase {
     }
 
     async fn fork_proof(&self, forker: NodeIndex, round: Round) -> TestForkProof {
-        let u0 = self.unchecked_signed_unit(forker, round, 0).await;
-        let u1 = self.unchecked_signed_unit(forker, round, 1).await;
-        (u0, u1)
+        self.fork_proof_in_session(forker, round, SESSION_ID).await
+    }
+
+    async fn fork_proof_in_session(
+        &self,
+        forker: NodeIndex,
+        round: Round,
+        session_id: SessionId,
+    ) -> TestForkProof {
+        let u0 = self.full_unit_in_session(forker, round, 0, session_id);
+        let u1 = self.full_unit_in_session(forker, round, 1, session_id);
+        (
+            self.unchecked_signed(u0, forker).await,
+            self.unchecked_signed(u1, forker).await,
+        )
     }
 
     fn alert_with_commitment(
@@ -141,7 +172,30 @@ impl TestCase {
         proof: TestForkProof,
         commitment: Vec<UncheckedSigned<TestFullUnit, Signature>>,
     ) -> TestAlert {
-        Alert::new(sender, proof, commitment)
+        Alert::new(sender, SESSION_ID, proof, commitment)
+    }
+
+    fn alert_hash(&self, alert: &TestAlert) -> TestAlertHash {
+        AlertHash::new(SESSION_ID, alert.hash())
+    }
+
+    async fn multisigned_alert_hash(
+        &self,
+        alert_hash: TestAlertHash,
+        signers: impl IntoIterator<Item = NodeIndex>,
+    ) -> UncheckedSigned<TestAlertHash, PartialMultisignature> {
+        let keychain = self.keychain(NodeIndex(0));
+        let mut signers = signers.into_iter();
+        let first = signers.next().expect("there is a signer");
+        let mut multisigned = Signed::sign_with_index(alert_hash, self.keychain(first))
+            .await
+            .into_partially_multisigned(keychain);
+        for signer in signers {
+            let signed = Signed::sign_with_index(alert_hash, self.keychain(signer)).await;
+            multisigned = multisigned.add_signature(signed, keychain);
+        }
+        assert!(multisigned.is_complete(), "there should be enough signers");
+        multisigned.into_unchecked()
     }
 
     fn alert(&self, sender: NodeIndex, proof: TestForkProof) -> TestAlert {
@@ -226,7 +280,8 @@ impl TestCase {
             alerts_from_units,
             AlertConfig {
                 n_members,
-                session_id: 0,
+                session_id: SESSION_ID,
+                clock: Arc::new(SystemClock),
             },
             exit,
         ));
@@ -267,7 +322,7 @@ impl TestCase {
     async fn run(self, run_as: NodeIndex) {
         let keychain = self.keychain(run_as).clone();
         let mut timeout = Delay::new(Duration::from_millis(500)).fuse();
-        futures::select! {
+        futures::select_biased! {
             _ = self.test(keychain).fuse() => {},
             _ = timeout => {
                 panic!("Alerter took too long to emit expected items.");
@@ -300,7 +355,7 @@ async fn reacts_to_correctly_incoming_alert() {
     let fork_proof = test_case.fork_proof(forker, 0).await;
     let alert = test_case.alert(alerter_index, fork_proof.clone());
     let signed_alert_hash = test_case
-        .indexed_unchecked_signed(Signable::hash(&alert), own_index)
+        .indexed_unchecked_signed(test_case.alert_hash(&alert), own_index)
         .await;
     let signed_alert = test_case
         .unchecked_signed(alert.clone(), alerter_index)
@@ -324,7 +379,7 @@ async fn notifies_about_finished_alert() {
     let mut test_case = TestCase::new(n_members);
     let fork_proof = test_case.fork_proof(forker, 0).await;
     let alert = test_case.alert(alerter_index, fork_proof.clone());
-    let alert_hash = Signable::hash(&alert);
+    let alert_hash = test_case.alert_hash(&alert);
     let signed_alert = test_case
         .unchecked_signed(alert.clone(), alerter_index)
         .await;
@@ -355,7 +410,7 @@ async fn asks_about_unknown_alert() {
     let mut test_case = TestCase::new(n_members);
     let fork_proof = test_case.fork_proof(forker, 0).await;
     let alert = test_case.alert(alerter_index, fork_proof.clone());
-    let alert_hash = Signable::hash(&alert);
+    let alert_hash = test_case.alert_hash(&alert);
     let signed_alert_hash = test_case
         .indexed_unchecked_signed(alert_hash, alerter_index)
         .await;
@@ -365,7 +420,7 @@ async fn asks_about_unknown_alert() {
             RmcMessage::SignedHash(signed_alert_hash),
         ))
         .outgoing_message(
-            AlertMessage::AlertRequest(own_index, alert_hash),
+            AlertMessage::AlertRequest(own_index, alert.hash()),
             Recipient::Node(alerter_index),
         );
     test_case.run(own_index).await;
@@ -385,7 +440,7 @@ async fn ignores_wrong_alert() {
         .unchecked_signed(wrong_alert.clone(), forker)
         .await;
     let signed_wrong_alert_hash = test_case
-        .indexed_unchecked_signed(Signable::hash(&wrong_alert), own_index)
+        .indexed_unchecked_signed(test_case.alert_hash(&wrong_alert), own_index)
         .await;
     test_case
         .incoming_message(AlertMessage::ForkAlert(signed_wrong_alert))
@@ -419,7 +474,7 @@ async fn responds_to_alert_queries() {
     let forker = NodeIndex(6);
     let mut test_case = TestCase::new(n_members);
     let alert = test_case.alert(own_index, test_case.fork_proof(forker, 0).await);
-    let alert_hash = Signable::hash(&alert);
+    let alert_hash = test_case.alert_hash(&alert);
     let signed_alert = test_case.unchecked_signed(alert.clone(), own_index).await;
     let signed_alert_hash = test_case
         .indexed_unchecked_signed(alert_hash, own_index)
@@ -435,7 +490,7 @@ async fn responds_to_alert_queries() {
             Recipient::Everyone,
         )
         .wait()
-        .incoming_message(AlertMessage::AlertRequest(querier, alert_hash))
+        .incoming_message(AlertMessage::AlertRequest(querier, alert.hash()))
         .outgoing_message(
             AlertMessage::ForkAlert(signed_alert.clone()),
             Recipient::Node(querier),
@@ -443,7 +498,7 @@ async fn responds_to_alert_queries() {
     for i in 1..n_members.0 {
         let node_id = NodeIndex(i);
         test_case
-            .incoming_message(AlertMessage::AlertRequest(node_id, alert_hash))
+            .incoming_message(AlertMessage::AlertRequest(node_id, alert.hash()))
             .outgoing_message(
                 AlertMessage::ForkAlert(signed_alert.clone()),
                 Recipient::Node(node_id),
@@ -462,7 +517,7 @@ async fn notifies_only_about_multisigned_alert() {
     let mut test_case = TestCase::new(n_members);
     let fork_proof = test_case.fork_proof(forker, 0).await;
     let empty_alert = test_case.alert(double_committer, fork_proof.clone());
-    let empty_alert_hash = Signable::hash(&empty_alert);
+    let empty_alert_hash = test_case.alert_hash(&empty_alert);
     let signed_empty_alert = test_case
         .unchecked_signed(empty_alert.clone(), double_committer)
         .await;
@@ -484,7 +539,7 @@ async fn notifies_only_about_multisigned_alert() {
         fork_proof.clone(),
         vec![forker_unit.clone()],
     );
-    let nonempty_alert_hash = Signable::hash(&nonempty_alert);
+    let nonempty_alert_hash = test_case.alert_hash(&nonempty_alert);
     let signed_nonempty_alert = test_case
         .unchecked_signed(nonempty_alert.clone(), double_committer)
         .await;
@@ -520,3 +575,164 @@ async fn notifies_only_about_multisigned_alert() {
         .unexpected_notification(ForkingNotification::Units(Vec::new()));
     test_case.run(own_index).await;
 }
+
+#[tokio::test]
+async fn ignores_alert_from_other_session() {
+    let n_members = NodeCount(7);
+    let own_index = NodeIndex(0);
+    let alerter_index = NodeIndex(1);
+    let forker = NodeIndex(6);
+    let other_forker = NodeIndex(5);
+    let mut test_case = TestCase::new(n_members);
+    // The units are from our session, only the alert claims to be from another one.
+    let fork_proof = test_case.fork_proof(forker, 0).await;
+    let other_session_alert = Alert::new(
+        alerter_index,
+        SESSION_ID + 1,
+        fork_proof.clone(),
+        Vec::new(),
+    );
+    let signed_other_session_alert = test_case
+        .unchecked_signed(other_session_alert, alerter_index)
+        .await;
+    test_case
+        .incoming_message(AlertMessage::ForkAlert(signed_other_session_alert))
+        .unexpected_notification(ForkingNotification::Forker(fork_proof));
+    // A proper alert, processed after the other one, to have something to wait for.
+    let other_fork_proof = test_case.fork_proof(other_forker, 0).await;
+    let alert = test_case.alert(alerter_index, other_fork_proof.clone());
+    let signed_alert = test_case
+        .unchecked_signed(alert.clone(), alerter_index)
+        .await;
+    test_case
+        .incoming_message(AlertMessage::ForkAlert(signed_alert))
+        .outgoing_notification(ForkingNotification::Forker(other_fork_proof));
+    test_case.run(own_index).await;
+}
+
+#[tokio::test]
+async fn ignores_multisigned_alert_hash_from_other_session() {
+    let n_members = NodeCount(7);
+    let own_index = NodeIndex(0);
+    let replayer = NodeIndex(1);
+    let double_committer = NodeIndex(5);
+    let forker = NodeIndex(6);
+    let mut test_case = TestCase::new(n_members);
+    let fork_proof = test_case.fork_proof(forker, 0).await;
+    let empty_alert = test_case.alert(double_committer, fork_proof.clone());
+    let signed_empty_alert = test_case
+        .unchecked_signed(empty_alert.clone(), double_committer)
+        .await;
+    let other_session_hash = AlertHash::new(SESSION_ID + 1, empty_alert.hash());
+    let replayed_multisigned_hash = test_case
+        .multisigned_alert_hash(other_session_hash, (1..6).map(NodeIndex))
+        .await;
+    test_case
+        .incoming_message(AlertMessage::ForkAlert(signed_empty_alert))
+        .outgoing_notification(ForkingNotification::Forker(fork_proof.clone()))
+        .wait()
+        .incoming_message(AlertMessage::RmcMessage(
+            replayer,
+            RmcMessage::MultisignedHash(replayed_multisigned_hash),
+        ))
+        .unexpected_notification(ForkingNotification::Units(Vec::new()));
+    // A properly multisigned alert, processed after the replayed one, to have something to wait
+    // for.
+    let forker_unit = fork_proof.0.clone();
+    let nonempty_alert =
+        test_case.alert_with_commitment(replayer, fork_proof, vec![forker_unit.clone()]);
+    let signed_nonempty_alert = test_case
+        .unchecked_signed(nonempty_alert.clone(), replayer)
+        .await;
+    let multisigned_hash = test_case
+        .multisigned_alert_hash(test_case.alert_hash(&nonempty_alert), (1..6).map(NodeIndex))
+        .await;
+    test_case
+        .incoming_message(AlertMessage::ForkAlert(signed_nonempty_alert))
+        .incoming_message(AlertMessage::RmcMessage(
+            replayer,
+            RmcMessage::MultisignedHash(multisigned_hash),
+        ))
+        .outgoing_notification(ForkingNotification::Units(vec![forker_unit]))
+        .unexpected_notification(ForkingNotification::Units(Vec::new()));
+    test_case.run(own_index).await;
+}
+
+type SigningAlert = Alert<Hasher64, Data, TestSignature>;
+
+async fn signing_alert(keychains: &[TestMultiKeychain], session_id: SessionId) -> SigningAlert {
+    let forker = NodeIndex(3);
+    let mut proof = Vec::new();
+    for variant in 0..2 {
+        let full_unit = FullUnit::new(
+            PreUnit::new(
+                forker,
+                0,
+                ControlHash::new(&NodeMap::new_with_len(NodeCount(keychains.len()))),
+            ),
+            Data::new(UnitCoord::new(0, forker), variant),
+            session_id,
+        );
+        proof.push(Signed::sign(full_unit, &keychains[forker.0]).await.into());
+    }
+    let proof = (proof.remove(0), proof.remove(0));
+    Alert::new(NodeIndex(0), session_id, proof, Vec::new())
+}
+
+#[tokio::test]
+async fn alert_signatures_commit_to_session_and_kind() {
+    let keychains = test_multi_keychains(NodeCount(4));
+    let alert = signing_alert(&keychains, SESSION_ID).await;
+    let signature = Signed::sign(alert.clone(), &keychains[0])
+        .await
+        .into_unchecked()
+        .signature();
+    assert!(UncheckedSigned::new(alert.clone(), signature.clone())
+        .check(&keychains[1])
+        .is_ok());
+
+    let other_session_alert = signing_alert(&keychains, SESSION_ID + 1).await;
+    assert!(
+        UncheckedSigned::new(other_session_alert, signature)
+            .check(&keychains[1])
+            .is_err(),
+        "a signature of an alert should not be valid in another session"
+    );
+
+    let hash_signature =
+        Signed::sign_with_index(AlertHash::new(SESSION_ID, alert.hash()), &keychains[0])
+            .await
+            .into_unchecked()
+            .signature();
+    assert!(
+        UncheckedSigned::new(alert, hash_signature)
+            .check(&keychains[1])
+            .is_err(),
+        "a signature of a multicast alert hash should not be valid for the alert"
+    );
+}
+
+#[tokio::test]
+async fn multisigned_alert_hashes_commit_to_session() {
+    let keychains = test_multi_keychains(NodeCount(4));
+    let alert = signing_alert(&keychains, SESSION_ID).await;
+    let other_session_hash = AlertHash::new(SESSION_ID + 1, alert.hash());
+    let mut multisigned = Signed::sign_with_index(other_session_hash, &keychains[0])
+        .await
+        .into_partially_multisigned(&keychains[0]);
+    for keychain in keychains.iter().skip(1) {
+        let signed = Signed::sign_with_index(other_session_hash, keychain).await;
+        multisigned = multisigned.add_signature(signed, &keychains[0]);
+    }
+    let multisigned = multisigned.into_unchecked();
+    assert!(multisigned.clone().check_multi(&keychains[1]).is_ok());
+
+    let replayed = UncheckedSigned::new(
+        AlertHash::new(SESSION_ID, alert.hash()),
+        multisigned.signature(),
+    );
+    assert!(
+        replayed.check_multi(&keychains[1]).is_err(),
+        "a multisignature of an alert hash should not be valid in another session"
+    );
+}
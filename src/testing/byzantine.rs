@@ -1,43 +1,73 @@
 use log::{debug, error, trace};
 
-use futures::{channel::oneshot, StreamExt};
+use futures::channel::oneshot;
+use futures_timer::Delay;
 
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use crate::{
-    network::NetworkDataInner::Units,
+    alerts::{Alert, AlertMessage::ForkAlert},
+    member::{NewestUnitResponse, UnitMessage},
+    network::NetworkDataInner::{Alert as Alerts, Units},
     nodes::NodeMap,
     signed::Signed,
     testing::mock::{
         configure_network, init_log, spawn_honest_member, AlertHook, Data, Hash64, Hasher64,
-        KeyBox, Network, NetworkData, Spawner,
+        KeyBox, Network, NetworkData, Signature, Spawner,
     },
-    units::{ControlHash, FullUnit, PreUnit, SignedUnit, UnitCoord},
+    testing::BatchChecker,
+    units::{ControlHash, FullUnit, PreUnit, SignedUnit, UncheckedSignedUnit, UnitCoord},
     Hasher, Network as NetworkT, NetworkData as NetworkDataT, NodeCount, NodeIndex, Recipient,
     Round, SessionId, SpawnHandle, TaskHandle,
 };
 
-use crate::member::UnitMessage::NewUnit;
+/// A single deviation from the protocol. A malicious member may combine any number of them,
+/// otherwise it creates units as an honest member would, but never answers requests.
+#[derive(Clone, Debug)]
+enum Behaviour {
+    /// Send two variants of our unit at each of these rounds, to the even and odd nodes.
+    Equivocate(Vec<Round>),
+    /// Never send our units to these nodes.
+    Withhold(Vec<NodeIndex>),
+    /// Create our units at these rounds with a control hash not matching their parents.
+    WrongControlHash(Vec<Round>),
+    /// Answer requests for the newest unit claiming we know no units of the requester.
+    LieAboutNewest,
+    /// Answer requests for parents with units of the wrong round.
+    BogusParents,
+    /// Send this many requests for the units of the previous round on every unit we create,
+    /// in the name of all the members in turn, so that the responses go to them.
+    SpamCoordRequests(usize),
+    /// Broadcast alerts signed by us, whose proofs consist of two units of an honest member
+    /// from different rounds.
+    ForgeAlerts,
+    /// Resend the units we receive as the given session, with the signatures of their creators,
+    /// as if they were recorded while running that session.
+    ReplayOtherSession(SessionId),
+}
 
-struct MaliciousMember<'a> {
+struct MaliciousMember {
     node_ix: NodeIndex,
     n_members: NodeCount,
     threshold: NodeCount,
     session_id: SessionId,
-    forking_round: Round,
-    keybox: &'a KeyBox,
+    behaviours: Vec<Behaviour>,
+    keybox: KeyBox,
     network: Network,
-    unit_store: HashMap<UnitCoord, SignedUnit<'a, Hasher64, Data, KeyBox>>,
+    unit_store: HashMap<UnitCoord, SignedUnit<Hasher64, Data, KeyBox>>,
+    // The parents of our own units, as we would answer requests for them.
+    own_parents: HashMap<Hash64, Vec<UncheckedSignedUnit<Hasher64, Data, Signature>>>,
+    spam_counter: usize,
 }
 
-impl<'a> MaliciousMember<'a> {
+impl MaliciousMember {
     fn new(
-        keybox: &'a KeyBox,
+        keybox: KeyBox,
         network: Network,
         node_ix: NodeIndex,
         n_members: NodeCount,
         session_id: SessionId,
-        forking_round: Round,
+        behaviours: Vec<Behaviour>,
     ) -> Self {
         let threshold = (n_members * 2) / 3 + NodeCount(1);
         MaliciousMember {
@@ -45,15 +75,73 @@ impl<'a> MaliciousMember<'a> {
             n_members,
             threshold,
             session_id,
-            forking_round,
+            behaviours,
             keybox,
             network,
             unit_store: HashMap::new(),
+            own_parents: HashMap::new(),
+            spam_counter: 0,
         }
     }
 
-    fn unit_to_data(su: SignedUnit<'a, Hasher64, Data, KeyBox>) -> NetworkData {
-        NetworkDataT(Units(NewUnit(su.into())))
+    fn equivocates_at(&self, round: Round) -> bool {
+        self.behaviours
+            .iter()
+            .any(|b| matches!(b, Behaviour::Equivocate(rounds) if rounds.contains(&round)))
+    }
+
+    fn breaks_control_hash_at(&self, round: Round) -> bool {
+        self.behaviours
+            .iter()
+            .any(|b| matches!(b, Behaviour::WrongControlHash(rounds) if rounds.contains(&round)))
+    }
+
+    fn withholds_from(&self, node_ix: NodeIndex) -> bool {
+        self.behaviours
+            .iter()
+            .any(|b| matches!(b, Behaviour::Withhold(nodes) if nodes.contains(&node_ix)))
+    }
+
+    fn lies_about_newest(&self) -> bool {
+        self.behaviours
+            .iter()
+            .any(|b| matches!(b, Behaviour::LieAboutNewest))
+    }
+
+    fn sends_bogus_parents(&self) -> bool {
+        self.behaviours
+            .iter()
+            .any(|b| matches!(b, Behaviour::BogusParents))
+    }
+
+    fn forges_alerts(&self) -> bool {
+        self.behaviours
+            .iter()
+            .any(|b| matches!(b, Behaviour::ForgeAlerts))
+    }
+
+    fn spam_requests(&self) -> usize {
+        self.behaviours
+            .iter()
+            .map(|b| match b {
+                Behaviour::SpamCoordRequests(n) => *n,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn replayed_sessions(&self) -> Vec<SessionId> {
+        self.behaviours
+            .iter()
+            .filter_map(|b| match b {
+                Behaviour::ReplayOtherSession(session_id) => Some(*session_id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn unit_to_data(su: SignedUnit<Hasher64, Data, KeyBox>) -> NetworkData {
+        NetworkDataT(Units(UnitMessage::NewUnit(su.into())))
     }
 
     fn pick_parents(&self, round: Round) -> Option<NodeMap<Option<Hash64>>> {
@@ -83,62 +171,137 @@ impl<'a> MaliciousMember<'a> {
         }
     }
 
-    fn send_legit_unit(&mut self, su: SignedUnit<'a, Hasher64, Data, KeyBox>) {
+    fn send_to_recipients(&self, message: NetworkData, node_filter: impl Fn(NodeIndex) -> bool) {
+        let recipients = (0..self.n_members.0)
+            .map(NodeIndex)
+            .filter(|node_ix| *node_ix != self.node_ix && !self.withholds_from(*node_ix))
+            .filter(|node_ix| node_filter(*node_ix))
+            .collect();
+        let _ = self.network.send(message, Recipient::Nodes(recipients));
+    }
+
+    fn send_legit_unit(&mut self, su: SignedUnit<Hasher64, Data, KeyBox>) {
         let message = Self::unit_to_data(su);
-        self.network.send(message, Recipient::Everyone);
+        self.send_to_recipients(message, |_| true);
     }
 
     fn send_two_variants(
         &mut self,
-        su0: SignedUnit<'a, Hasher64, Data, KeyBox>,
-        su1: SignedUnit<'a, Hasher64, Data, KeyBox>,
+        su0: SignedUnit<Hasher64, Data, KeyBox>,
+        su1: SignedUnit<Hasher64, Data, KeyBox>,
     ) {
         // We send variant k \in {0,1} to each node with index = k (mod 2)
-        // We also send to ourselves, it does not matter much.
         let message0 = Self::unit_to_data(su0);
         let message1 = Self::unit_to_data(su1);
-        for ix in 0..self.n_members.0 {
-            let node_ix = NodeIndex(ix);
-            let _ = if ix % 2 == 0 {
-                self.network
-                    .send(message0.clone(), Recipient::Node(node_ix))
-            } else {
-                self.network
-                    .send(message1.clone(), Recipient::Node(node_ix))
-            };
-        }
+        self.send_to_recipients(message0, |node_ix| node_ix.0 % 2 == 0);
+        self.send_to_recipients(message1, |node_ix| node_ix.0 % 2 == 1);
     }
 
     async fn create_if_possible(&mut self, round: Round) -> bool {
         if let Some(parents) = self.pick_parents(round) {
             debug!(target: "malicious-member", "Creating a legit unit for round {}.", round);
-            let control_hash = ControlHash::<Hasher64>::new(&parents);
+            let mut control_hash = ControlHash::<Hasher64>::new(&parents);
+            if self.breaks_control_hash_at(round) {
+                debug!(target: "malicious-member", "Breaking the control hash for round {}.", round);
+                control_hash.combined_hash = Hasher64::hash(b"not the parents");
+            }
             let new_preunit = PreUnit::<Hasher64>::new(self.node_ix, round, control_hash);
             let coord = UnitCoord::new(round, self.node_ix);
-            if round != self.forking_round {
-                let data = Data::new(coord, 0);
-                let full_unit = FullUnit::new(new_preunit, data, self.session_id);
-                let signed_unit = Signed::sign(full_unit, self.keybox).await;
-                self.on_unit_received(signed_unit.clone());
-                self.send_legit_unit(signed_unit);
+            let parent_units = parents
+                .iter()
+                .flatten()
+                .filter_map(|hash| {
+                    self.unit_store
+                        .values()
+                        .find(|su| su.as_signable().hash() == *hash)
+                })
+                .map(|su| su.clone().into())
+                .collect();
+            let mut variants = Vec::new();
+            let n_variants = if self.equivocates_at(round) { 2 } else { 1 };
+            for var in 0..n_variants {
+                let data = Data::new(coord, var);
+                let full_unit = FullUnit::new(new_preunit.clone(), data, self.session_id);
+                let signed = Signed::sign(full_unit, &self.keybox).await;
+                variants.push(signed);
+            }
+            // We build upon the first variant in any case.
+            self.own_parents
+                .insert(variants[0].as_signable().hash(), parent_units);
+            self.on_unit_received(variants[0].clone());
+            if variants.len() == 1 {
+                self.send_legit_unit(variants[0].clone());
             } else {
                 // FORKING HAPPENS HERE!
                 debug!(target: "malicious-member", "Creating forks for round {}.", round);
-                let mut variants = Vec::new();
-                for var in 0u32..2u32 {
-                    let data = Data::new(coord, var);
-                    let full_unit = FullUnit::new(new_preunit.clone(), data, self.session_id);
-                    let signed = Signed::sign(full_unit, self.keybox).await;
-                    variants.push(signed);
-                }
                 self.send_two_variants(variants[0].clone(), variants[1].clone());
             }
+            self.spam_coord_requests(round);
+            self.forge_alert(round).await;
             return true;
         }
         false
     }
 
-    fn on_unit_received(&mut self, su: SignedUnit<'a, Hasher64, Data, KeyBox>) {
+    fn spam_coord_requests(&mut self, round: Round) {
+        if round == 0 {
+            return;
+        }
+        for _ in 0..self.spam_requests() {
+            let requester = NodeIndex(self.spam_counter % self.n_members.0);
+            let coord = UnitCoord::new(
+                round - 1,
+                NodeIndex(self.spam_counter / self.n_members.0 % self.n_members.0),
+            );
+            self.spam_counter += 1;
+            let message = NetworkDataT(Units(UnitMessage::RequestCoord(requester, coord)));
+            let _ = self.network.send(message, Recipient::Everyone);
+        }
+    }
+
+    async fn forge_alert(&mut self, round: Round) {
+        if !self.forges_alerts() || round < 2 {
+            return;
+        }
+        // We accuse a different honest member every round.
+        let accused = NodeIndex(round as usize % self.n_members.0);
+        let (u1, u2) = match (
+            self.unit_store.get(&UnitCoord::new(round - 1, accused)),
+            self.unit_store.get(&UnitCoord::new(round - 2, accused)),
+        ) {
+            (Some(u1), Some(u2)) if accused != self.node_ix => (u1.clone(), u2.clone()),
+            _ => return,
+        };
+        debug!(target: "malicious-member", "Forging an alert against {:?}.", accused);
+        let alert = Alert::new(
+            self.node_ix,
+            self.session_id,
+            (u1.into(), u2.into()),
+            Vec::new(),
+        );
+        let signed_alert = Signed::sign(alert, &self.keybox).await.into_unchecked();
+        let message = NetworkDataT(Alerts(ForkAlert(signed_alert)));
+        let _ = self.network.send(message, Recipient::Everyone);
+    }
+
+    async fn replay_in_other_sessions(&self, su: &SignedUnit<Hasher64, Data, KeyBox>) {
+        let full_unit = su.as_signable();
+        let creator_keybox = KeyBox::new(self.n_members, full_unit.creator());
+        for session_id in self.replayed_sessions() {
+            let replayed = FullUnit::new(
+                full_unit.as_pre_unit().clone(),
+                full_unit.data().clone(),
+                session_id,
+            );
+            let replayed = Signed::sign(replayed, &creator_keybox)
+                .await
+                .into_unchecked();
+            let message = NetworkDataT(Units(UnitMessage::NewUnit(replayed)));
+            let _ = self.network.send(message, Recipient::Everyone);
+        }
+    }
+
+    fn on_unit_received(&mut self, su: SignedUnit<Hasher64, Data, KeyBox>) {
         let full_unit = su.as_signable();
         let coord: UnitCoord = full_unit.coord();
         // We don't care if we overwrite something as long as we keep at least one version of a unit
@@ -146,18 +309,72 @@ impl<'a> MaliciousMember<'a> {
         self.unit_store.insert(coord, su);
     }
 
-    fn on_network_data(&mut self, data: NetworkData) {
-        // We ignore all messages except those carrying new units.
-        if let NetworkDataT(Units(NewUnit(unchecked))) = data {
-            trace!(target: "malicious-member", "New unit received {:?}.", &unchecked);
-            match unchecked.check(self.keybox) {
-                Ok(su) => self.on_unit_received(su),
-                Err(unchecked) => {
-                    panic!("Wrong signature received {:?}.", &unchecked);
+    fn on_request_parents(&self, requester: NodeIndex, u_hash: Hash64) {
+        let parents = match self.own_parents.get(&u_hash) {
+            Some(parents) => parents,
+            // We only know how to answer for our own units.
+            None => return,
+        };
+        let parents = if self.sends_bogus_parents() {
+            debug!(target: "malicious-member", "Sending bogus parents to {:?}.", requester);
+            let round = parents
+                .first()
+                .map(|parent| parent.as_signable().round() + 1)
+                .unwrap_or(0);
+            self.unit_store
+                .values()
+                .filter(|su| su.as_signable().round() == round)
+                .take(parents.len())
+                .map(|su| su.clone().into())
+                .collect()
+        } else {
+            parents.clone()
+        };
+        let message = NetworkDataT(Units(UnitMessage::ResponseParents(u_hash, parents)));
+        let _ = self.network.send(message, Recipient::Node(requester));
+    }
+
+    async fn on_request_newest(&self, requester: NodeIndex, salt: u64) {
+        if !self.lies_about_newest() {
+            return;
+        }
+        debug!(target: "malicious-member", "Lying to {:?} about its newest unit.", requester);
+        let response = NewestUnitResponse {
+            requester,
+            responder: self.node_ix,
+            unit: None,
+            salt,
+        };
+        let response = Signed::sign(response, &self.keybox).await.into_unchecked();
+        let message = NetworkDataT(Units(UnitMessage::ResponseNewest(response)));
+        let _ = self.network.send(message, Recipient::Node(requester));
+    }
+
+    async fn on_network_data(&mut self, data: NetworkData) {
+        // We ignore all other messages.
+        match data {
+            NetworkDataT(Units(UnitMessage::NewUnit(unchecked))) => {
+                trace!(target: "malicious-member", "New unit received {:?}.", &unchecked);
+                match unchecked.check(&self.keybox) {
+                    Ok(su) if su.as_signable().session_id() == self.session_id => {
+                        self.replay_in_other_sessions(&su).await;
+                        self.on_unit_received(su);
+                    }
+                    // Most likely our own replays, coming back to us.
+                    Ok(_) => {}
+                    Err(unchecked) => {
+                        panic!("Wrong signature received {:?}.", &unchecked);
+                    }
                 }
             }
+            NetworkDataT(Units(UnitMessage::RequestParents(requester, u_hash))) => {
+                self.on_request_parents(requester, u_hash)
+            }
+            NetworkDataT(Units(UnitMessage::RequestNewest(requester, salt))) => {
+                self.on_request_newest(requester, salt).await
+            }
+            _ => {}
         }
-        // else we stay silent
     }
 
     pub async fn run_session(mut self, mut exit: oneshot::Receiver<()>) {
@@ -169,7 +386,7 @@ impl<'a> MaliciousMember<'a> {
             tokio::select! {
                 event = self.network.next_event() => match event {
                     Some(data) => {
-                        self.on_network_data(data);
+                        self.on_network_data(data).await;
                     },
                     None => {
                         error!(target: "malicious-member", "Network message stream closed.");
@@ -186,7 +403,7 @@ fn spawn_malicious_member(
     spawner: Spawner,
     node_index: NodeIndex,
     n_members: NodeCount,
-    round_to_fork: Round,
+    behaviours: Vec<Behaviour>,
     network: Network,
 ) -> (oneshot::Sender<()>, TaskHandle) {
     let (exit_tx, exit_rx) = oneshot::channel();
@@ -194,12 +411,7 @@ fn spawn_malicious_member(
         let keybox = KeyBox::new(n_members, node_index);
         let session_id = 0u64;
         let lesniak = MaliciousMember::new(
-            &keybox,
-            network,
-            node_index,
-            n_members,
-            session_id,
-            round_to_fork,
+            keybox, network, node_index, n_members, session_id, behaviours,
         );
         lesniak.run_session(exit_rx).await;
     };
@@ -207,11 +419,41 @@ fn spawn_malicious_member(
     (exit_tx, task_handle)
 }
 
+// Alerts are sent independently of ordering the batches, so they may still be on their way.
+async fn wait_for_alerts(
+    alert_hook: &AlertHook,
+    sender: NodeIndex,
+    recipient: NodeIndex,
+    expected: usize,
+) {
+    let deadline = Duration::from_secs(30);
+    let interval = Duration::from_millis(50);
+    let mut waited = Duration::from_secs(0);
+    loop {
+        let alerts_sent = alert_hook.count(sender, recipient);
+        if alerts_sent >= expected {
+            return;
+        }
+        assert!(
+            waited < deadline,
+            "Node {:?} sent only {:?} alerts to {:?} in {:?}, expected at least {:?}.",
+            sender,
+            alerts_sent,
+            recipient,
+            deadline,
+            expected
+        );
+        Delay::new(interval).await;
+        waited += interval;
+    }
+}
+
 async fn honest_members_agree_on_batches_byzantine(
     n_members: NodeCount,
     n_honest: NodeCount,
     n_batches: usize,
     network_reliability: f64,
+    behaviours: Vec<Behaviour>,
 ) {
     init_log();
     let spawner = Spawner::new();
@@ -228,41 +470,73 @@ async fn honest_members_agree_on_batches_byzantine(
     for network in networks {
         let ix = network.index();
         let (exit_tx, handle) = if !n_honest.into_range().contains(&ix) {
-            spawn_malicious_member(spawner.clone(), ix, n_members, 2, network)
+            spawn_malicious_member(spawner.clone(), ix, n_members, behaviours.clone(), network)
         } else {
             let (batch_rx, exit_tx, handle) =
                 spawn_honest_member(spawner.clone(), ix, n_members, network);
-            batch_rxs.push(batch_rx);
+            batch_rxs.push((ix, batch_rx));
             (exit_tx, handle)
         };
         exits.push(exit_tx);
         handles.push(handle);
     }
 
-    let mut batches = Vec::new();
-    for mut rx in batch_rxs.drain(..) {
-        let mut batches_per_ix = Vec::new();
-        for _ in 0..n_batches {
-            let batch = rx.next().await.unwrap();
-            batches_per_ix.push(batch);
-        }
-        batches.push(batches_per_ix);
+    let mut checker = BatchChecker::new();
+    if let Err(inconsistency) = checker.check_streams(&mut batch_rxs, n_batches).await {
+        panic!("{}", inconsistency);
+    }
+    // Members may be at most a few rounds late to have their data ordered.
+    let max_batches = n_batches + 20;
+    if let Err(inconsistency) = checker
+        .check_eventual_inclusion(
+            &mut batch_rxs,
+            n_honest.into_iterator(),
+            Data::creator,
+            max_batches,
+        )
+        .await
+    {
+        panic!("{}", inconsistency);
     }
 
+    for node_ix in n_honest.into_iterator() {
+        assert!(checker.ordered(node_ix) >= n_batches);
+    }
+    // Only equivocation is provable, the other behaviours, forged alerts included, should not
+    // make honest members alert each other.
+    let equivocating = behaviours
+        .iter()
+        .any(|b| matches!(b, Behaviour::Equivocate(_)));
     let expected_forkers = n_members - n_honest;
-    for node_ix in n_honest.into_iterator().skip(1) {
-        debug!(target: "byzantine-test", "batch {:?} received", node_ix);
-        assert_eq!(batches[0], batches[node_ix.0]);
-        for recipient_id in n_honest.into_iterator().skip(1) {
-            if node_ix != recipient_id {
+    if behaviours
+        .iter()
+        .any(|b| matches!(b, Behaviour::ForgeAlerts))
+    {
+        let forged_alerts: usize = n_members
+            .into_iterator()
+            .skip(n_honest.0)
+            .flat_map(|forger| {
+                n_honest
+                    .into_iterator()
+                    .map(move |recipient_id| (forger, recipient_id))
+            })
+            .map(|(forger, recipient_id)| alert_hook.count(forger, recipient_id))
+            .sum();
+        assert!(forged_alerts > 0, "No alerts were forged.");
+    }
+    for node_ix in n_honest.into_iterator() {
+        for recipient_id in n_honest.into_iterator() {
+            if node_ix == recipient_id {
+                continue;
+            }
+            if equivocating {
+                wait_for_alerts(&alert_hook, node_ix, recipient_id, expected_forkers.0).await;
+            } else {
                 let alerts_sent = alert_hook.count(node_ix, recipient_id);
-                assert!(
-                    alerts_sent >= expected_forkers.into(),
-                    "Node {:?} sent only {:?} alerts to {:?}, expected at least {:?}.",
-                    node_ix,
-                    alerts_sent,
-                    recipient_id,
-                    expected_forkers
+                assert_eq!(
+                    alerts_sent, 0,
+                    "Node {:?} sent {:?} alerts to {:?}, but nobody equivocated.",
+                    node_ix, alerts_sent, recipient_id
                 );
             }
         }
@@ -277,15 +551,84 @@ async fn honest_members_agree_on_batches_byzantine(
 
 #[tokio::test]
 async fn small_byzantine_one_forker() {
-    honest_members_agree_on_batches_byzantine(4.into(), 3.into(), 5, 1.0).await;
+    let behaviours = vec![Behaviour::Equivocate(vec![2])];
+    honest_members_agree_on_batches_byzantine(4.into(), 3.into(), 5, 1.0, behaviours).await;
 }
 
 #[tokio::test]
 async fn small_byzantine_two_forkers() {
-    honest_members_agree_on_batches_byzantine(7.into(), 5.into(), 5, 1.0).await;
+    let behaviours = vec![Behaviour::Equivocate(vec![2])];
+    honest_members_agree_on_batches_byzantine(7.into(), 5.into(), 5, 1.0, behaviours).await;
 }
 
 #[tokio::test]
 async fn medium_byzantine_ten_forkers() {
-    honest_members_agree_on_batches_byzantine(31.into(), 21.into(), 5, 1.0).await;
+    let behaviours = vec![Behaviour::Equivocate(vec![2])];
+    honest_members_agree_on_batches_byzantine(31.into(), 21.into(), 5, 1.0, behaviours).await;
+}
+
+#[tokio::test]
+async fn small_byzantine_repeated_forker() {
+    let behaviours = vec![Behaviour::Equivocate(vec![1, 3, 5])];
+    honest_members_agree_on_batches_byzantine(4.into(), 3.into(), 5, 1.0, behaviours).await;
+}
+
+#[tokio::test]
+async fn small_byzantine_withholding_units() {
+    let behaviours = vec![Behaviour::Withhold(vec![NodeIndex(0), NodeIndex(1)])];
+    honest_members_agree_on_batches_byzantine(7.into(), 5.into(), 5, 1.0, behaviours).await;
+}
+
+#[tokio::test]
+async fn small_byzantine_wrong_control_hashes() {
+    let behaviours = vec![Behaviour::WrongControlHash(vec![1, 3])];
+    honest_members_agree_on_batches_byzantine(4.into(), 3.into(), 5, 1.0, behaviours).await;
+}
+
+#[tokio::test]
+async fn small_byzantine_lying_about_newest_units() {
+    let behaviours = vec![Behaviour::LieAboutNewest];
+    honest_members_agree_on_batches_byzantine(4.into(), 3.into(), 5, 1.0, behaviours).await;
+}
+
+#[tokio::test]
+async fn small_byzantine_bogus_parents() {
+    let behaviours = vec![
+        Behaviour::WrongControlHash(vec![2]),
+        Behaviour::BogusParents,
+    ];
+    honest_members_agree_on_batches_byzantine(4.into(), 3.into(), 5, 1.0, behaviours).await;
+}
+
+#[tokio::test]
+async fn small_byzantine_coord_request_spam() {
+    let behaviours = vec![Behaviour::SpamCoordRequests(50)];
+    honest_members_agree_on_batches_byzantine(4.into(), 3.into(), 5, 1.0, behaviours).await;
+}
+
+#[tokio::test]
+async fn small_byzantine_forged_alerts() {
+    let behaviours = vec![Behaviour::ForgeAlerts];
+    honest_members_agree_on_batches_byzantine(4.into(), 3.into(), 5, 1.0, behaviours).await;
+}
+
+#[tokio::test]
+async fn small_byzantine_replays_from_other_session() {
+    let behaviours = vec![Behaviour::ReplayOtherSession(1)];
+    honest_members_agree_on_batches_byzantine(4.into(), 3.into(), 5, 1.0, behaviours).await;
+}
+
+#[tokio::test]
+async fn small_byzantine_all_behaviours() {
+    let behaviours = vec![
+        Behaviour::Equivocate(vec![4]),
+        Behaviour::Withhold(vec![NodeIndex(0)]),
+        Behaviour::WrongControlHash(vec![2]),
+        Behaviour::LieAboutNewest,
+        Behaviour::BogusParents,
+        Behaviour::SpamCoordRequests(10),
+        Behaviour::ForgeAlerts,
+        Behaviour::ReplayOtherSession(1),
+    ];
+    honest_members_agree_on_batches_byzantine(7.into(), 5.into(), 5, 1.0, behaviours).await;
 }
@@ -0,0 +1,81 @@
+use futures::StreamExt;
+
+use crate::{
+    testing::mock::{configure_network, init_log, spawn_honest_member_with_channels, Spawner},
+    ApplicationChannels, NodeCount, NodeIndex, Recipient, SpawnHandle,
+};
+
+fn greeting(ix: usize) -> Vec<u8> {
+    format!("hello from {}", ix).into_bytes()
+}
+
+#[tokio::test]
+async fn application_messages_are_delivered_alongside_consensus() {
+    init_log();
+    let n_members = NodeCount(4);
+    let n_batches = 5;
+    let spawner = Spawner::new();
+    let (net_hub, networks) = configure_network(n_members, 1.0);
+    spawner.spawn("network-hub", net_hub);
+
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    let mut batch_rxs = Vec::new();
+    let mut app_channels = Vec::new();
+    for network in networks {
+        let ix = network.index();
+        let mut channels = ApplicationChannels::new();
+        let app_channel = channels
+            .register::<(NodeIndex, Vec<u8>)>(7)
+            .expect("the channel is registered for the first time");
+        // A channel nobody sends anything over, to check that messages are routed by the id.
+        let _unused = channels.register::<u64>(8);
+        let (batch_rx, exit_tx, handle) =
+            spawn_honest_member_with_channels(spawner.clone(), ix, n_members, network, channels);
+        batch_rxs.push(batch_rx);
+        exits.push(exit_tx);
+        handles.push(handle);
+        app_channels.push(app_channel);
+    }
+
+    for (ix, app_channel) in app_channels.iter().enumerate() {
+        app_channel
+            .send((ix.into(), greeting(ix)), Recipient::Everyone)
+            .expect("the session is running");
+    }
+    for (ix, app_channel) in app_channels.iter_mut().enumerate() {
+        let mut senders = Vec::new();
+        for _ in 1..n_members.0 {
+            let (sender, text) = app_channel.next().await.expect("the session is running");
+            assert_eq!(text, greeting(sender.0));
+            senders.push(sender);
+        }
+        senders.sort_by_key(|sender| sender.0);
+        let expected: Vec<_> = n_members
+            .into_iterator()
+            .filter(|sender| sender.0 != ix)
+            .collect();
+        assert_eq!(senders, expected);
+    }
+
+    // The consensus goes on after the application drops all its channels.
+    drop(app_channels);
+    let mut batches = vec![];
+    for mut rx in batch_rxs.drain(..) {
+        let mut batches_per_ix = vec![];
+        for _ in 0..n_batches {
+            batches_per_ix.push(rx.next().await.unwrap());
+        }
+        batches.push(batches_per_ix);
+    }
+    for node_ix in n_members.into_iterator().skip(1) {
+        assert_eq!(batches[0], batches[node_ix.0]);
+    }
+
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+}
@@ -0,0 +1,360 @@
+use crate::{NodeIndex, OrderedBatch};
+use futures::{channel::mpsc::UnboundedReceiver, future, StreamExt};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug, Display},
+    hash::Hash,
+    task::Poll,
+};
+
+/// A violation of the guarantees about the batches ordered by the members, found by a
+/// [`BatchChecker`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Inconsistency<D> {
+    /// Two members ordered different batches in the same round.
+    Divergence {
+        /// The round of the batches, counting from zero.
+        round: usize,
+        /// The member which ordered its batch of this round first, together with the batch.
+        first: (NodeIndex, OrderedBatch<D>),
+        /// The member which ordered a different batch, together with the batch.
+        second: (NodeIndex, OrderedBatch<D>),
+        /// The items contained in only one of the batches, those of the first batch first.
+        differing: Vec<D>,
+    },
+    /// The same item was ordered twice.
+    Duplicate {
+        /// The item ordered twice.
+        data: D,
+        /// The rounds of the batches containing the item.
+        rounds: (usize, usize),
+    },
+    /// None of the items originating from the member were ordered.
+    Excluded(NodeIndex),
+}
+
+impl<D: Debug> Display for Inconsistency<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Inconsistency::Divergence {
+                round,
+                first,
+                second,
+                differing,
+            } => write!(
+                f,
+                "{:?} ordered {:?} in round {}, but {:?} ordered {:?}, differing in {:?}",
+                first.0, first.1, round, second.0, second.1, differing
+            ),
+            Inconsistency::Duplicate { data, rounds } => write!(
+                f,
+                "{:?} was ordered in round {} and again in round {}",
+                data, rounds.0, rounds.1
+            ),
+            Inconsistency::Excluded(member) => {
+                write!(f, "no data of {:?} was ordered", member)
+            }
+        }
+    }
+}
+
+/// Checks, while the session runs, that the members order the same batches in the same order
+/// and never order the same item twice.
+///
+/// The batches of every round are compared with the ones ordered by the first member to reach
+/// that round, so members may be arbitrarily far behind each other.
+pub struct BatchChecker<D> {
+    // The batches ordered so far, by the first member to order them.
+    batches: Vec<(NodeIndex, OrderedBatch<D>)>,
+    // The number of batches ordered by every member.
+    progress: HashMap<NodeIndex, usize>,
+    // The round in which every item was ordered.
+    rounds: HashMap<D, usize>,
+}
+
+impl<D: Clone + Debug + Eq + Hash> BatchChecker<D> {
+    pub fn new() -> Self {
+        BatchChecker {
+            batches: Vec::new(),
+            progress: HashMap::new(),
+            rounds: HashMap::new(),
+        }
+    }
+
+    /// Records the next batch ordered by the member, returning the inconsistency it causes,
+    /// if any. A batch causing an inconsistency is not recorded.
+    pub fn on_batch(
+        &mut self,
+        member: NodeIndex,
+        batch: OrderedBatch<D>,
+    ) -> Result<(), Inconsistency<D>> {
+        let current = self.ordered(member);
+        match self.batches.get(current) {
+            Some((first, expected)) if *expected != batch => {
+                let differing = expected
+                    .iter()
+                    .filter(|data| !batch.contains(data))
+                    .chain(batch.iter().filter(|data| !expected.contains(data)))
+                    .cloned()
+                    .collect();
+                return Err(Inconsistency::Divergence {
+                    round: current,
+                    first: (*first, expected.clone()),
+                    second: (member, batch),
+                    differing,
+                });
+            }
+            Some(_) => {}
+            None => {
+                let mut new_items = HashSet::new();
+                for data in batch.iter() {
+                    let previous = match self.rounds.get(data) {
+                        Some(previous) => Some(*previous),
+                        None if !new_items.insert(data) => Some(current),
+                        None => None,
+                    };
+                    if let Some(previous) = previous {
+                        return Err(Inconsistency::Duplicate {
+                            data: data.clone(),
+                            rounds: (previous, current),
+                        });
+                    }
+                }
+                for data in batch.iter() {
+                    self.rounds.insert(data.clone(), current);
+                }
+                self.batches.push((member, batch));
+            }
+        }
+        self.progress.insert(member, current + 1);
+        Ok(())
+    }
+
+    /// Makes the member order its batches from the first round again, e.g. after a restart
+    /// with empty state.
+    pub fn on_restart(&mut self, member: NodeIndex) {
+        self.progress.remove(&member);
+    }
+
+    /// The number of batches ordered by the member since its last restart.
+    pub fn ordered(&self, member: NodeIndex) -> usize {
+        self.progress.get(&member).copied().unwrap_or(0)
+    }
+
+    /// Checks that every one of `members` originated some item in the batches ordered so far,
+    /// using `origin` to tell the member an item originated from.
+    pub fn check_inclusion(
+        &self,
+        members: impl IntoIterator<Item = NodeIndex>,
+        origin: impl Fn(&D) -> NodeIndex,
+    ) -> Result<(), Inconsistency<D>> {
+        let origins: HashSet<_> = self.rounds.keys().map(origin).collect();
+        match members.into_iter().find(|member| !origins.contains(member)) {
+            Some(member) => Err(Inconsistency::Excluded(member)),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads the streams of batches ordered by the members, checking every batch as it arrives,
+    /// until every member orders `n_batches` batches or its stream ends. Returns the first
+    /// inconsistency found, if any. Batches beyond the `n_batches` are left in the streams.
+    pub async fn check_streams(
+        &mut self,
+        streams: &mut [(NodeIndex, UnboundedReceiver<OrderedBatch<D>>)],
+        n_batches: usize,
+    ) -> Result<(), Inconsistency<D>> {
+        let mut unfinished: HashSet<_> = streams
+            .iter()
+            .map(|(member, _)| *member)
+            .filter(|member| self.ordered(*member) < n_batches)
+            .collect();
+        while !unfinished.is_empty() {
+            let (member, batch) = future::poll_fn(|cx| {
+                for (member, batches) in streams.iter_mut() {
+                    if !unfinished.contains(member) {
+                        continue;
+                    }
+                    if let Poll::Ready(batch) = batches.poll_next_unpin(cx) {
+                        return Poll::Ready((*member, batch));
+                    }
+                }
+                Poll::Pending
+            })
+            .await;
+            match batch {
+                Some(batch) => {
+                    self.on_batch(member, batch)?;
+                    if self.ordered(member) >= n_batches {
+                        unfinished.remove(&member);
+                    }
+                }
+                None => {
+                    unfinished.remove(&member);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads further batches from the streams, checking them as [`BatchChecker::check_streams`]
+    /// does, until every one of `members` originated some ordered item, or the members order
+    /// `max_batches` batches. Returns [`Inconsistency::Excluded`] for the first member whose
+    /// data was still not ordered.
+    pub async fn check_eventual_inclusion(
+        &mut self,
+        streams: &mut [(NodeIndex, UnboundedReceiver<OrderedBatch<D>>)],
+        members: impl IntoIterator<Item = NodeIndex>,
+        origin: impl Fn(&D) -> NodeIndex,
+        max_batches: usize,
+    ) -> Result<(), Inconsistency<D>> {
+        let members: Vec<_> = members.into_iter().collect();
+        let mut n_batches = streams
+            .iter()
+            .map(|(member, _)| self.ordered(*member))
+            .min()
+            .unwrap_or(0);
+        loop {
+            match self.check_inclusion(members.iter().copied(), &origin) {
+                Err(_) if n_batches < max_batches => {
+                    n_batches += 1;
+                    self.check_streams(streams, n_batches).await?;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<D: Clone + Debug + Eq + Hash> Default for BatchChecker<D> {
+    fn default() -> Self {
+        BatchChecker::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BatchChecker, Inconsistency};
+    use crate::NodeIndex;
+    use futures::channel::mpsc::unbounded;
+
+    #[test]
+    fn accepts_members_behind_each_other() {
+        let mut checker = BatchChecker::new();
+        assert!(checker.on_batch(NodeIndex(0), vec![1, 2]).is_ok());
+        assert!(checker.on_batch(NodeIndex(0), vec![3]).is_ok());
+        assert!(checker.on_batch(NodeIndex(1), vec![1, 2]).is_ok());
+        assert_eq!(checker.ordered(NodeIndex(0)), 2);
+        assert_eq!(checker.ordered(NodeIndex(1)), 1);
+    }
+
+    #[test]
+    fn reports_first_divergence() {
+        let mut checker = BatchChecker::new();
+        assert!(checker.on_batch(NodeIndex(0), vec![1, 2]).is_ok());
+        assert!(checker.on_batch(NodeIndex(1), vec![1, 2]).is_ok());
+        assert!(checker.on_batch(NodeIndex(1), vec![3, 4]).is_ok());
+        assert_eq!(
+            checker.on_batch(NodeIndex(0), vec![3, 5]),
+            Err(Inconsistency::Divergence {
+                round: 1,
+                first: (NodeIndex(1), vec![3, 4]),
+                second: (NodeIndex(0), vec![3, 5]),
+                differing: vec![4, 5],
+            })
+        );
+    }
+
+    #[test]
+    fn reports_duplicates() {
+        let mut checker = BatchChecker::new();
+        assert!(checker.on_batch(NodeIndex(0), vec![1, 2]).is_ok());
+        assert_eq!(
+            checker.on_batch(NodeIndex(0), vec![3, 1]),
+            Err(Inconsistency::Duplicate {
+                data: 1,
+                rounds: (0, 1)
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_batches_with_duplicates() {
+        let mut checker = BatchChecker::new();
+        assert!(checker.on_batch(NodeIndex(0), vec![1]).is_ok());
+        assert!(checker.on_batch(NodeIndex(0), vec![2, 1]).is_err());
+        assert!(checker.on_batch(NodeIndex(0), vec![3, 3]).is_err());
+        assert_eq!(checker.ordered(NodeIndex(0)), 1);
+        // Neither the rejected batches nor their items were recorded.
+        assert!(checker.on_batch(NodeIndex(1), vec![1]).is_ok());
+        assert!(checker.on_batch(NodeIndex(1), vec![2, 3]).is_ok());
+        assert!(checker.on_batch(NodeIndex(0), vec![2, 3]).is_ok());
+    }
+
+    #[test]
+    fn checks_restarted_member_from_first_round() {
+        let mut checker = BatchChecker::new();
+        assert!(checker.on_batch(NodeIndex(0), vec![1]).is_ok());
+        assert!(checker.on_batch(NodeIndex(0), vec![2]).is_ok());
+        checker.on_restart(NodeIndex(0));
+        assert!(checker.on_batch(NodeIndex(0), vec![1]).is_ok());
+        assert!(checker.on_batch(NodeIndex(0), vec![3]).is_err());
+    }
+
+    #[test]
+    fn reports_excluded_members() {
+        let mut checker = BatchChecker::new();
+        assert!(checker.on_batch(NodeIndex(0), vec![0, 10]).is_ok());
+        let origin = |data: &usize| NodeIndex(data / 10);
+        assert!(checker
+            .check_inclusion(vec![NodeIndex(0), NodeIndex(1)], origin)
+            .is_ok());
+        assert_eq!(
+            checker.check_inclusion(vec![NodeIndex(2)], origin),
+            Err(Inconsistency::Excluded(NodeIndex(2)))
+        );
+    }
+
+    #[tokio::test]
+    async fn waits_for_inclusion_of_all_members() {
+        let (tx, rx) = unbounded();
+        for batch in [vec![0], vec![1], vec![2], vec![3]] {
+            tx.unbounded_send(batch).unwrap();
+        }
+        drop(tx);
+        let mut checker = BatchChecker::new();
+        let mut streams = vec![(NodeIndex(0), rx)];
+        let origin = |data: &usize| NodeIndex(*data);
+        let members = vec![NodeIndex(0), NodeIndex(2)];
+        assert!(checker
+            .check_eventual_inclusion(&mut streams, members, origin, 10)
+            .await
+            .is_ok());
+        assert_eq!(checker.ordered(NodeIndex(0)), 3);
+        assert_eq!(
+            checker
+                .check_eventual_inclusion(&mut streams, vec![NodeIndex(5)], origin, 10)
+                .await,
+            Err(Inconsistency::Excluded(NodeIndex(5)))
+        );
+    }
+
+    #[tokio::test]
+    async fn checks_streams_until_all_members_finish() {
+        let (tx0, rx0) = unbounded();
+        let (tx1, rx1) = unbounded();
+        for batch in [vec![1], vec![2], vec![3]] {
+            tx0.unbounded_send(batch.clone()).unwrap();
+            tx1.unbounded_send(batch).unwrap();
+        }
+        drop((tx0, tx1));
+        let mut checker = BatchChecker::new();
+        let mut streams = vec![(NodeIndex(0), rx0), (NodeIndex(1), rx1)];
+        assert!(checker.check_streams(&mut streams, 2).await.is_ok());
+        assert_eq!(checker.ordered(NodeIndex(0)), 2);
+        assert_eq!(checker.ordered(NodeIndex(1)), 2);
+        // The remaining batches stay in the streams.
+        assert!(checker.check_streams(&mut streams, 5).await.is_ok());
+        assert_eq!(checker.ordered(NodeIndex(0)), 3);
+        assert_eq!(checker.ordered(NodeIndex(1)), 3);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     consensus,
+    environment::SystemClock,
     runway::{NotificationIn, NotificationOut},
     testing::mock::{complete_oneshot, gen_config, Hasher64, HonestHub, Spawner},
     units::{ControlHash, PreUnit, Unit},
@@ -11,6 +12,7 @@ use futures::{
     stream::StreamExt,
 };
 use log::trace;
+use std::sync::Arc;
 
 fn init_log() {
     let _ = env_logger::builder()
@@ -47,6 +49,7 @@ async fn agree_on_first_batch() {
                 tx,
                 batch_tx,
                 spawner.clone(),
+                Arc::new(SystemClock),
                 starting_round,
                 exit_rx,
             ),
@@ -89,6 +92,7 @@ async fn catches_wrong_control_hash() {
             tx_out,
             batch_tx,
             spawner.clone(),
+            Arc::new(SystemClock),
             starting_round,
             exit_rx,
         ),
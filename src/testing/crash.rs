@@ -1,8 +1,11 @@
-use futures::StreamExt;
+use std::time::Duration;
 
 use crate::{
-    testing::mock::{configure_network, init_log, spawn_honest_member, Spawner},
-    NodeCount, SpawnHandle,
+    testing::{
+        mock::{configure_network, init_log, spawn_honest_member, Data, Spawner},
+        BatchChecker, NetworkModel, Restart, Simulation,
+    },
+    NodeCount, NodeIndex, SpawnHandle,
 };
 
 async fn honest_members_agree_on_batches(
@@ -24,24 +27,31 @@ async fn honest_members_agree_on_batches(
         if n_alive.into_range().contains(&ix) {
             let (batch_rx, exit_tx, handle) =
                 spawn_honest_member(spawner.clone(), ix, n_members, network);
-            batch_rxs.push(batch_rx);
+            batch_rxs.push((ix, batch_rx));
             exits.push(exit_tx);
             handles.push(handle);
         }
     }
 
-    let mut batches = vec![];
-    for mut rx in batch_rxs.drain(..) {
-        let mut batches_per_ix = vec![];
-        for _ in 0..n_batches {
-            let batch = rx.next().await.unwrap();
-            batches_per_ix.push(batch);
-        }
-        batches.push(batches_per_ix);
+    let mut checker = BatchChecker::new();
+    if let Err(inconsistency) = checker.check_streams(&mut batch_rxs, n_batches).await {
+        panic!("{}", inconsistency);
     }
-
-    for node_ix in n_alive.into_iterator().skip(1) {
-        assert_eq!(batches[0], batches[node_ix.0]);
+    // Members may be at most a few rounds late to have their data ordered.
+    let max_batches = n_batches + 20;
+    if let Err(inconsistency) = checker
+        .check_eventual_inclusion(
+            &mut batch_rxs,
+            n_alive.into_iterator(),
+            Data::creator,
+            max_batches,
+        )
+        .await
+    {
+        panic!("{}", inconsistency);
+    }
+    for (ix, _) in batch_rxs.iter() {
+        assert!(checker.ordered(*ix) >= n_batches);
     }
     for exit in exits {
         let _ = exit.send(());
@@ -80,3 +90,87 @@ async fn medium_honest_ten_crashes() {
 async fn medium_honest_ten_crashes_unreliable_network() {
     honest_members_agree_on_batches(31.into(), 21.into(), 5, 0.9).await;
 }
+
+// Crashes the members in `restarting` at each of the `crashes` times and starts them again a
+// second later, requiring every member to order `n_batches` batches in its last run, and the
+// restarted members to catch up and create units again, all within a bounded virtual time.
+fn honest_members_agree_on_batches_with_restarts(
+    n_members: NodeCount,
+    restarting: Vec<NodeIndex>,
+    crashes: Vec<Duration>,
+    n_batches: usize,
+    seed: u64,
+) {
+    let downtime = Duration::from_secs(1);
+    let time_limit = Duration::from_secs(600);
+    let restarts = crashes
+        .iter()
+        .flat_map(|crash| {
+            restarting.iter().map(move |member| Restart {
+                member: *member,
+                crash: *crash,
+                restart: *crash + downtime,
+            })
+        })
+        .collect();
+    let outcome = Simulation::new(n_members, seed, NetworkModel::default())
+        .with_restarts(restarts)
+        .run(n_batches, time_limit);
+
+    for member in n_members.into_iterator() {
+        let ordered = outcome.batches[member.0].len();
+        assert!(
+            ordered >= n_batches,
+            "{:?} ordered only {} of {} batches in its last run within {:?} of virtual time.",
+            member,
+            ordered,
+            n_batches,
+            time_limit
+        );
+    }
+    for member in restarting.iter() {
+        let created = outcome.created[member.0];
+        assert!(
+            created >= 3,
+            "{:?} created only {} units after its last restart.",
+            member,
+            created
+        );
+    }
+    assert!(
+        outcome.accused.is_empty(),
+        "Members {:?} were reported as forkers after restarting.",
+        outcome.accused
+    );
+    let mut checker = BatchChecker::new();
+    for member in n_members.into_iterator() {
+        let runs = outcome.earlier_runs[member.0]
+            .iter()
+            .chain(std::iter::once(&outcome.batches[member.0]));
+        for run in runs {
+            checker.on_restart(member);
+            for batch in run {
+                if let Err(inconsistency) = checker.on_batch(member, batch.clone()) {
+                    panic!("{}", inconsistency);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn small_honest_one_restart() {
+    let crashes = vec![Duration::from_secs(10)];
+    honest_members_agree_on_batches_with_restarts(4.into(), vec![NodeIndex(3)], crashes, 200, 0);
+}
+
+#[test]
+fn medium_honest_repeated_restarts() {
+    let restarting = vec![NodeIndex(5), NodeIndex(6)];
+    let crashes = [7, 15, 23]
+        .iter()
+        .copied()
+        .map(Duration::from_secs)
+        .collect();
+    honest_members_agree_on_batches_with_restarts(7.into(), restarting, crashes, 420, 1);
+}
@@ -1,8 +1,12 @@
 use crate::{
     consensus,
+    environment::SystemClock,
     nodes::{NodeCount, NodeIndex, NodeMap},
     runway::{NotificationIn, NotificationOut},
-    testing::mock::{gen_config, Hash64, Hasher64, Spawner},
+    testing::{
+        mock::{gen_config, Hash64, Hasher64, Spawner},
+        BatchChecker,
+    },
     units::{ControlHash, PreUnit, Unit},
     Receiver, Round, Sender, SpawnHandle,
 };
@@ -14,7 +18,7 @@ use futures::{
 use futures_timer::Delay;
 use log::{debug, error, trace};
 use rand::{distributions::Open01, prelude::*};
-use std::{cmp, time::Duration};
+use std::{sync::Arc, time::Duration};
 
 use crate::testing::mock::complete_oneshot;
 use std::collections::HashMap;
@@ -141,6 +145,7 @@ async fn run_consensus_on_dag(
             tx_out,
             batch_tx,
             spawner.clone(),
+            Arc::new(SystemClock),
             starting_round,
             exit_rx,
         ),
@@ -149,7 +154,7 @@ async fn run_consensus_on_dag(
     let mut batches = Vec::new();
     let mut delay_fut = Delay::new(Duration::from_millis(deadline_ms)).fuse();
     loop {
-        futures::select! {
+        futures::select_biased! {
             batch = batch_rx.next() => {
                 batches.push(batch.unwrap());
             },
@@ -267,12 +272,13 @@ fn generate_random_dag(n_members: NodeCount, height: Round, seed: u64) -> Vec<Un
 }
 
 fn batch_lists_consistent(batches1: &[Vec<Hash64>], batches2: &[Vec<Hash64>]) -> bool {
-    for i in 0..cmp::min(batches1.len(), batches2.len()) {
-        if batches1[i] != batches2[i] {
-            return false;
-        }
-    }
-    true
+    let mut checker = BatchChecker::new();
+    let mut ordered = |ix, batches: &[Vec<Hash64>]| {
+        batches
+            .iter()
+            .all(|batch| checker.on_batch(ix, batch.clone()).is_ok())
+    };
+    ordered(NodeIndex(0), batches1) && ordered(NodeIndex(1), batches2)
 }
 
 #[tokio::test]
@@ -0,0 +1,76 @@
+use futures::StreamExt;
+
+use crate::{
+    testing::mock::{
+        configure_network, gen_config, init_log, spawn_honest_member_with_config, Spawner,
+    },
+    ApplicationChannels, Dissemination, NodeCount, SpawnHandle,
+};
+
+async fn honest_members_agree_on_batches_with_gossip(
+    n_members: NodeCount,
+    n_alive: NodeCount,
+    fanout: usize,
+    n_batches: usize,
+    network_reliability: f64,
+) {
+    init_log();
+    let spawner = Spawner::new();
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    let mut batch_rxs = Vec::new();
+    let (net_hub, networks) = configure_network(n_members, network_reliability);
+    spawner.spawn("network-hub", net_hub);
+
+    for network in networks {
+        let ix = network.index();
+        if n_alive.into_range().contains(&ix) {
+            let mut config = gen_config(ix, n_members);
+            config.dissemination = Dissemination::Gossip { fanout };
+            let (batch_rx, exit_tx, handle) = spawn_honest_member_with_config(
+                spawner.clone(),
+                config,
+                network,
+                ApplicationChannels::new(),
+            );
+            batch_rxs.push(batch_rx);
+            exits.push(exit_tx);
+            handles.push(handle);
+        }
+    }
+
+    let mut batches = vec![];
+    for mut rx in batch_rxs.drain(..) {
+        let mut batches_per_ix = vec![];
+        for _ in 0..n_batches {
+            let batch = rx.next().await.unwrap();
+            batches_per_ix.push(batch);
+        }
+        batches.push(batches_per_ix);
+    }
+
+    for node_ix in n_alive.into_iterator().skip(1) {
+        assert_eq!(batches[0], batches[node_ix.0]);
+    }
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+}
+
+#[tokio::test]
+async fn small_gossip_all_alive() {
+    honest_members_agree_on_batches_with_gossip(4.into(), 4.into(), 1, 5, 1.0).await;
+}
+
+#[tokio::test]
+async fn medium_gossip_all_alive() {
+    honest_members_agree_on_batches_with_gossip(16.into(), 16.into(), 3, 5, 1.0).await;
+}
+
+#[tokio::test]
+async fn medium_gossip_five_crashes_unreliable_network() {
+    honest_members_agree_on_batches_with_gossip(16.into(), 11.into(), 3, 5, 0.9).await;
+}
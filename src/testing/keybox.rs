@@ -0,0 +1,41 @@
+use crate::{
+    member::NewestUnitResponse,
+    nodes::NodeMap,
+    testing::mock::Hasher64,
+    units::{ControlHash, FullUnit, PreUnit},
+    Data, KeyBox, Round, SessionId, Signed,
+};
+
+/// Signs with `keybox` a unit of its member carrying `data`, and then a response to a request
+/// for the newest unit of the member carrying that unit, just as the protocol would. Returns
+/// whether `keybox` accepts both signatures. Useful for testing [`KeyBox`] implementations which
+/// inspect the signed messages, e.g. ones limiting their size.
+pub async fn sign_unit_and_newest_response<KB: KeyBox, D: Data>(
+    keybox: &KB,
+    session_id: SessionId,
+    round: Round,
+    data: D,
+) -> bool {
+    let index = keybox.index();
+    let control_hash = ControlHash::<Hasher64>::new(&NodeMap::new_with_len(keybox.node_count()));
+    let unit = FullUnit::new(PreUnit::new(index, round, control_hash), data, session_id);
+    let unit = match Signed::sign(unit, keybox)
+        .await
+        .into_unchecked()
+        .check(keybox)
+    {
+        Ok(unit) => unit.into_unchecked(),
+        Err(_) => return false,
+    };
+    let response = NewestUnitResponse {
+        requester: index,
+        responder: index,
+        unit: Some(unit),
+        salt: 0,
+    };
+    Signed::sign(response, keybox)
+        .await
+        .into_unchecked()
+        .check(keybox)
+        .is_ok()
+}
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
-use codec::{Decode, Encode};
-use log::{debug, error};
+use codec::{Compact, Decode, Encode, Error, Input};
+use log::debug;
+#[cfg(test)]
 use parking_lot::Mutex;
 
 use futures::{
@@ -8,28 +9,37 @@ use futures::{
         mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
         oneshot,
     },
-    Future, StreamExt,
+    Future, FutureExt, StreamExt,
 };
 
 use std::{
     cell::{Cell, RefCell},
-    collections::{hash_map::DefaultHasher, HashMap},
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, BinaryHeap, HashMap},
     hash::Hasher as StdHasher,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    exponential_slowdown, run_session,
+    exponential_slowdown, run_session_with_extras,
+    testing::network_model::{Links, NetworkModel},
+    units::UnitCoord,
+    ApplicationChannels, Clock, Config, Data as DataT, DataIO as DataIOT, DecodingLimits,
+    DelayConfig, Dissemination, Hasher, Index, KeyBox as KeyBoxT, MultiKeychain as MultiKeychainT,
+    MultiVerifier, Network as NetworkT, NodeCount, NodeIndex, OrderedBatch,
+    PartialMultisignature as PartialMultisignatureT, Recipient, Round, SendError, SessionExtras,
+    Signature as SignatureT, Sleep, SpawnHandle, SystemClock, TaskHandle, Verifier,
+};
+#[cfg(test)]
+use crate::{
     runway::{NotificationIn, NotificationOut},
-    units::{Unit, UnitCoord},
-    Config, DataIO as DataIOT, DelayConfig, Hasher, Index, KeyBox as KeyBoxT,
-    MultiKeychain as MultiKeychainT, Network as NetworkT, NodeCount, NodeIndex, OrderedBatch,
-    PartialMultisignature as PartialMultisignatureT, Recipient, Round, SpawnHandle, TaskHandle,
+    units::Unit,
 };
 
+/// Initializes logging to the test output, at all levels.
 pub fn init_log() {
     let _ = env_logger::builder()
         .filter_level(log::LevelFilter::max())
@@ -37,14 +47,29 @@ pub fn init_log() {
         .try_init();
 }
 
+fn request_delay(t: usize) -> Duration {
+    exponential_slowdown(t, 50.0, 2, 1.5).min(Duration::from_millis(500))
+}
+
+/// A configuration with delays short enough for tests running all the members in one process.
 pub fn gen_config(node_ix: NodeIndex, n_members: NodeCount) -> Config {
     let delay_config = DelayConfig {
         tick_interval: Duration::from_millis(5),
-        requests_interval: Duration::from_millis(50),
         unit_broadcast_delay: Arc::new(|t| exponential_slowdown(t, 100.0, 1, 3.0)),
         //100, 100, 300, 900, 2700, ...
+        coord_request_delay: Arc::new(request_delay),
+        //50, 50, 50, 75, 112, ..., 500, 500, ...
+        coord_request_recipients: Arc::new(|t| 1 << t.min(2)),
+        //1, 2, 4, 4, ...
+        parent_request_delay: Arc::new(request_delay),
+        parent_request_recipients: Arc::new(|t| 1 << t.min(2)),
+        newest_request_delay: Arc::new(|t| exponential_slowdown(t, 100.0, 1, 3.0)),
+        request_attempts_limit: None,
+        network_congestion_delay: Duration::from_millis(50),
+        unit_rebroadcast_horizon: None,
         unit_creation_delay: Arc::new(|t| exponential_slowdown(t, 50.0, usize::MAX, 1.000)),
         //50, 50, 50, 50, ...
+        unit_verification_window: Duration::from_millis(1),
     };
     Config {
         node_ix,
@@ -52,6 +77,9 @@ pub fn gen_config(node_ix: NodeIndex, n_members: NodeCount) -> Config {
         n_members,
         delay_config,
         max_round: 5000,
+        dissemination: Dissemination::Broadcast,
+        verification_workers: 2,
+        clock: Arc::new(SystemClock),
     }
 }
 
@@ -70,6 +98,7 @@ impl Hasher for Hasher64 {
     }
 }
 
+#[cfg(test)]
 pub(crate) type Hash64 = <Hasher64 as Hasher>::Hash;
 
 // This struct allows to create a Hub to interconnect several instances of the Consensus engine, without
@@ -78,6 +107,7 @@ pub(crate) type Hash64 = <Hasher64 as Hasher>::Hash;
 // Hub should be used to run simple tests in honest scenarios only.
 // Usage: 1) create an instance using new(n_members), 2) connect all n_members instances, 0, 1, 2, ..., n_members - 1.
 // 3) run the HonestHub instance as a Future.
+#[cfg(test)]
 pub(crate) struct HonestHub {
     n_members: usize,
     ntfct_out_rxs: HashMap<NodeIndex, UnboundedReceiver<NotificationOut<Hasher64>>>,
@@ -85,6 +115,7 @@ pub(crate) struct HonestHub {
     units_by_coord: HashMap<UnitCoord, Unit<Hasher64>>,
 }
 
+#[cfg(test)]
 impl HonestHub {
     pub(crate) fn new(n_members: usize) -> Self {
         HonestHub {
@@ -162,6 +193,7 @@ impl HonestHub {
     }
 }
 
+#[cfg(test)]
 impl Future for HonestHub {
     type Output = ();
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
@@ -196,6 +228,7 @@ impl Future for HonestHub {
     }
 }
 
+/// Spawns tasks on the current tokio runtime.
 #[derive(Clone)]
 pub struct Spawner {}
 
@@ -230,74 +263,185 @@ impl Default for Spawner {
     }
 }
 
+/// The [`crate::NetworkData`] sent by members using the mock types.
 pub type NetworkData = crate::NetworkData<Hasher64, Data, Signature, PartialMultisignature>;
-type NetworkReceiver = UnboundedReceiver<(NetworkData, NodeIndex)>;
-type NetworkSender = UnboundedSender<(NetworkData, NodeIndex)>;
-
-pub struct Network {
-    rx: NetworkReceiver,
-    tx: NetworkSender,
+type NetworkReceiver<H, D, S, MS> = UnboundedReceiver<(crate::NetworkData<H, D, S, MS>, NodeIndex)>;
+type NetworkSender<H, D, S, MS> = UnboundedSender<(crate::NetworkData<H, D, S, MS>, NodeIndex)>;
+type Hooks<H, D, S, MS> = RefCell<Vec<Box<dyn NetworkHook<H, D, S, MS>>>>;
+type RouterWithNetworks<H, D, S, MS> = (UnreliableRouter<H, D, S, MS>, Vec<Network<H, D, S, MS>>);
+
+/// The [`NetworkT`] of a single member connected to an [`UnreliableRouter`].
+pub struct Network<
+    H: Hasher = Hasher64,
+    D: DataT = Data,
+    S: SignatureT = Signature,
+    MS: PartialMultisignatureT = PartialMultisignature,
+> {
+    rx: NetworkReceiver<H, D, S, MS>,
+    tx: NetworkSender<H, D, S, MS>,
     peers: Vec<NodeIndex>,
     index: NodeIndex,
 }
 
-impl Network {
+impl<H: Hasher, D: DataT, S: SignatureT, MS: PartialMultisignatureT> Network<H, D, S, MS> {
     pub fn index(&self) -> NodeIndex {
         self.index
     }
 }
 
 #[async_trait::async_trait]
-impl NetworkT<Hasher64, Data, Signature, PartialMultisignature> for Network {
-    fn send(&self, data: NetworkData, recipient: Recipient) {
+impl<H: Hasher, D: DataT, S: SignatureT, MS: PartialMultisignatureT> NetworkT<H, D, S, MS>
+    for Network<H, D, S, MS>
+{
+    fn send(
+        &self,
+        data: crate::NetworkData<H, D, S, MS>,
+        recipient: Recipient,
+    ) -> Result<(), SendError> {
         use Recipient::*;
         match recipient {
             Node(node) => self
                 .tx
                 .unbounded_send((data, node))
                 .expect("send on channel should work"),
+            Nodes(nodes) => {
+                for node in nodes {
+                    self.send(data.clone(), Node(node))?;
+                }
+            }
             Everyone => {
                 for peer in self.peers.iter() {
                     if *peer != self.index {
-                        self.send(data.clone(), Node(*peer));
+                        self.send(data.clone(), Node(*peer))?;
                     }
                 }
             }
         }
+        Ok(())
     }
 
-    async fn next_event(&mut self) -> Option<NetworkData> {
+    async fn next_event(&mut self) -> Option<crate::NetworkData<H, D, S, MS>> {
         Some(self.rx.next().await?.0)
     }
 }
 
-struct Peer {
-    tx: NetworkSender,
-    rx: NetworkReceiver,
+struct Peer<H: Hasher, D: DataT, S: SignatureT, MS: PartialMultisignatureT> {
+    tx: NetworkSender<H, D, S, MS>,
+    rx: NetworkReceiver<H, D, S, MS>,
 }
 
-pub struct UnreliableRouter {
-    peers: RefCell<HashMap<NodeIndex, Peer>>,
+type DelayedMessage<H, D, S, MS> = (crate::NetworkData<H, D, S, MS>, NodeIndex, NodeIndex);
+
+/// Messages waiting for their deadlines, delivered in the order of deadlines and then of sending.
+struct DelayedMessages<H: Hasher, D: DataT, S: SignatureT, MS: PartialMultisignatureT> {
+    deadlines: BinaryHeap<Reverse<(Duration, u64)>>,
+    messages: HashMap<u64, DelayedMessage<H, D, S, MS>>,
+    next_id: u64,
+}
+
+impl<H: Hasher, D: DataT, S: SignatureT, MS: PartialMultisignatureT> DelayedMessages<H, D, S, MS> {
+    fn new() -> Self {
+        DelayedMessages {
+            deadlines: BinaryHeap::new(),
+            messages: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    fn push(&mut self, deadline: Duration, message: DelayedMessage<H, D, S, MS>) {
+        self.deadlines.push(Reverse((deadline, self.next_id)));
+        self.messages.insert(self.next_id, message);
+        self.next_id += 1;
+    }
+
+    fn next_deadline(&self) -> Option<Duration> {
+        self.deadlines
+            .peek()
+            .map(|Reverse((deadline, _))| *deadline)
+    }
+
+    fn pop_due(&mut self, elapsed: Duration) -> Option<DelayedMessage<H, D, S, MS>> {
+        if self.next_deadline()? > elapsed {
+            return None;
+        }
+        let Reverse((_, id)) = self.deadlines.pop()?;
+        self.messages.remove(&id)
+    }
+}
+
+/// Passes messages between the connected [`Network`]s, dropping some of them at random and
+/// letting the [`NetworkHook`]s inspect and modify the rest. Has to be polled as a future.
+///
+/// Messages are passed on immediately, unless a [`NetworkModel`] is set with
+/// [`UnreliableRouter::set_network_model`], in which case they are delayed, duplicated or lost
+/// according to the model, with its partitions timed from the creation of the router. Time is
+/// measured with the [`Clock`] set with [`UnreliableRouter::set_clock`], [`SystemClock`] by
+/// default.
+pub struct UnreliableRouter<
+    H: Hasher = Hasher64,
+    D: DataT = Data,
+    S: SignatureT = Signature,
+    MS: PartialMultisignatureT = PartialMultisignature,
+> {
+    peers: RefCell<HashMap<NodeIndex, Peer<H, D, S, MS>>>,
     peer_list: Vec<NodeIndex>,
-    hook_list: RefCell<Vec<Box<dyn NetworkHook>>>,
+    hook_list: Hooks<H, D, S, MS>,
     reliability: f64, //a number in the range [0, 1], 1.0 means perfect reliability, 0.0 means no message gets through
+    links: Option<Links>,
+    clock: Arc<dyn Clock>,
+    start: Instant,
+    delayed: DelayedMessages<H, D, S, MS>,
+    timer: Option<Sleep>,
 }
 
-impl UnreliableRouter {
-    pub(crate) fn new(peer_list: Vec<NodeIndex>, reliability: f64) -> Self {
+impl<H: Hasher, D: DataT, S: SignatureT, MS: PartialMultisignatureT> UnreliableRouter<H, D, S, MS> {
+    pub fn new(peer_list: Vec<NodeIndex>, reliability: f64) -> Self {
         UnreliableRouter {
             peers: RefCell::new(HashMap::new()),
             peer_list,
             hook_list: RefCell::new(Vec::new()),
             reliability,
+            links: None,
+            clock: Arc::new(SystemClock),
+            start: Instant::now(),
+            delayed: DelayedMessages::new(),
+            timer: None,
         }
     }
 
-    pub fn add_hook<HK: NetworkHook + 'static>(&mut self, hook: HK) {
+    /// Makes the router measure time with `clock`, starting from now.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.start = clock.now();
+        self.clock = clock;
+    }
+
+    /// Makes the router deliver messages according to the model rather than immediately.
+    pub fn set_network_model(&mut self, model: NetworkModel) {
+        self.links = Some(Links::new(model));
+    }
+
+    pub fn add_hook<HK: NetworkHook<H, D, S, MS> + 'static>(&mut self, hook: HK) {
         self.hook_list.borrow_mut().push(Box::new(hook));
     }
 
-    pub(crate) fn connect_peer(&mut self, peer: NodeIndex) -> Network {
+    fn deliver(
+        &self,
+        data: crate::NetworkData<H, D, S, MS>,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    ) {
+        if let Some(peer) = self.peers.borrow().get(&recipient) {
+            peer.tx
+                .unbounded_send((data, sender))
+                .expect("channel should be open");
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.clock.now().saturating_duration_since(self.start)
+    }
+
+    pub fn connect_peer(&mut self, peer: NodeIndex) -> Network<H, D, S, MS> {
         assert!(
             self.peer_list.iter().any(|p| *p == peer),
             "Must connect a peer in the list."
@@ -322,7 +466,15 @@ impl UnreliableRouter {
     }
 }
 
-impl Future for UnreliableRouter {
+// Nothing in the router relies on being pinned.
+impl<H: Hasher, D: DataT, S: SignatureT, MS: PartialMultisignatureT> Unpin
+    for UnreliableRouter<H, D, S, MS>
+{
+}
+
+impl<H: Hasher, D: DataT, S: SignatureT, MS: PartialMultisignatureT> Future
+    for UnreliableRouter<H, D, S, MS>
+{
     type Output = ();
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = &mut self;
@@ -355,13 +507,41 @@ impl Future for UnreliableRouter {
                 continue;
             }
 
-            if let Some(peer) = this.peers.borrow().get(&recipient) {
-                for hook in this.hook_list.borrow_mut().iter_mut() {
-                    hook.update_state(&mut data, sender, recipient);
+            if !this.peers.borrow().contains_key(&recipient) {
+                continue;
+            }
+            for hook in this.hook_list.borrow_mut().iter_mut() {
+                hook.update_state(&mut data, sender, recipient);
+            }
+            let elapsed = this.elapsed();
+            match this.links.as_mut() {
+                Some(links) => {
+                    let size = data.encoded_size();
+                    let deliveries =
+                        links.deliveries(&mut rand::thread_rng(), elapsed, sender, recipient, size);
+                    for delay in deliveries {
+                        this.delayed
+                            .push(elapsed + delay, (data.clone(), sender, recipient));
+                    }
                 }
-                peer.tx
-                    .unbounded_send((data, sender))
-                    .expect("channel should be open");
+                None => this.deliver(data, sender, recipient),
+            }
+        }
+        loop {
+            let elapsed = this.elapsed();
+            while let Some((data, sender, recipient)) = this.delayed.pop_due(elapsed) {
+                this.deliver(data, sender, recipient);
+            }
+            this.timer = None;
+            let deadline = match this.delayed.next_deadline() {
+                Some(deadline) => deadline,
+                None => break,
+            };
+            let mut timer = this.clock.sleep(deadline.saturating_sub(elapsed));
+            // this call is responsible for waking this Future when the next message is due
+            if timer.poll_unpin(cx).is_pending() {
+                this.timer = Some(timer);
+                break;
             }
         }
         if this.peers.borrow().is_empty() {
@@ -372,15 +552,29 @@ impl Future for UnreliableRouter {
     }
 }
 
-pub trait NetworkHook: Send {
-    fn update_state(&mut self, data: &mut NetworkData, sender: NodeIndex, recipient: NodeIndex);
+/// Called by the [`UnreliableRouter`] for every message it passes on.
+pub trait NetworkHook<
+    H: Hasher = Hasher64,
+    D: DataT = Data,
+    S: SignatureT = Signature,
+    MS: PartialMultisignatureT = PartialMultisignature,
+>: Send
+{
+    fn update_state(
+        &mut self,
+        data: &mut crate::NetworkData<H, D, S, MS>,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+    );
 }
 
+#[cfg(test)]
 #[derive(Clone)]
 pub(crate) struct AlertHook {
     alerts_sent_by_connection: Arc<Mutex<HashMap<(NodeIndex, NodeIndex), usize>>>,
 }
 
+#[cfg(test)]
 impl AlertHook {
     pub(crate) fn new() -> Self {
         AlertHook {
@@ -400,6 +594,7 @@ impl AlertHook {
     }
 }
 
+#[cfg(test)]
 impl NetworkHook for AlertHook {
     fn update_state(&mut self, data: &mut NetworkData, sender: NodeIndex, recipient: NodeIndex) {
         use crate::{alerts::AlertMessage::*, network::NetworkDataInner::*};
@@ -413,23 +608,32 @@ impl NetworkHook for AlertHook {
     }
 }
 
+/// Data identified by the coordinates of the unit it was created for.
 #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Hash)]
 pub struct Data {
     coord: UnitCoord,
     variant: u32,
 }
 
+#[cfg(test)]
 impl Data {
-    #[cfg(test)]
     pub(crate) fn new(coord: UnitCoord, variant: u32) -> Self {
         Data { coord, variant }
     }
 }
 
+impl Data {
+    /// The member which proposed the data.
+    pub fn creator(&self) -> NodeIndex {
+        self.coord.creator()
+    }
+}
+
+/// An empty signature, accepted by every [`KeyBox`].
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
 pub struct Signature {}
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Encode, Decode)]
 pub struct PartialMultisignature {
     signed_by: Vec<NodeIndex>,
 }
@@ -446,12 +650,24 @@ impl PartialMultisignatureT for PartialMultisignature {
         signed_by.push(index);
         Self { signed_by }
     }
+
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let len = <Compact<u32>>::decode(input)?.0 as usize;
+        if len > limits.n_members.0 {
+            return Err(Error::from("More signers than members of the committee."));
+        }
+        let signed_by = (0..len)
+            .map(|_| NodeIndex::decode(input))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { signed_by })
+    }
 }
 
-pub(crate) struct DataIO {
+/// Provides a new [`Data`] item for every unit and ignores the ordered batches.
+pub struct DataIO {
     ix: NodeIndex,
+    variant: u32,
     round_counter: Cell<Round>,
-    tx: UnboundedSender<OrderedBatch<Data>>,
 }
 
 impl DataIOT<Data> for DataIO {
@@ -459,35 +675,59 @@ impl DataIOT<Data> for DataIO {
     fn get_data(&self) -> Data {
         let coord = UnitCoord::new(self.round_counter.get(), self.ix);
         self.round_counter.set(self.round_counter.get() + 1);
-        Data { coord, variant: 0 }
+        Data {
+            coord,
+            variant: self.variant,
+        }
     }
-    fn send_ordered_batch(&mut self, data: OrderedBatch<Data>) -> Result<(), ()> {
-        self.tx.unbounded_send(data).map_err(|e| {
-            error!(target: "data-io", "Error when sending data from DataIO {:?}.", e);
-        })
+    fn send_ordered_batch(&mut self, _data: OrderedBatch<Data>) -> Result<(), ()> {
+        Ok(())
     }
 }
 
 impl DataIO {
-    pub(crate) fn new(ix: NodeIndex) -> (Self, UnboundedReceiver<OrderedBatch<Data>>) {
-        let (tx, rx) = unbounded();
-        let data_io = DataIO {
+    pub fn new(ix: NodeIndex) -> Self {
+        DataIO::with_variant(ix, 0)
+    }
+
+    // Proposes data distinct from the one proposed by the member with the default variant.
+    pub(crate) fn with_variant(ix: NodeIndex, variant: u32) -> Self {
+        DataIO {
             ix,
+            variant,
             round_counter: Cell::new(0),
-            tx,
-        };
-        (data_io, rx)
+        }
+    }
+}
+
+// Passes the ordered batches to the wrapped DataIO and to the test.
+pub(crate) struct CollectingDataIO<D: DataT, DP: DataIOT<D>> {
+    pub(crate) data_io: DP,
+    pub(crate) batches: UnboundedSender<OrderedBatch<D>>,
+}
+
+impl<D: DataT, DP: DataIOT<D>> DataIOT<D> for CollectingDataIO<D, DP> {
+    type Error = DP::Error;
+    fn get_data(&self) -> D {
+        self.data_io.get_data()
+    }
+    fn send_ordered_batch(&mut self, data: OrderedBatch<D>) -> Result<(), DP::Error> {
+        if self.batches.unbounded_send(data.clone()).is_err() {
+            debug!(target: "data-io", "Nobody collects the ordered batches anymore.");
+        }
+        self.data_io.send_ordered_batch(data)
     }
 }
 
+/// Not secure at all, all the signatures are valid.
 #[derive(Clone)]
-pub(crate) struct KeyBox {
+pub struct KeyBox {
     count: NodeCount,
     ix: NodeIndex,
 }
 
 impl KeyBox {
-    pub(crate) fn new(count: NodeCount, ix: NodeIndex) -> Self {
+    pub fn new(count: NodeCount, ix: NodeIndex) -> Self {
         KeyBox { count, ix }
     }
 }
@@ -498,51 +738,44 @@ impl Index for KeyBox {
     }
 }
 
-#[async_trait]
-impl KeyBoxT for KeyBox {
+impl Verifier for KeyBox {
     type Signature = Signature;
 
     fn node_count(&self) -> NodeCount {
         self.count
     }
 
+    fn verify(&self, _msg: &[u8], _sgn: &Signature, _index: NodeIndex) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl KeyBoxT for KeyBox {
     async fn sign(&self, _msg: &[u8]) -> Signature {
         Signature {}
     }
+}
 
-    fn verify(&self, _msg: &[u8], _sgn: &Signature, _index: NodeIndex) -> bool {
-        true
+impl MultiVerifier for KeyBox {
+    type PartialMultisignature = PartialMultisignature;
+    fn is_complete(&self, _: &[u8], partial: &Self::PartialMultisignature) -> bool {
+        (self.count * 2) / 3 < NodeCount(partial.signed_by.len())
     }
 }
 
 impl MultiKeychainT for KeyBox {
-    type PartialMultisignature = PartialMultisignature;
     fn from_signature(&self, _: &Self::Signature, index: NodeIndex) -> Self::PartialMultisignature {
         let signed_by = vec![index];
         PartialMultisignature { signed_by }
     }
-    fn is_complete(&self, _: &[u8], partial: &Self::PartialMultisignature) -> bool {
-        (self.count * 2) / 3 < NodeCount(partial.signed_by.len())
-    }
 }
 
-pub(crate) async fn run_honest_member<
-    N: 'static + NetworkT<Hasher64, Data, Signature, PartialMultisignature>,
->(
-    config: Config,
-    network: N,
-    data_io: DataIO,
-    keybox: KeyBox,
-    spawn_handle: Spawner,
-    exit: oneshot::Receiver<()>,
-) {
-    run_session(config, network, data_io, keybox, spawn_handle, exit).await
-}
-
-pub fn configure_network(
+/// Creates a router and networks for all the `n_members` members, connected to it.
+pub fn configure_network<H: Hasher, D: DataT, S: SignatureT, MS: PartialMultisignatureT>(
     n_members: NodeCount,
     reliability: f64,
-) -> (UnreliableRouter, Vec<Network>) {
+) -> RouterWithNetworks<H, D, S, MS> {
     let peer_list = n_members.into_iterator().collect();
     let mut router = UnreliableRouter::new(peer_list, reliability);
     let mut networks = Vec::new();
@@ -553,6 +786,8 @@ pub fn configure_network(
     (router, networks)
 }
 
+/// Spawns an honest member using the mock types with the [`gen_config`] configuration.
+/// Returns the stream of batches it orders, the sender stopping it and the handle of its task.
 pub fn spawn_honest_member(
     spawner: Spawner,
     node_index: NodeIndex,
@@ -563,26 +798,98 @@ pub fn spawn_honest_member(
     oneshot::Sender<()>,
     TaskHandle,
 ) {
-    let (data_io, rx_batch) = DataIO::new(node_index);
-    let config = gen_config(node_index, n_members);
+    spawn_honest_member_with_channels(
+        spawner,
+        node_index,
+        n_members,
+        network,
+        ApplicationChannels::new(),
+    )
+}
+
+/// Like [`spawn_honest_member`], passing additionally the application channels to the member.
+pub fn spawn_honest_member_with_channels(
+    spawner: Spawner,
+    node_index: NodeIndex,
+    n_members: NodeCount,
+    network: impl 'static + NetworkT<Hasher64, Data, Signature, PartialMultisignature>,
+    application_channels: ApplicationChannels,
+) -> (
+    UnboundedReceiver<OrderedBatch<Data>>,
+    oneshot::Sender<()>,
+    TaskHandle,
+) {
+    spawn_honest_member_with_config(
+        spawner,
+        gen_config(node_index, n_members),
+        network,
+        application_channels,
+    )
+}
+
+/// Like [`spawn_honest_member_with_channels`], with the given configuration.
+pub fn spawn_honest_member_with_config(
+    spawner: Spawner,
+    config: Config,
+    network: impl 'static + NetworkT<Hasher64, Data, Signature, PartialMultisignature>,
+    application_channels: ApplicationChannels,
+) -> (
+    UnboundedReceiver<OrderedBatch<Data>>,
+    oneshot::Sender<()>,
+    TaskHandle,
+) {
+    let data_io = DataIO::new(config.node_ix);
+    let keybox = KeyBox::new(config.n_members, config.node_ix);
+    spawn_honest_member_generic(
+        spawner,
+        config,
+        network,
+        application_channels,
+        data_io,
+        keybox,
+    )
+}
+
+/// Spawns an honest member using the given [`DataIOT`] and [`MultiKeychainT`] implementations.
+/// Returns the stream of batches it orders, the sender stopping it and the handle of its task.
+pub fn spawn_honest_member_generic<
+    H: Hasher,
+    D: DataT,
+    DP: DataIOT<D> + Send + 'static,
+    MK: MultiKeychainT,
+>(
+    spawner: Spawner,
+    config: Config,
+    network: impl 'static + NetworkT<H, D, MK::Signature, MK::PartialMultisignature>,
+    application_channels: ApplicationChannels,
+    data_io: DP,
+    keybox: MK,
+) -> (
+    UnboundedReceiver<OrderedBatch<D>>,
+    oneshot::Sender<()>,
+    TaskHandle,
+) {
+    let (batches, batches_rx) = unbounded();
+    let data_io = CollectingDataIO { data_io, batches };
     let (exit_tx, exit_rx) = oneshot::channel();
     let spawner_inner = spawner.clone();
     let member_task = async move {
-        let keybox = KeyBox::new(n_members, node_index);
-        run_honest_member(
+        run_session_with_extras(
             config,
             network,
             data_io,
             keybox,
-            spawner_inner.clone(),
+            spawner_inner,
+            SessionExtras::default().with_application_channels(application_channels),
             exit_rx,
         )
         .await
     };
     let handle = spawner.spawn_essential("member", member_task);
-    (rx_batch, exit_tx, handle)
+    (batches_rx, exit_tx, handle)
 }
 
+#[cfg(test)]
 pub fn complete_oneshot<T: std::fmt::Debug>(t: T) -> oneshot::Receiver<T> {
     let (tx, rx) = oneshot::channel();
     tx.send(t).unwrap();
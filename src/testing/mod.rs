@@ -1,18 +1,56 @@
+//! Helpers for testing applications of AlephBFT, available with the `testing` feature.
+//!
+//! [`configure_network`] creates an in-process [`UnreliableRouter`] connecting the networks of
+//! a whole committee, which may drop messages and lets [`NetworkHook`]s observe or modify them.
+//! The members are started with [`spawn_honest_member`], using the mock [`Data`], [`DataIO`] and
+//! [`KeyBox`], or with [`spawn_honest_member_generic`], using any `DataIO` and `MultiKeychain`
+//! implementations. Both return a stream of the batches ordered by the member. A
+//! [`NetworkModel`] set on the router adds latencies, bandwidth limits, reordering, duplication
+//! and timed [`Partition`]s to the messages it passes.
+//!
+//! [`BatchChecker`] consumes the streams of batches while the members run, and reports the first
+//! divergence between them, items ordered twice, or members whose data never gets ordered.
+//!
+//! [`Simulation`] instead runs a whole committee on a single thread, with virtual time and a
+//! seeded [`NetworkModel`], so that its runs do not depend on timing and can be replayed exactly.
+//! It can also crash members and start them again from scratch, as described by [`Restart`]s.
+//!
+//! [`sign_unit_and_newest_response`] signs the messages carrying units with a
+//! [`KeyBox`](crate::KeyBox) under test.
+
 #[cfg(test)]
 mod alerts;
 #[cfg(test)]
 mod byzantine;
 #[cfg(test)]
+mod channels;
+mod checker;
+#[cfg(test)]
 mod consensus;
 #[cfg(test)]
 mod crash;
 #[cfg(test)]
 mod dag;
 #[cfg(test)]
+mod gossip;
+mod keybox;
 pub(crate) mod mock;
+mod network_model;
 #[cfg(test)]
 mod rmc;
 #[cfg(test)]
 pub(crate) mod signed;
+mod simulation;
 #[cfg(test)]
 mod unreliable;
+
+pub use checker::{BatchChecker, Inconsistency};
+pub use keybox::sign_unit_and_newest_response;
+pub use mock::{
+    configure_network, gen_config, init_log, spawn_honest_member, spawn_honest_member_generic,
+    spawn_honest_member_with_channels, spawn_honest_member_with_config, Data, DataIO, Hasher64,
+    KeyBox, Network, NetworkData, NetworkHook, PartialMultisignature, Signature, Spawner,
+    UnreliableRouter,
+};
+pub use network_model::{Latency, NetworkModel, Partition};
+pub use simulation::{Restart, Simulation, SimulationOutcome};
@@ -0,0 +1,252 @@
+use crate::NodeIndex;
+use rand::Rng;
+use std::{collections::HashMap, time::Duration};
+
+/// The time interval in which the committee is split into groups unable to communicate.
+#[derive(Clone, Debug)]
+pub struct Partition {
+    /// The time since the start of the network at which the partition begins.
+    pub start: Duration,
+    /// The time since the start of the network at which the partition heals.
+    pub end: Duration,
+    /// Members in different groups cannot communicate, and members in no group are isolated.
+    pub groups: Vec<Vec<NodeIndex>>,
+}
+
+impl Partition {
+    fn separates(&self, elapsed: Duration, sender: NodeIndex, recipient: NodeIndex) -> bool {
+        if elapsed < self.start || elapsed >= self.end {
+            return false;
+        }
+        !self
+            .groups
+            .iter()
+            .any(|group| group.contains(&sender) && group.contains(&recipient))
+    }
+}
+
+/// The distribution of the time messages take to travel over a link.
+#[derive(Clone, Debug)]
+pub enum Latency {
+    /// Uniformly distributed between the two bounds.
+    Uniform(Duration, Duration),
+    /// The first value, exceeded by an exponentially distributed time with the second value as
+    /// its mean. The long tail resembles the latencies of wide area networks.
+    Exponential(Duration, Duration),
+}
+
+impl Latency {
+    fn sample<R: Rng>(&self, rng: &mut R) -> Duration {
+        match self {
+            Latency::Uniform(min, max) if max > min => rng.gen_range(*min..=*max),
+            Latency::Uniform(min, _) => *min,
+            Latency::Exponential(min, mean) => {
+                let sample: f64 = rng.gen();
+                *min + mean.mul_f64(-(1.0 - sample).ln())
+            }
+        }
+    }
+}
+
+/// The model of the network connecting the members.
+#[derive(Clone, Debug)]
+pub struct NetworkModel {
+    /// The minimal time a message takes to reach its recipient, unless its link has a latency
+    /// of its own in `link_latencies`.
+    pub min_latency: Duration,
+    /// The maximal time a message takes to reach its recipient. The latency of every message
+    /// is chosen uniformly at random, so messages are delivered out of order.
+    pub max_latency: Duration,
+    /// The latencies of the links from the first member to the second one.
+    pub link_latencies: HashMap<(NodeIndex, NodeIndex), Latency>,
+    /// The probability that a message is lost.
+    pub loss: f64,
+    /// Messages sent while a partition separates their sender and recipient are lost.
+    pub partitions: Vec<Partition>,
+    /// The number of bytes per second every link carries, unlimited if `None`. Messages wait
+    /// until the ones sent earlier over the same link are transmitted.
+    pub bandwidth: Option<u64>,
+    /// The probability that a message is held back for another latency of its link, so that
+    /// messages sent after it overtake it.
+    pub reordering: f64,
+    /// The probability that a message is delivered twice.
+    pub duplication: f64,
+}
+
+impl Default for NetworkModel {
+    fn default() -> Self {
+        NetworkModel {
+            min_latency: Duration::from_millis(10),
+            max_latency: Duration::from_millis(100),
+            link_latencies: HashMap::new(),
+            loss: 0.0,
+            partitions: Vec::new(),
+            bandwidth: None,
+            reordering: 0.0,
+            duplication: 0.0,
+        }
+    }
+}
+
+/// The state of the links of a [`NetworkModel`], deciding the fate of the messages sent over them.
+pub(crate) struct Links {
+    model: NetworkModel,
+    busy_until: HashMap<(NodeIndex, NodeIndex), Duration>,
+}
+
+impl Links {
+    pub(crate) fn new(model: NetworkModel) -> Self {
+        Links {
+            model,
+            busy_until: HashMap::new(),
+        }
+    }
+
+    fn latency<R: Rng>(&self, rng: &mut R, sender: NodeIndex, recipient: NodeIndex) -> Duration {
+        match self.model.link_latencies.get(&(sender, recipient)) {
+            Some(latency) => latency.sample(rng),
+            None => Latency::Uniform(self.model.min_latency, self.model.max_latency).sample(rng),
+        }
+    }
+
+    /// The delays after which the copies of a message of `size` bytes, sent `elapsed` after the
+    /// start of the network, reach the recipient. Empty if the message is lost.
+    pub(crate) fn deliveries<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        elapsed: Duration,
+        sender: NodeIndex,
+        recipient: NodeIndex,
+        size: usize,
+    ) -> Vec<Duration> {
+        if self
+            .model
+            .partitions
+            .iter()
+            .any(|partition| partition.separates(elapsed, sender, recipient))
+        {
+            return Vec::new();
+        }
+        if rng.gen_bool(self.model.loss) {
+            return Vec::new();
+        }
+        let queueing = match self.model.bandwidth {
+            Some(bandwidth) => {
+                let busy_until = self
+                    .busy_until
+                    .entry((sender, recipient))
+                    .or_insert(elapsed);
+                let transmission = Duration::from_secs_f64(size as f64 / bandwidth as f64);
+                *busy_until = (*busy_until).max(elapsed) + transmission;
+                *busy_until - elapsed
+            }
+            None => Duration::from_secs(0),
+        };
+        let mut deliveries = vec![queueing + self.latency(rng, sender, recipient)];
+        if self.model.duplication > 0.0 && rng.gen_bool(self.model.duplication) {
+            deliveries.push(queueing + self.latency(rng, sender, recipient));
+        }
+        if self.model.reordering > 0.0 {
+            for delay in deliveries.iter_mut() {
+                if rng.gen_bool(self.model.reordering) {
+                    *delay += self.latency(rng, sender, recipient);
+                }
+            }
+        }
+        deliveries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Latency, Links, NetworkModel, Partition};
+    use crate::NodeIndex;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::time::Duration;
+
+    const A: NodeIndex = NodeIndex(0);
+    const B: NodeIndex = NodeIndex(1);
+
+    fn fixed_latency_model() -> NetworkModel {
+        NetworkModel {
+            min_latency: Duration::from_millis(10),
+            max_latency: Duration::from_millis(10),
+            ..NetworkModel::default()
+        }
+    }
+
+    #[test]
+    fn drops_messages_only_during_partition() {
+        let mut links = Links::new(NetworkModel {
+            partitions: vec![Partition {
+                start: Duration::from_secs(1),
+                end: Duration::from_secs(2),
+                groups: vec![vec![A], vec![B]],
+            }],
+            ..fixed_latency_model()
+        });
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut deliveries_at =
+            |seconds| links.deliveries(&mut rng, Duration::from_secs(seconds), A, B, 100);
+        assert_eq!(deliveries_at(0), vec![Duration::from_millis(10)]);
+        assert!(deliveries_at(1).is_empty());
+        assert_eq!(deliveries_at(2), vec![Duration::from_millis(10)]);
+    }
+
+    #[test]
+    fn queues_messages_on_saturated_link() {
+        let mut links = Links::new(NetworkModel {
+            bandwidth: Some(1000),
+            ..fixed_latency_model()
+        });
+        let mut rng = StdRng::seed_from_u64(0);
+        let now = Duration::from_secs(0);
+        assert_eq!(
+            links.deliveries(&mut rng, now, A, B, 100),
+            vec![Duration::from_millis(110)]
+        );
+        assert_eq!(
+            links.deliveries(&mut rng, now, A, B, 100),
+            vec![Duration::from_millis(210)]
+        );
+        // Other links are not affected.
+        assert_eq!(
+            links.deliveries(&mut rng, now, B, A, 100),
+            vec![Duration::from_millis(110)]
+        );
+    }
+
+    #[test]
+    fn uses_latency_of_link() {
+        let mut model = fixed_latency_model();
+        model.link_latencies.insert(
+            (A, B),
+            Latency::Uniform(Duration::from_secs(1), Duration::from_secs(1)),
+        );
+        let mut links = Links::new(model);
+        let mut rng = StdRng::seed_from_u64(0);
+        let now = Duration::from_secs(0);
+        assert_eq!(
+            links.deliveries(&mut rng, now, A, B, 100),
+            vec![Duration::from_secs(1)]
+        );
+        assert_eq!(
+            links.deliveries(&mut rng, now, B, A, 100),
+            vec![Duration::from_millis(10)]
+        );
+    }
+
+    #[test]
+    fn duplicates_and_holds_back_messages() {
+        let mut links = Links::new(NetworkModel {
+            duplication: 1.0,
+            reordering: 1.0,
+            ..fixed_latency_model()
+        });
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(
+            links.deliveries(&mut rng, Duration::from_secs(0), A, B, 100),
+            vec![Duration::from_millis(20), Duration::from_millis(20)]
+        );
+    }
+}
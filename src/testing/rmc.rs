@@ -2,10 +2,8 @@ use crate::{
     nodes::NodeCount,
     rmc::*,
     signed::*,
-    testing::signed::{
-        test_multi_keychain, TestMultiKeychain, TestPartialMultisignature, TestSignature,
-    },
-    NodeIndex,
+    testing::signed::{test_multi_keychains, TestMultiKeychain, TestSignature},
+    Clock, NodeIndex, Sleep,
 };
 use futures::{
     channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
@@ -13,8 +11,14 @@ use futures::{
     stream::{self, Stream},
     StreamExt,
 };
+use parking_lot::Mutex;
 use rand::Rng;
-use std::{collections::HashMap, pin::Pin, time::Duration};
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Ord, PartialOrd)]
 struct Hash {
@@ -29,30 +33,28 @@ impl Signable for Hash {
     }
 }
 
-type TestMessage = Message<Hash, TestSignature, TestPartialMultisignature>;
+type TestMessage<MK> =
+    Message<Hash, <MK as Verifier>::Signature, <MK as MultiVerifier>::PartialMultisignature>;
 
-struct TestNetwork {
-    outgoing_rx: Pin<Box<dyn Stream<Item = TestMessage>>>,
-    incoming_txs: Vec<UnboundedSender<TestMessage>>,
-    message_filter: Box<dyn FnMut(NodeIndex, TestMessage) -> bool>,
+struct TestNetwork<M> {
+    outgoing_rx: Pin<Box<dyn Stream<Item = M>>>,
+    incoming_txs: Vec<UnboundedSender<M>>,
+    message_filter: Box<dyn FnMut(NodeIndex, M) -> bool>,
 }
 
-impl TestNetwork {
+impl<M: Clone + 'static> TestNetwork<M> {
     fn new(
         node_count: NodeCount,
-        message_filter: impl FnMut(NodeIndex, TestMessage) -> bool + 'static,
-    ) -> (
-        Self,
-        Vec<(UnboundedReceiver<TestMessage>, UnboundedSender<TestMessage>)>,
-    ) {
+        message_filter: impl FnMut(NodeIndex, M) -> bool + 'static,
+    ) -> (Self, Vec<(UnboundedReceiver<M>, UnboundedSender<M>)>) {
         let all_nodes: Vec<_> = (0..node_count.0).map(NodeIndex).collect();
         let (incomng_txs, incoming_rxs): (Vec<_>, Vec<_>) =
-            all_nodes.iter().map(|_| unbounded::<TestMessage>()).unzip();
+            all_nodes.iter().map(|_| unbounded::<M>()).unzip();
         let (outgoing_txs, outgoing_rxs): (Vec<_>, Vec<_>) = {
             all_nodes
                 .iter()
                 .map(|_| {
-                    let (tx, rx) = unbounded::<TestMessage>();
+                    let (tx, rx) = unbounded::<M>();
                     (tx, rx)
                 })
                 .unzip()
@@ -67,15 +69,13 @@ impl TestNetwork {
         (network, channels)
     }
 
-    fn broadcast_message(&mut self, msg: TestMessage) {
+    fn broadcast_message(&mut self, msg: M) {
         for tx in &mut self.incoming_txs {
             tx.unbounded_send(msg.clone())
                 .expect("Channel should be open");
         }
     }
-}
 
-impl TestNetwork {
     async fn run(&mut self) {
         while let Some(message) = self.outgoing_rx.next().await {
             for (i, tx) in self.incoming_txs.iter().enumerate() {
@@ -88,22 +88,54 @@ impl TestNetwork {
     }
 }
 
-fn prepare_keychains(node_count: NodeCount) -> Vec<TestMultiKeychain> {
-    (0..node_count.0)
-        .map(|i| test_multi_keychain(node_count, i.into()))
-        .collect()
+/// A keychain able to produce signatures and multisignatures which it rejects.
+trait BadSignatures: MultiKeychain {
+    fn bad_signature(&self) -> Self::Signature;
+    fn bad_multisignature(&self) -> Self::PartialMultisignature;
+}
+
+impl BadSignatures for TestMultiKeychain {
+    fn bad_signature(&self) -> TestSignature {
+        TestSignature {
+            msg: Vec::new(),
+            index: 111.into(),
+        }
+    }
+
+    fn bad_multisignature(&self) -> Self::PartialMultisignature {
+        SignatureSet::new(self.node_count())
+    }
+}
+
+#[cfg(feature = "bls")]
+impl BadSignatures for crate::bls::Keychain {
+    fn bad_signature(&self) -> Self::Signature {
+        crate::bls::Signature::from_bytes(&bls12_381::G1Affine::generator().to_compressed())
+            .expect("the generator is a valid point")
+    }
+
+    // Claims signatures of all the members, so that only the pairing check can reject it.
+    fn bad_multisignature(&self) -> Self::PartialMultisignature {
+        let signature = self.bad_signature();
+        self.node_count()
+            .into_iterator()
+            .skip(1)
+            .fold(self.from_signature(&signature, 0.into()), |partial, i| {
+                partial.add_signature(&signature, i)
+            })
+    }
 }
 
-struct TestData<'a> {
-    network: TestNetwork,
-    rmcs: Vec<ReliableMulticast<'a, Hash, TestMultiKeychain>>,
+struct TestData<MK: MultiKeychain> {
+    network: TestNetwork<TestMessage<MK>>,
+    rmcs: Vec<ReliableMulticast<Hash, MK>>,
 }
 
-impl<'a> TestData<'a> {
+impl<MK: MultiKeychain> TestData<MK> {
     fn new(
         node_count: NodeCount,
-        keychains: &'a [TestMultiKeychain],
-        message_filter: impl FnMut(NodeIndex, TestMessage) -> bool + 'static,
+        keychains: &[MK],
+        message_filter: impl FnMut(NodeIndex, TestMessage<MK>) -> bool + 'static,
     ) -> Self {
         let (network, channels) = TestNetwork::new(node_count, message_filter);
         let mut rmcs = Vec::new();
@@ -111,7 +143,7 @@ impl<'a> TestData<'a> {
             let rmc = ReliableMulticast::new(
                 rx,
                 tx,
-                &keychains[i],
+                keychains[i].clone(),
                 node_count,
                 DoublingDelayScheduler::new(Duration::from_millis(1)),
             );
@@ -123,12 +155,12 @@ impl<'a> TestData<'a> {
     async fn collect_multisigned_hashes(
         mut self,
         count: usize,
-    ) -> HashMap<NodeIndex, Vec<Multisigned<'a, Hash, TestMultiKeychain>>> {
+    ) -> HashMap<NodeIndex, Vec<Multisigned<Hash, MK>>> {
         let mut hashes = HashMap::new();
 
         for _ in 0..count {
             // covert each RMC into a future returning an optional unchecked multisigned hash.
-            let rmc_futures: Vec<BoxFuture<Multisigned<'a, Hash, TestMultiKeychain>>> = self
+            let rmc_futures: Vec<BoxFuture<Multisigned<Hash, MK>>> = self
                 .rmcs
                 .iter_mut()
                 .map(|rmc| rmc.next_multisigned_hash().boxed())
@@ -146,11 +178,22 @@ impl<'a> TestData<'a> {
     }
 }
 
+fn check_all_multisigned<MK: MultiKeychain>(
+    hashes: HashMap<NodeIndex, Vec<Multisigned<Hash, MK>>>,
+    node_count: NodeCount,
+    hash: Hash,
+) {
+    assert_eq!(hashes.len(), node_count.0);
+    for i in 0..node_count.0 {
+        let multisignatures = &hashes[&i.into()];
+        assert_eq!(multisignatures.len(), 1);
+        assert_eq!(multisignatures[0].as_signable(), &hash);
+    }
+}
+
 /// Create 10 honest nodes and let each of them start rmc for the same hash.
-#[tokio::test]
-async fn simple_scenario() {
-    let node_count = NodeCount(10);
-    let keychains = prepare_keychains(node_count);
+async fn simple_scenario_with<MK: MultiKeychain>(keychains: Vec<MK>) {
+    let node_count = NodeCount(keychains.len());
     let mut data = TestData::new(node_count, &keychains, |_, _| true);
 
     let hash = Hash { byte: 56 };
@@ -159,19 +202,12 @@ async fn simple_scenario() {
     }
 
     let hashes = data.collect_multisigned_hashes(node_count.0).await;
-    assert_eq!(hashes.len(), node_count.0);
-    for i in 0..node_count.0 {
-        let multisignatures = &hashes[&i.into()];
-        assert_eq!(multisignatures.len(), 1);
-        assert_eq!(multisignatures[0].as_signable(), &hash);
-    }
+    check_all_multisigned(hashes, node_count, hash);
 }
 
 /// Each message is delivered with 20% probability
-#[tokio::test]
-async fn faulty_network() {
-    let node_count = NodeCount(10);
-    let keychains = prepare_keychains(node_count);
+async fn faulty_network_with<MK: MultiKeychain>(keychains: Vec<MK>) {
+    let node_count = NodeCount(keychains.len());
     let mut rng = rand::thread_rng();
     let mut data = TestData::new(node_count, &keychains, move |_, _| rng.gen_range(0..5) == 0);
 
@@ -181,20 +217,13 @@ async fn faulty_network() {
     }
 
     let hashes = data.collect_multisigned_hashes(node_count.0).await;
-    assert_eq!(hashes.len(), node_count.0);
-    for i in 0..node_count.0 {
-        let multisignatures = &hashes[&i.into()];
-        assert_eq!(multisignatures.len(), 1);
-        assert_eq!(multisignatures[0].as_signable(), &hash);
-    }
+    check_all_multisigned(hashes, node_count, hash);
 }
 
 /// Only 7 nodes start rmc and one of the nodes which didn't start rmc
 /// is delivered only messages with complete multisignatures
-#[tokio::test]
-async fn node_hearing_only_multisignatures() {
-    let node_count = NodeCount(10);
-    let keychains = prepare_keychains(node_count);
+async fn node_hearing_only_multisignatures_with<MK: MultiKeychain>(keychains: Vec<MK>) {
+    let node_count = NodeCount(keychains.len());
     let mut data = TestData::new(node_count, &keychains, move |node_ix, message| {
         !matches!((node_ix.0, message), (0, Message::SignedHash(_)))
     });
@@ -206,42 +235,26 @@ async fn node_hearing_only_multisignatures() {
     }
 
     let hashes = data.collect_multisigned_hashes(node_count.0).await;
-    assert_eq!(hashes.len(), node_count.0);
-    for i in 0..node_count.0 {
-        let multisignatures = &hashes[&i.into()];
-        assert_eq!(multisignatures.len(), 1);
-        assert_eq!(multisignatures[0].as_signable(), &hash);
-    }
-}
-
-fn bad_signature() -> TestSignature {
-    TestSignature {
-        msg: Vec::new(),
-        index: 111.into(),
-    }
-}
-
-fn bad_multisignature(node_count: NodeCount) -> TestPartialMultisignature {
-    SignatureSet::new(node_count)
+    check_all_multisigned(hashes, node_count, hash);
 }
 
 /// 7 honest nodes and 3 dishonest nodes which emit bad signatures and multisignatures
-#[tokio::test]
-async fn bad_signatures_and_multisignatures_are_ignored() {
-    let node_count = NodeCount(10);
-    let keychains = prepare_keychains(node_count);
+async fn bad_signatures_and_multisignatures_are_ignored_with<MK: BadSignatures>(
+    keychains: Vec<MK>,
+) {
+    let node_count = NodeCount(keychains.len());
     let mut data = TestData::new(node_count, &keychains, |_, _| true);
 
     let bad_hash = Hash { byte: 65 };
-    let bad_msg = TestMessage::SignedHash(UncheckedSigned::new_with_index(
+    let bad_msg = TestMessage::<MK>::SignedHash(UncheckedSigned::new_with_index(
         bad_hash,
         0.into(),
-        bad_signature(),
+        keychains[0].bad_signature(),
     ));
     data.network.broadcast_message(bad_msg);
-    let bad_msg = TestMessage::MultisignedHash(UncheckedSigned::new(
+    let bad_msg = TestMessage::<MK>::MultisignedHash(UncheckedSigned::new(
         bad_hash,
-        bad_multisignature(node_count),
+        keychains[0].bad_multisignature(),
     ));
     data.network.broadcast_message(bad_msg);
 
@@ -251,10 +264,88 @@ async fn bad_signatures_and_multisignatures_are_ignored() {
     }
 
     let hashes = data.collect_multisigned_hashes(node_count.0).await;
-    assert_eq!(hashes.len(), node_count.0);
-    for i in 0..node_count.0 {
-        let multisignatures = &hashes[&i.into()];
-        assert_eq!(multisignatures.len(), 1);
-        assert_eq!(multisignatures[0].as_signable(), &hash);
+    check_all_multisigned(hashes, node_count, hash);
+}
+
+#[tokio::test]
+async fn simple_scenario() {
+    simple_scenario_with(test_multi_keychains(NodeCount(10))).await;
+}
+
+#[tokio::test]
+async fn faulty_network() {
+    faulty_network_with(test_multi_keychains(NodeCount(10))).await;
+}
+
+#[tokio::test]
+async fn node_hearing_only_multisignatures() {
+    node_hearing_only_multisignatures_with(test_multi_keychains(NodeCount(10))).await;
+}
+
+#[tokio::test]
+async fn bad_signatures_and_multisignatures_are_ignored() {
+    bad_signatures_and_multisignatures_are_ignored_with(test_multi_keychains(NodeCount(10))).await;
+}
+
+#[cfg(feature = "bls")]
+mod bls {
+    use crate::{nodes::NodeCount, testing::signed::bls_keychains};
+
+    #[tokio::test]
+    async fn simple_scenario() {
+        super::simple_scenario_with(bls_keychains(NodeCount(10))).await;
+    }
+
+    #[tokio::test]
+    async fn faulty_network() {
+        super::faulty_network_with(bls_keychains(NodeCount(10))).await;
+    }
+
+    #[tokio::test]
+    async fn node_hearing_only_multisignatures() {
+        super::node_hearing_only_multisignatures_with(bls_keychains(NodeCount(10))).await;
+    }
+
+    #[tokio::test]
+    async fn bad_signatures_and_multisignatures_are_ignored() {
+        super::bad_signatures_and_multisignatures_are_ignored_with(bls_keychains(NodeCount(10)))
+            .await;
+    }
+}
+
+// Completes every sleep at once, moving the time forward by its duration.
+struct FastForwardClock {
+    now: Mutex<Instant>,
+}
+
+impl Clock for FastForwardClock {
+    fn now(&self) -> Instant {
+        *self.now.lock()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        *self.now.lock() += duration;
+        Box::pin(future::ready(()))
+    }
+}
+
+#[tokio::test]
+async fn scheduler_doubles_delays_on_fast_forward_clock() {
+    let start = Instant::now();
+    let clock = Arc::new(FastForwardClock {
+        now: Mutex::new(start),
+    });
+    let mut scheduler = DoublingDelayScheduler::with_clock(Duration::from_secs(60), clock.clone());
+    scheduler.add_task(7);
+
+    let mut elapsed = Vec::new();
+    for _ in 0..4 {
+        assert_eq!(scheduler.next_task().await, Some(7));
+        elapsed.push(clock.now() - start);
     }
+    let expected: Vec<_> = [0, 60, 180, 420]
+        .iter()
+        .map(|secs| Duration::from_secs(*secs))
+        .collect();
+    assert_eq!(elapsed, expected);
 }
@@ -48,20 +48,39 @@ impl Index for TestKeyBox {
     }
 }
 
-#[async_trait]
-impl KeyBox for TestKeyBox {
+impl Verifier for TestKeyBox {
     type Signature = TestSignature;
 
     fn node_count(&self) -> NodeCount {
         self.count
     }
 
+    fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
+        index == sgn.index && msg == sgn.msg
+    }
+}
+
+#[async_trait]
+impl KeyBox for TestKeyBox {
     async fn sign(&self, msg: &[u8]) -> Self::Signature {
         TestSignature {
             msg: msg.to_vec(),
             index: self.index,
         }
     }
+}
+
+#[derive(Clone, Debug)]
+struct TestVerifier {
+    count: NodeCount,
+}
+
+impl Verifier for TestVerifier {
+    type Signature = TestSignature;
+
+    fn node_count(&self) -> NodeCount {
+        self.count
+    }
 
     fn verify(&self, msg: &[u8], sgn: &Self::Signature, index: NodeIndex) -> bool {
         index == sgn.index && msg == sgn.msg
@@ -70,21 +89,36 @@ impl KeyBox for TestKeyBox {
 
 pub(crate) type TestMultiKeychain = DefaultMultiKeychain<TestKeyBox>;
 
-pub(crate) type TestPartialMultisignature = SignatureSet<TestSignature>;
-
 pub(crate) fn test_multi_keychain(node_count: NodeCount, index: NodeIndex) -> TestMultiKeychain {
     let key_box = TestKeyBox::new(node_count, index);
     DefaultMultiKeychain::new(key_box)
 }
 
-#[tokio::test]
-async fn test_valid_signatures() {
-    let node_count: NodeCount = 7.into();
-    let keychains: Vec<TestMultiKeychain> = (0_usize..node_count.0)
+pub(crate) fn test_multi_keychains(node_count: NodeCount) -> Vec<TestMultiKeychain> {
+    (0..node_count.0)
         .map(|i| test_multi_keychain(node_count, i.into()))
-        .collect();
-    for i in 0..node_count.0 {
-        for j in 0..node_count.0 {
+        .collect()
+}
+
+#[cfg(feature = "bls")]
+pub(crate) fn bls_keychains(node_count: NodeCount) -> Vec<crate::bls::Keychain> {
+    use crate::bls::{generate_keys, Keychain};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let (secret_keys, public_keys) = generate_keys(&mut StdRng::seed_from_u64(0), node_count);
+    secret_keys
+        .into_iter()
+        .enumerate()
+        .map(|(i, secret_key)| {
+            Keychain::new(i.into(), secret_key, public_keys.clone())
+                .expect("the keys belong to the member")
+        })
+        .collect()
+}
+
+async fn check_valid_signatures<MK: MultiKeychain>(keychains: &[MK]) {
+    for i in 0..keychains.len() {
+        for j in 0..keychains.len() {
             let msg = test_message();
             let signed_msg = Signed::sign_with_index(msg.clone(), &keychains[i]).await;
             let unchecked_msg = signed_msg.into_unchecked();
@@ -96,6 +130,61 @@ async fn test_valid_signatures() {
     }
 }
 
+async fn check_incomplete_multisignature<MK: MultiKeychain>(keychains: &[MK]) {
+    let msg = test_message();
+    let partial = PartiallyMultisigned::sign(msg, &keychains[0]).await;
+    assert!(
+        !partial.is_complete(),
+        "One signature does not form a complete multisignature",
+    );
+}
+
+async fn check_multisignatures<MK: MultiKeychain + std::fmt::Debug>(keychains: &[MK]) {
+    let msg = test_message();
+    let mut partial = PartiallyMultisigned::sign(msg.clone(), &keychains[0]).await;
+    for keychain in keychains.iter().skip(1).take(4) {
+        assert!(!partial.is_complete());
+        let signed = Signed::sign_with_index(msg.clone(), keychain).await;
+        partial = partial.add_signature(signed, keychain);
+    }
+    assert!(
+        partial.is_complete(),
+        "5 signatures should form a complete signature {:?}",
+        partial
+    );
+}
+
+async fn check_with_verifier<MK, MV>(keychains: &[MK], verifier: &MV)
+where
+    MK: MultiKeychain,
+    MV: MultiVerifier<Signature = MK::Signature, PartialMultisignature = MK::PartialMultisignature>,
+{
+    let msg = test_message();
+    let mut partial = PartiallyMultisigned::sign(msg.clone(), &keychains[0]).await;
+    for keychain in keychains.iter().skip(1) {
+        let signed = Signed::sign_with_index(msg.clone(), keychain).await;
+        assert!(
+            signed.clone().into_unchecked().check(verifier).is_ok(),
+            "Signed message should be valid without a key"
+        );
+        partial = partial.add_signature(signed, keychain);
+    }
+    assert!(
+        partial.into_unchecked().check_multi(verifier).is_ok(),
+        "Multisigned message should be valid without a key"
+    );
+    let partial = PartiallyMultisigned::sign(msg, &keychains[0]).await;
+    assert!(
+        partial.into_unchecked().check_multi(verifier).is_err(),
+        "One signature does not form a complete multisignature",
+    );
+}
+
+#[tokio::test]
+async fn test_valid_signatures() {
+    check_valid_signatures(&test_multi_keychains(7.into())).await;
+}
+
 #[tokio::test]
 async fn test_invalid_signatures() {
     let node_count: NodeCount = 1.into();
@@ -113,36 +202,175 @@ async fn test_invalid_signatures() {
 }
 
 #[tokio::test]
-async fn test_incomplete_multisignature() {
-    let msg = test_message();
-    let index: NodeIndex = 0.into();
-    let node_count: NodeCount = 2.into();
-    let keychain = test_multi_keychain(node_count, index);
+async fn test_checking_batches() {
+    let keychains = test_multi_keychains(3.into());
+    let mut batch = Vec::new();
+    for keychain in keychains.iter() {
+        let signed_msg = Signed::sign_with_index(test_message(), keychain).await;
+        batch.push(signed_msg.into_unchecked());
+    }
+    batch[1].signature_mut().index = 2.into();
+    let results = UncheckedSigned::check_batch(batch, &keychains[0]);
+    assert_eq!(
+        results.iter().map(Result::is_ok).collect::<Vec<_>>(),
+        vec![true, false, true],
+        "only the signature with the wrong index should be rejected"
+    );
+}
 
-    let partial = PartiallyMultisigned::sign(msg, &keychain).await;
+#[tokio::test]
+async fn test_verified_data_outlives_keychain() {
+    let keychains = test_multi_keychains(7.into());
+    let mut partial = PartiallyMultisigned::sign(test_message(), &keychains[0]).await;
+    for keychain in keychains.iter().skip(1) {
+        let signed = Signed::sign_with_index(test_message(), keychain).await;
+        partial = partial.add_signature(signed, keychain);
+    }
+    let multisigned = match partial {
+        PartiallyMultisigned::Complete { multisigned } => multisigned,
+        PartiallyMultisigned::Incomplete { .. } => panic!("7 signatures should be complete"),
+    };
+    drop(keychains);
+    let signable = tokio::spawn(async move { multisigned.as_signable().clone() })
+        .await
+        .expect("the task should not panic");
+    assert_eq!(signable, test_message());
+}
+
+#[test]
+fn test_signature_set_codec() {
+    let mut signature_set = SignatureSet::new(10.into());
+    for i in [0, 3, 4, 9] {
+        signature_set = signature_set.add_signature(&(i as u64), i.into());
+    }
+    let encoded = signature_set.encode();
+    let decoded = SignatureSet::<u64>::decode(&mut encoded.as_slice());
+    assert_eq!(decoded, Ok(signature_set));
     assert!(
-        !partial.is_complete(),
-        "One signature does not form a complete multisignature",
+        SignatureSet::<u64>::decode(&mut &encoded[..encoded.len() - 1]).is_err(),
+        "a missing signature should not decode"
     );
 }
 
+#[test]
+fn test_signature_set_rejects_non_canonical_bitmap() {
+    let mut signature_set = SignatureSet::new(6.into());
+    signature_set = signature_set.add_signature(&7u64, 5.into());
+    let mut encoded = signature_set.encode();
+    // The capacity, the length of the bitmap and the single byte of it, in which only the six
+    // most significant bits are used.
+    assert_eq!(encoded[4..6], [4, 0b0000_0100]);
+    encoded[5] |= 1;
+    assert!(SignatureSet::<u64>::decode(&mut encoded.as_slice()).is_err());
+}
+
+#[test]
+fn test_signature_set_decoding_is_bounded_by_committee() {
+    use crate::DecodingLimits;
+
+    let mut signature_set = SignatureSet::new(10.into());
+    signature_set = signature_set.add_signature(&7u64, 2.into());
+    let encoded = signature_set.encode();
+    let limits = |n_members: usize| DecodingLimits {
+        n_members: n_members.into(),
+        max_round: 0,
+        max_data_size: 0,
+    };
+    let decoded = <SignatureSet<u64> as PartialMultisignature>::decode_bounded(
+        &mut encoded.as_slice(),
+        &limits(10),
+    );
+    assert_eq!(decoded, Ok(signature_set));
+    assert!(
+        <SignatureSet<u64> as PartialMultisignature>::decode_bounded(
+            &mut encoded.as_slice(),
+            &limits(9)
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_signature_set_has_efficient_encoding() {
+    let mut signature_set = SignatureSet::new(100.into());
+    assert!(signature_set.encode().len() < 20);
+    for i in 0..67 {
+        signature_set = signature_set.add_signature(&(i as u8), i.into());
+    }
+    // An option tag for every member would take 100 bytes.
+    assert!(signature_set.encode().len() < 100);
+}
+
+#[tokio::test]
+async fn test_incomplete_multisignature() {
+    check_incomplete_multisignature(&test_multi_keychains(2.into())).await;
+}
+
 #[tokio::test]
 async fn test_multisignatures() {
+    check_multisignatures(&test_multi_keychains(7.into())).await;
+}
+
+#[tokio::test]
+async fn test_checking_with_verifier() {
+    let node_count = 7.into();
+    let verifier = DefaultMultiKeychain::new(TestVerifier { count: node_count });
+    check_with_verifier(&test_multi_keychains(node_count), &verifier).await;
+}
+
+#[cfg(feature = "bls")]
+#[tokio::test]
+async fn test_bls_valid_signatures() {
+    check_valid_signatures(&bls_keychains(7.into())).await;
+}
+
+#[cfg(feature = "bls")]
+#[tokio::test]
+async fn test_bls_invalid_signatures() {
+    let keychains = bls_keychains(2.into());
     let msg = test_message();
-    let node_count: NodeCount = 7.into();
-    let keychains: Vec<TestMultiKeychain> = (0..node_count.0)
-        .map(|i| test_multi_keychain(node_count, i.into()))
-        .collect();
+    let signed_msg = Signed::sign_with_index(msg, &keychains[1]).await;
+    let signature = signed_msg.into_unchecked().signature();
+    let unchecked_msg = UncheckedSigned::new_with_index(test_message(), 0.into(), signature);
 
-    let mut partial = PartiallyMultisigned::sign(msg.clone(), &keychains[0]).await;
-    for keychain in keychains.iter().skip(1).take(4) {
-        assert!(!partial.is_complete());
-        let signed = Signed::sign_with_index(msg.clone(), keychain).await;
-        partial = partial.add_signature(signed, keychain);
-    }
     assert!(
-        partial.is_complete(),
-        "5 signatures should form a complete signature {:?}",
-        partial
+        unchecked_msg.check(&keychains[0]).is_err(),
+        "wrong index makes wrong signature"
+    );
+}
+
+#[cfg(feature = "bls")]
+#[tokio::test]
+async fn test_bls_incomplete_multisignature() {
+    check_incomplete_multisignature(&bls_keychains(2.into())).await;
+}
+
+#[cfg(feature = "bls")]
+#[tokio::test]
+async fn test_bls_multisignatures() {
+    check_multisignatures(&bls_keychains(7.into())).await;
+}
+
+#[cfg(feature = "bls")]
+#[tokio::test]
+async fn test_bls_checking_with_verifier() {
+    let keychains = bls_keychains(7.into());
+    let public_keys = keychains[0].public_keys().clone();
+    check_with_verifier(&keychains, &public_keys).await;
+}
+
+#[test]
+fn test_unit_signing_round_recognizes_only_unit_messages() {
+    let msg = unit_signing_message(7, 3, &[0; 32]);
+    assert_eq!(unit_signing_round(&msg), Some((7, 3)));
+
+    let mut extended = msg.clone();
+    extended.push(0);
+    assert_eq!(unit_signing_round(&extended), None);
+    assert_eq!(unit_signing_round(&msg[..msg.len() - 1]), None);
+    // A hash which merely starts like a tagged message is not a unit.
+    assert_eq!(
+        unit_signing_round(&(0u8, 7u64, 3u16, [0u8; 32]).encode()),
+        None
     );
 }
@@ -1,9 +1,10 @@
 use crate::{
+    network::{decode_bounded_data, DecodeBounded, DecodingLimits},
     nodes::BoolNodeMap,
-    signed::{Signable, Signed, UncheckedSigned},
+    signed::{unit_signing_message, Signable, Signed, UncheckedSigned},
     Data, Hasher, Index, KeyBox, NodeCount, NodeIndex, NodeMap, Round, SessionId,
 };
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, Error, Input};
 use derivative::Derivative;
 use std::collections::HashMap;
 
@@ -66,6 +67,17 @@ impl<H: Hasher> ControlHash<H> {
     }
 }
 
+impl<H: Hasher> DecodeBounded for ControlHash<H> {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let parents_mask = BoolNodeMap::decode_bounded(input, limits)?;
+        let combined_hash = H::Hash::decode(input)?;
+        Ok(ControlHash {
+            parents_mask,
+            combined_hash,
+        })
+    }
+}
+
 /// The simplest type representing a unit, consisting of coordinates and a control hash
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
 pub(crate) struct PreUnit<H: Hasher> {
@@ -102,6 +114,17 @@ impl<H: Hasher> PreUnit<H> {
     }
 }
 
+impl<H: Hasher> DecodeBounded for PreUnit<H> {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let coord = UnitCoord::decode(input)?;
+        let control_hash = ControlHash::decode_bounded(input, limits)?;
+        Ok(PreUnit {
+            coord,
+            control_hash,
+        })
+    }
+}
+
 ///
 #[derive(Debug, Encode, Decode, Derivative)]
 #[derivative(PartialEq, Eq, Hash)]
@@ -177,10 +200,19 @@ impl<H: Hasher, D: Data> FullUnit<H, D> {
     }
 }
 
+impl<H: Hasher, D: Data> DecodeBounded for FullUnit<H, D> {
+    fn decode_bounded<I: Input>(input: &mut I, limits: &DecodingLimits) -> Result<Self, Error> {
+        let pre_unit = PreUnit::decode_bounded(input, limits)?;
+        let data = decode_bounded_data(input, limits)?;
+        let session_id = SessionId::decode(input)?;
+        Ok(FullUnit::new(pre_unit, data, session_id))
+    }
+}
+
 impl<H: Hasher, D: Data> Signable for FullUnit<H, D> {
-    type Hash = H::Hash;
-    fn hash(&self) -> H::Hash {
-        self.hash()
+    type Hash = Vec<u8>;
+    fn hash(&self) -> Self::Hash {
+        unit_signing_message(self.session_id, self.round(), self.hash().as_ref())
     }
 }
 
@@ -192,7 +224,7 @@ impl<H: Hasher, D: Data> Index for FullUnit<H, D> {
 
 pub(crate) type UncheckedSignedUnit<H, D, S> = UncheckedSigned<FullUnit<H, D>, S>;
 
-pub(crate) type SignedUnit<'a, H, D, KB> = Signed<'a, FullUnit<H, D>, KB>;
+pub(crate) type SignedUnit<H, D, KB> = Signed<FullUnit<H, D>, KB>;
 
 #[derive(Clone, Debug, PartialEq, Encode, Decode)]
 pub(crate) struct Unit<H: Hasher> {
@@ -5,17 +5,17 @@ use log::{trace, warn};
 /// to the Terminal. We refer to the documentation https://cardinal-cryptography.github.io/AlephBFT/internals.html
 /// Section 5.4 for a discussion of this component and the notion of "legit" units.
 
-pub(crate) struct UnitStore<'a, H: Hasher, D: Data, KB: KeyBox> {
-    by_coord: HashMap<UnitCoord, SignedUnit<'a, H, D, KB>>,
-    by_hash: HashMap<H::Hash, SignedUnit<'a, H, D, KB>>,
+pub(crate) struct UnitStore<H: Hasher, D: Data, KB: KeyBox> {
+    by_coord: HashMap<UnitCoord, SignedUnit<H, D, KB>>,
+    by_hash: HashMap<H::Hash, SignedUnit<H, D, KB>>,
     parents: HashMap<H::Hash, Vec<H::Hash>>,
     //the number of unique nodes that we hold units for a given round
     is_forker: NodeMap<bool>,
-    legit_buffer: Vec<SignedUnit<'a, H, D, KB>>,
+    legit_buffer: Vec<SignedUnit<H, D, KB>>,
     max_round: Round,
 }
 
-impl<'a, H: Hasher, D: Data, KB: KeyBox> UnitStore<'a, H, D, KB> {
+impl<H: Hasher, D: Data, KB: KeyBox> UnitStore<H, D, KB> {
     pub(crate) fn new(n_nodes: NodeCount, max_round: Round) -> Self {
         UnitStore {
             by_coord: HashMap::new(),
@@ -28,11 +28,11 @@ impl<'a, H: Hasher, D: Data, KB: KeyBox> UnitStore<'a, H, D, KB> {
         }
     }
 
-    pub(crate) fn unit_by_coord(&self, coord: UnitCoord) -> Option<&SignedUnit<'a, H, D, KB>> {
+    pub(crate) fn unit_by_coord(&self, coord: UnitCoord) -> Option<&SignedUnit<H, D, KB>> {
         self.by_coord.get(&coord)
     }
 
-    pub(crate) fn unit_by_hash(&self, hash: &H::Hash) -> Option<&SignedUnit<'a, H, D, KB>> {
+    pub(crate) fn unit_by_hash(&self, hash: &H::Hash) -> Option<&SignedUnit<H, D, KB>> {
         self.by_hash.get(hash)
     }
 
@@ -59,12 +59,12 @@ impl<'a, H: Hasher, D: Data, KB: KeyBox> UnitStore<'a, H, D, KB> {
     }
 
     // Outputs new legit units that are supposed to be sent to Consensus and empties the buffer.
-    pub(crate) fn yield_buffer_units(&mut self) -> Vec<SignedUnit<'a, H, D, KB>> {
+    pub(crate) fn yield_buffer_units(&mut self) -> Vec<SignedUnit<H, D, KB>> {
         std::mem::take(&mut self.legit_buffer)
     }
 
     // Outputs None if this is not a newly-discovered fork or Some(sv) where (su, sv) form a fork
-    pub(crate) fn is_new_fork(&self, fu: &FullUnit<H, D>) -> Option<SignedUnit<'a, H, D, KB>> {
+    pub(crate) fn is_new_fork(&self, fu: &FullUnit<H, D>) -> Option<SignedUnit<H, D, KB>> {
         if self.contains_hash(&fu.hash()) {
             return None;
         }
@@ -77,7 +77,7 @@ impl<'a, H: Hasher, D: Data, KB: KeyBox> UnitStore<'a, H, D, KB> {
 
     // Marks a node as a forker and outputs all units in store created by this node.
     // The returned vector is sorted w.r.t. increasing rounds.
-    pub(crate) fn mark_forker(&mut self, forker: NodeIndex) -> Vec<SignedUnit<'a, H, D, KB>> {
+    pub(crate) fn mark_forker(&mut self, forker: NodeIndex) -> Vec<SignedUnit<H, D, KB>> {
         if self.is_forker[forker] {
             warn!(target: "AlephBFT-unit-store", "Trying to mark the node {:?} as forker for the second time.", forker);
         }
@@ -87,7 +87,7 @@ impl<'a, H: Hasher, D: Data, KB: KeyBox> UnitStore<'a, H, D, KB> {
             .collect()
     }
 
-    pub(crate) fn add_unit(&mut self, su: SignedUnit<'a, H, D, KB>, alert: bool) {
+    pub(crate) fn add_unit(&mut self, su: SignedUnit<H, D, KB>, alert: bool) {
         let hash = su.as_signable().hash();
         let creator = su.as_signable().creator();
 
@@ -133,13 +133,13 @@ mod tests {
         NodeCount, NodeIndex, Round, Signed,
     };
 
-    async fn create_unit<'a>(
+    async fn create_unit(
         round: Round,
         node_idx: NodeIndex,
         count: NodeCount,
         session_id: u64,
-        keybox: &'_ KeyBox,
-    ) -> SignedUnit<'_, Hasher64, Data, KeyBox> {
+        keybox: &KeyBox,
+    ) -> SignedUnit<Hasher64, Data, KeyBox> {
         let preunit = PreUnit::<Hasher64>::new(
             node_idx,
             round,
@@ -0,0 +1,77 @@
+use std::{convert::TryFrom, io};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The number of bytes used to encode the length of a frame.
+const LENGTH_PREFIX_SIZE: usize = 4;
+
+/// Writes `payload` as a single frame, prefixed with its length as a big endian `u32`.
+pub(crate) async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    payload: &[u8],
+) -> io::Result<()> {
+    let length = u32::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Frame is too large."))?;
+    let mut buffer = Vec::with_capacity(LENGTH_PREFIX_SIZE + payload.len());
+    buffer.extend_from_slice(&length.to_be_bytes());
+    buffer.extend_from_slice(payload);
+    writer.write_all(&buffer).await?;
+    writer.flush().await
+}
+
+/// Reads a single frame written by [`write_frame`]. Frames longer than `max_frame_size` are
+/// rejected before any space is allocated for them.
+pub(crate) async fn read_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    max_frame_size: usize,
+) -> io::Result<Vec<u8>> {
+    let mut length = [0u8; LENGTH_PREFIX_SIZE];
+    reader.read_exact(&mut length).await?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > max_frame_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Frame of {} bytes exceeds the limit of {} bytes.",
+                length, max_frame_size
+            ),
+        ));
+    }
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload).await?;
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_frame, write_frame};
+    use std::io;
+
+    #[tokio::test]
+    async fn frames_roundtrip() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"first").await.unwrap();
+        write_frame(&mut buffer, b"").await.unwrap();
+        write_frame(&mut buffer, b"third").await.unwrap();
+
+        let mut reader = &buffer[..];
+        assert_eq!(read_frame(&mut reader, 5).await.unwrap(), b"first");
+        assert_eq!(read_frame(&mut reader, 5).await.unwrap(), b"");
+        assert_eq!(read_frame(&mut reader, 5).await.unwrap(), b"third");
+        assert_eq!(
+            read_frame(&mut reader, 5).await.unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_frames() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, &[0; 100]).await.unwrap();
+
+        let mut reader = &buffer[..];
+        assert_eq!(
+            read_frame(&mut reader, 99).await.unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
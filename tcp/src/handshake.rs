@@ -0,0 +1,170 @@
+use crate::frame::{read_frame, write_frame};
+use aleph_bft::{KeyBox, NodeIndex};
+use codec::{Decode, Encode};
+use std::{fmt, io};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Domain separator for the signatures made during the handshake, so that they cannot be
+/// confused with signatures made by the consensus.
+const HANDSHAKE_CONTEXT: &[u8] = b"AlephBFT-tcp-handshake";
+
+/// The limit on the size of the handshake frames, big enough for any reasonable signature.
+const MAX_HANDSHAKE_FRAME_SIZE: usize = 64 * 1024;
+
+/// The first message sent by both sides of a connection.
+#[derive(Encode, Decode)]
+struct Hello {
+    index: NodeIndex,
+    challenge: [u8; 32],
+}
+
+#[derive(Debug)]
+pub(crate) enum HandshakeError {
+    Io(io::Error),
+    Malformed,
+    UnknownPeer(NodeIndex),
+    UnexpectedPeer {
+        expected: NodeIndex,
+        claimed: NodeIndex,
+    },
+    InvalidSignature(NodeIndex),
+    TimedOut,
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandshakeError::Io(e) => write!(f, "io error: {}", e),
+            HandshakeError::Malformed => write!(f, "malformed handshake message"),
+            HandshakeError::UnknownPeer(index) => write!(f, "unknown peer {:?}", index),
+            HandshakeError::UnexpectedPeer { expected, claimed } => {
+                write!(f, "expected peer {:?}, got {:?}", expected, claimed)
+            }
+            HandshakeError::InvalidSignature(index) => {
+                write!(f, "invalid signature from {:?}", index)
+            }
+            HandshakeError::TimedOut => write!(f, "handshake timed out"),
+        }
+    }
+}
+
+impl From<io::Error> for HandshakeError {
+    fn from(e: io::Error) -> Self {
+        HandshakeError::Io(e)
+    }
+}
+
+fn handshake_message(challenge: &[u8; 32], signer: NodeIndex, verifier: NodeIndex) -> Vec<u8> {
+    (HANDSHAKE_CONTEXT, challenge, signer, verifier).encode()
+}
+
+/// Mutually authenticates the two ends of a fresh connection and returns the index of the peer.
+///
+/// Both sides send their index together with a random challenge, and then prove they own the
+/// key of the index they claim by signing the challenge of the other side. If `expected` is
+/// given, the peer must claim exactly this index.
+pub(crate) async fn handshake<KB: KeyBox, S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    keybox: &KB,
+    expected: Option<NodeIndex>,
+) -> Result<NodeIndex, HandshakeError> {
+    let own_index = keybox.index();
+    let challenge: [u8; 32] = rand::random();
+    let hello = Hello {
+        index: own_index,
+        challenge,
+    };
+    write_frame(stream, &hello.encode()).await?;
+
+    let frame = read_frame(stream, MAX_HANDSHAKE_FRAME_SIZE).await?;
+    let peer_hello = Hello::decode(&mut &frame[..]).map_err(|_| HandshakeError::Malformed)?;
+    let peer = peer_hello.index;
+    if peer == own_index || peer.0 >= keybox.node_count().0 {
+        return Err(HandshakeError::UnknownPeer(peer));
+    }
+    if let Some(expected) = expected {
+        if expected != peer {
+            return Err(HandshakeError::UnexpectedPeer {
+                expected,
+                claimed: peer,
+            });
+        }
+    }
+
+    let signature = keybox
+        .sign(&handshake_message(&peer_hello.challenge, own_index, peer))
+        .await;
+    write_frame(stream, &signature.encode()).await?;
+
+    let frame = read_frame(stream, MAX_HANDSHAKE_FRAME_SIZE).await?;
+    let signature =
+        KB::Signature::decode(&mut &frame[..]).map_err(|_| HandshakeError::Malformed)?;
+    if !keybox.verify(
+        &handshake_message(&challenge, peer, own_index),
+        &signature,
+        peer,
+    ) {
+        return Err(HandshakeError::InvalidSignature(peer));
+    }
+    Ok(peer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{handshake, HandshakeError};
+    use crate::testing::{Impostor, KeyBox};
+    use aleph_bft::{NodeCount, NodeIndex};
+
+    #[tokio::test]
+    async fn authenticates_both_sides() {
+        let (mut left, mut right) = tokio::io::duplex(1024);
+        let n_members = NodeCount(4);
+        let left_keybox = KeyBox::new(n_members, NodeIndex(0));
+        let right_keybox = KeyBox::new(n_members, NodeIndex(3));
+
+        let (left_result, right_result) = tokio::join!(
+            handshake(&mut left, &left_keybox, Some(NodeIndex(3))),
+            handshake(&mut right, &right_keybox, None),
+        );
+        assert_eq!(left_result.unwrap(), NodeIndex(3));
+        assert_eq!(right_result.unwrap(), NodeIndex(0));
+    }
+
+    #[tokio::test]
+    async fn rejects_impostor() {
+        let (mut left, mut right) = tokio::io::duplex(1024);
+        let n_members = NodeCount(4);
+        let keybox = KeyBox::new(n_members, NodeIndex(0));
+        let impostor = Impostor::new(KeyBox::new(n_members, NodeIndex(1)), NodeIndex(2));
+
+        let (result, _) = tokio::join!(
+            handshake(&mut left, &keybox, None),
+            handshake(&mut right, &impostor, None),
+        );
+        assert!(matches!(
+            result,
+            Err(HandshakeError::InvalidSignature(NodeIndex(2)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_unexpected_peer() {
+        let (mut left, mut right) = tokio::io::duplex(1024);
+        let n_members = NodeCount(4);
+        let keybox = KeyBox::new(n_members, NodeIndex(0));
+        let other = KeyBox::new(n_members, NodeIndex(1));
+
+        // The connection is dropped as soon as the handshake fails, so that the other side ends.
+        let (result, _) = tokio::join!(
+            async move { handshake(&mut left, &keybox, Some(NodeIndex(2))).await },
+            handshake(&mut right, &other, None),
+        );
+        assert!(matches!(
+            result,
+            Err(HandshakeError::UnexpectedPeer {
+                expected: NodeIndex(2),
+                claimed: NodeIndex(1),
+            })
+        ));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{
+    frame::read_frame,
+    handshake::{handshake, HandshakeError},
+    Config,
+};
+use aleph_bft::{Data, Hasher, KeyBox, NetworkData, PartialMultisignature, Signature};
+use futures::{stream::FuturesUnordered, StreamExt};
+use log::{debug, warn};
+use std::{io, net::SocketAddr};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc::Sender,
+    time::timeout,
+};
+
+/// Accepts connections from peers and passes the messages received through them to the network.
+pub(crate) async fn run<KB: KeyBox, H: Hasher, D: Data, S: Signature, MS: PartialMultisignature>(
+    listener: TcpListener,
+    keybox: KB,
+    config: Config,
+    messages_for_network: Sender<NetworkData<H, D, S, MS>>,
+) {
+    let mut connections = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, address)) => connections.push(receive(
+                    stream,
+                    address,
+                    keybox.clone(),
+                    config.clone(),
+                    messages_for_network.clone(),
+                )),
+                Err(e) => {
+                    warn!(target: "AlephBFT-tcp", "{:?} Failed to accept a connection: {}.", keybox.index(), e)
+                }
+            },
+            Some(()) = connections.next(), if !connections.is_empty() => {},
+        }
+    }
+}
+
+async fn receive<KB: KeyBox, H: Hasher, D: Data, S: Signature, MS: PartialMultisignature>(
+    mut stream: TcpStream,
+    address: SocketAddr,
+    keybox: KB,
+    config: Config,
+    messages_for_network: Sender<NetworkData<H, D, S, MS>>,
+) {
+    let index = keybox.index();
+    let peer = match timeout(
+        config.handshake_timeout,
+        handshake(&mut stream, &keybox, None),
+    )
+    .await
+    .unwrap_or(Err(HandshakeError::TimedOut))
+    {
+        Ok(peer) => peer,
+        Err(e @ HandshakeError::Io(_)) | Err(e @ HandshakeError::TimedOut) => {
+            debug!(target: "AlephBFT-tcp", "{:?} Handshake with {} failed: {}.", index, address, e);
+            return;
+        }
+        Err(e) => {
+            warn!(target: "AlephBFT-tcp", "{:?} Rejected connection from {}: {}.", index, address, e);
+            return;
+        }
+    };
+    debug!(target: "AlephBFT-tcp", "{:?} Accepted connection from {:?}.", index, peer);
+    loop {
+        let frame = match read_frame(&mut stream, config.max_frame_size).await {
+            Ok(frame) => frame,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                warn!(target: "AlephBFT-tcp", "{:?} Received an oversized message from {:?}, disconnecting: {}.", index, peer, e);
+                return;
+            }
+            Err(e) => {
+                debug!(target: "AlephBFT-tcp", "{:?} Connection from {:?} closed: {}.", index, peer, e);
+                return;
+            }
+        };
+        match NetworkData::decode_bounded(&mut &frame[..], &config.decoding_limits) {
+            Ok(data) => {
+                // Waits while the network is behind, the socket is not read in the meantime.
+                if messages_for_network.send(data).await.is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                warn!(target: "AlephBFT-tcp", "{:?} Received a malformed message from {:?}, disconnecting: {}.", index, peer, e);
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,412 @@
+//! A reference TCP implementation of the AlephBFT [`Network`] trait.
+//!
+//! Every node listens on the address assigned to it in the [`AddressBook`] and keeps a single
+//! outgoing connection to every other node, which is reestablished with an exponential backoff
+//! whenever it fails. Messages are sent as length-prefixed frames of encoded [`NetworkData`].
+//! When a connection is established both sides prove, using their [`KeyBox`], that they own the
+//! keys of the [`NodeIndex`] they claim, so messages are accepted only from committee members.
+
+use aleph_bft::{
+    Data, DecodingLimits, Hasher, KeyBox, Network, NetworkData, NodeIndex, PartialMultisignature,
+    PeerEvent, Recipient, SendError, Signature,
+};
+use codec::Encode;
+use futures::channel::mpsc;
+use log::warn;
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    net::TcpListener,
+    sync::mpsc::{error::TrySendError, Receiver, Sender},
+    task::JoinHandle,
+};
+
+mod frame;
+mod handshake;
+mod incoming;
+mod outgoing;
+#[cfg(test)]
+mod testing;
+
+use outgoing::Outgoing;
+
+/// The addresses on which the committee members listen for connections.
+pub type AddressBook = HashMap<NodeIndex, SocketAddr>;
+
+/// Configuration of a [`TcpNetwork`].
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Limits used for decoding the messages received from peers.
+    pub decoding_limits: DecodingLimits,
+    /// The maximal size in bytes of a single encoded message. Peers sending larger messages are
+    /// disconnected.
+    pub max_frame_size: usize,
+    /// The number of messages which may wait to be sent to a single peer. Sending fails with
+    /// [`SendError::QueueFull`] when there are more.
+    pub queue_size: usize,
+    /// The number of received messages which may wait to be processed. Reading from the peers
+    /// stops while there are more, so that they are slowed down by TCP flow control.
+    pub receive_queue_size: usize,
+    /// The time after which a connection is dropped if the handshake has not been completed.
+    pub handshake_timeout: Duration,
+    /// The delay before reconnecting to a peer, doubled after every failed attempt.
+    pub reconnect_delay: Duration,
+    /// The maximal delay before reconnecting to a peer.
+    pub max_reconnect_delay: Duration,
+}
+
+/// Default configuration of a [`TcpNetwork`] decoding the messages with the given limits.
+pub fn default_config(decoding_limits: DecodingLimits) -> Config {
+    Config {
+        decoding_limits,
+        max_frame_size: 16 * 1024 * 1024,
+        queue_size: 1000,
+        receive_queue_size: 1000,
+        handshake_timeout: Duration::from_secs(5),
+        reconnect_delay: Duration::from_millis(100),
+        max_reconnect_delay: Duration::from_secs(10),
+    }
+}
+
+struct Peer {
+    queue: Sender<Arc<Vec<u8>>>,
+    reachable: Arc<AtomicBool>,
+}
+
+/// A [`Network`] connecting the committee members directly over TCP.
+///
+/// The tasks maintaining the connections are spawned on the tokio runtime and stopped when the
+/// network is dropped.
+pub struct TcpNetwork<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> {
+    index: NodeIndex,
+    max_frame_size: usize,
+    peers: HashMap<NodeIndex, Peer>,
+    messages_from_peers: Receiver<NetworkData<H, D, S, MS>>,
+    peer_events: Option<mpsc::UnboundedReceiver<PeerEvent>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> TcpNetwork<H, D, S, MS> {
+    /// Starts listening on the address of the node in the address book and connecting to all the
+    /// other nodes in it. Has to be called from within a tokio runtime.
+    pub async fn new<KB: KeyBox>(
+        keybox: KB,
+        address_book: AddressBook,
+        config: Config,
+    ) -> io::Result<Self> {
+        let address = address_book.get(&keybox.index()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No address for {:?} in the address book.", keybox.index()),
+            )
+        })?;
+        let listener = TcpListener::bind(address).await?;
+        Ok(Self::with_listener(keybox, listener, address_book, config))
+    }
+
+    /// Like [`TcpNetwork::new`], but accepts connections on an already bound listener. The
+    /// address of the node in the address book is ignored. Has to be called from within a
+    /// tokio runtime.
+    pub fn with_listener<KB: KeyBox>(
+        keybox: KB,
+        listener: TcpListener,
+        address_book: AddressBook,
+        config: Config,
+    ) -> Self {
+        let index = keybox.index();
+        let (messages_for_network, messages_from_peers) =
+            tokio::sync::mpsc::channel(config.receive_queue_size);
+        let (peer_events_for_network, peer_events) = mpsc::unbounded();
+        let mut tasks = vec![tokio::spawn(incoming::run(
+            listener,
+            keybox.clone(),
+            config.clone(),
+            messages_for_network,
+        ))];
+        let mut peers = HashMap::new();
+        for (peer, address) in address_book {
+            if peer == index {
+                continue;
+            }
+            let (queue, queue_rx) = tokio::sync::mpsc::channel(config.queue_size);
+            // All peers are assumed to be reachable until a connection attempt fails.
+            let reachable = Arc::new(AtomicBool::new(true));
+            let outgoing = Outgoing::new(
+                keybox.clone(),
+                peer,
+                address,
+                config.clone(),
+                queue_rx,
+                reachable.clone(),
+                peer_events_for_network.clone(),
+            );
+            tasks.push(tokio::spawn(outgoing.run()));
+            peers.insert(peer, Peer { queue, reachable });
+        }
+        TcpNetwork {
+            index,
+            max_frame_size: config.max_frame_size,
+            peers,
+            messages_from_peers,
+            peer_events: Some(peer_events),
+            tasks,
+        }
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> Drop for TcpNetwork<H, D, S, MS> {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<H: Hasher, D: Data, S: Signature, MS: PartialMultisignature> Network<H, D, S, MS>
+    for TcpNetwork<H, D, S, MS>
+{
+    fn send(&self, data: NetworkData<H, D, S, MS>, recipient: Recipient) -> Result<(), SendError> {
+        let recipients = match recipient {
+            Recipient::Everyone => self.peers.keys().copied().collect(),
+            Recipient::Node(node) => vec![node],
+            Recipient::Nodes(nodes) => nodes,
+        };
+        let frame = Arc::new(data.encode());
+        if frame.len() > self.max_frame_size {
+            warn!(target: "AlephBFT-tcp", "{:?} Dropping a message of {} bytes, larger than the frame size limit.", self.index, frame.len());
+            return Err(SendError::MessageTooLarge);
+        }
+        let mut unreachable = Vec::new();
+        let mut queue_full = false;
+        for node in recipients {
+            if node == self.index {
+                continue;
+            }
+            let peer = match self.peers.get(&node) {
+                Some(peer) if peer.reachable.load(Ordering::SeqCst) => peer,
+                _ => {
+                    unreachable.push(node);
+                    continue;
+                }
+            };
+            match peer.queue.try_send(frame.clone()) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => queue_full = true,
+                Err(TrySendError::Closed(_)) => return Err(SendError::Closed),
+            }
+        }
+        if !unreachable.is_empty() {
+            Err(SendError::PeerUnreachable(unreachable))
+        } else if queue_full {
+            Err(SendError::QueueFull)
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn next_event(&mut self) -> Option<NetworkData<H, D, S, MS>> {
+        self.messages_from_peers.recv().await
+    }
+
+    fn peer_events(&mut self) -> Option<mpsc::UnboundedReceiver<PeerEvent>> {
+        self.peer_events.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        default_config,
+        testing::{KeyBox, PartialMultisignature, Signature},
+        AddressBook, Config, TcpNetwork,
+    };
+    use aleph_bft::{
+        testing::{gen_config, spawn_honest_member_generic, Data, DataIO, Hasher64, Spawner},
+        ApplicationChannels, DecodingLimits, Network, NetworkData, NodeCount, NodeIndex, PeerEvent,
+        Recipient, SendError,
+    };
+    use codec::{Decode, Encode};
+    use futures::StreamExt;
+    use std::time::Duration;
+    use tokio::{net::TcpListener, time::timeout};
+
+    type TestNetwork = TcpNetwork<Hasher64, Data, Signature, PartialMultisignature>;
+
+    async fn bind_all(n_members: NodeCount) -> (Vec<TcpListener>, AddressBook) {
+        let mut listeners = Vec::new();
+        let mut address_book = AddressBook::new();
+        for ix in 0..n_members.0 {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            address_book.insert(NodeIndex(ix), listener.local_addr().unwrap());
+            listeners.push(listener);
+        }
+        (listeners, address_book)
+    }
+
+    fn test_config(n_members: NodeCount) -> Config {
+        let mut config = default_config(DecodingLimits::from_config(
+            &gen_config(NodeIndex(0), n_members),
+            1024,
+        ));
+        config.handshake_timeout = Duration::from_millis(200);
+        config.reconnect_delay = Duration::from_millis(20);
+        config.max_reconnect_delay = Duration::from_millis(100);
+        config
+    }
+
+    // The variants of NetworkData are private, so an application message is decoded from its
+    // encoding instead.
+    fn application_message(
+        payload: Vec<u8>,
+    ) -> NetworkData<Hasher64, Data, Signature, PartialMultisignature> {
+        let encoded = (2u8, 7u16, payload).encode();
+        NetworkData::decode(&mut &encoded[..]).expect("the encoding is valid")
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn nodes_on_localhost_agree_on_batches() {
+        let n_members = NodeCount(4);
+        let n_batches = 10;
+        let (listeners, address_book) = bind_all(n_members).await;
+        let mut batch_receivers = Vec::new();
+        let mut exits = Vec::new();
+        let mut handles = Vec::new();
+        for (ix, listener) in listeners.into_iter().enumerate() {
+            let keybox = KeyBox::new(n_members, NodeIndex(ix));
+            let network: TestNetwork = TcpNetwork::with_listener(
+                keybox.clone(),
+                listener,
+                address_book.clone(),
+                test_config(n_members),
+            );
+            let (batches, exit, handle) = spawn_honest_member_generic(
+                Spawner::new(),
+                gen_config(NodeIndex(ix), n_members),
+                network,
+                ApplicationChannels::new(),
+                DataIO::new(NodeIndex(ix)),
+                keybox,
+            );
+            batch_receivers.push(batches);
+            exits.push(exit);
+            handles.push(handle);
+        }
+
+        let mut batches = Vec::new();
+        for receiver in batch_receivers.iter_mut() {
+            let received: Vec<_> = timeout(
+                Duration::from_secs(60),
+                receiver.by_ref().take(n_batches).collect(),
+            )
+            .await
+            .expect("the nodes should produce batches");
+            batches.push(received);
+        }
+        for received in &batches[1..] {
+            assert_eq!(received, &batches[0]);
+        }
+
+        for exit in exits {
+            let _ = exit.send(());
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_peers_going_up() {
+        let n_members = NodeCount(2);
+        let (mut listeners, address_book) = bind_all(n_members).await;
+        let second_listener = listeners.pop().unwrap();
+        let first_listener = listeners.pop().unwrap();
+        let mut first: TestNetwork = TcpNetwork::with_listener(
+            KeyBox::new(n_members, NodeIndex(0)),
+            first_listener,
+            address_book.clone(),
+            test_config(n_members),
+        );
+        let mut peer_events = first.peer_events().unwrap();
+
+        // The second node does not accept connections yet, so the handshake times out.
+        assert_eq!(
+            peer_events.next().await,
+            Some(PeerEvent::Disconnected(NodeIndex(1)))
+        );
+
+        let _second: TestNetwork = TcpNetwork::with_listener(
+            KeyBox::new(n_members, NodeIndex(1)),
+            second_listener,
+            address_book,
+            test_config(n_members),
+        );
+        assert_eq!(
+            timeout(Duration::from_secs(5), peer_events.next())
+                .await
+                .unwrap(),
+            Some(PeerEvent::Connected(NodeIndex(1)))
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_oversized_messages() {
+        let n_members = NodeCount(2);
+        let (mut listeners, address_book) = bind_all(n_members).await;
+        let mut config = test_config(n_members);
+        config.max_frame_size = 100;
+        let network: TestNetwork = TcpNetwork::with_listener(
+            KeyBox::new(n_members, NodeIndex(0)),
+            listeners.remove(0),
+            address_book,
+            config,
+        );
+        assert_eq!(
+            network.send(application_message(vec![0; 200]), Recipient::Everyone),
+            Err(SendError::MessageTooLarge)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn delivers_all_messages_through_a_short_receive_queue() {
+        let n_members = NodeCount(2);
+        let n_messages = 50;
+        let (mut listeners, address_book) = bind_all(n_members).await;
+        let mut config = test_config(n_members);
+        config.receive_queue_size = 1;
+        let sender: TestNetwork = TcpNetwork::with_listener(
+            KeyBox::new(n_members, NodeIndex(0)),
+            listeners.remove(0),
+            address_book.clone(),
+            config.clone(),
+        );
+        let mut receiver: TestNetwork = TcpNetwork::with_listener(
+            KeyBox::new(n_members, NodeIndex(1)),
+            listeners.remove(0),
+            address_book,
+            config,
+        );
+        for i in 0..n_messages {
+            sender
+                .send(application_message(vec![i]), Recipient::Node(NodeIndex(1)))
+                .expect("the queue is long enough");
+        }
+        // The receiver is not read for a while, so the sender has to be held back.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        for i in 0..n_messages {
+            let message = timeout(Duration::from_secs(5), receiver.next_event())
+                .await
+                .expect("the message should arrive")
+                .expect("the network is running");
+            assert_eq!(message.encode(), application_message(vec![i]).encode());
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{
+    frame::write_frame,
+    handshake::{handshake, HandshakeError},
+    Config,
+};
+use aleph_bft::{KeyBox, NodeIndex, PeerEvent};
+use futures::channel::mpsc;
+use log::debug;
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    io::AsyncReadExt,
+    net::TcpStream,
+    sync::mpsc::Receiver,
+    time::{sleep, timeout},
+};
+
+/// Maintains the connection used for sending messages to a single peer, reconnecting with an
+/// exponential backoff whenever it fails.
+pub(crate) struct Outgoing<KB: KeyBox> {
+    keybox: KB,
+    peer: NodeIndex,
+    address: SocketAddr,
+    config: Config,
+    queue: Receiver<Arc<Vec<u8>>>,
+    reachable: Arc<AtomicBool>,
+    peer_events: mpsc::UnboundedSender<PeerEvent>,
+}
+
+impl<KB: KeyBox> Outgoing<KB> {
+    pub(crate) fn new(
+        keybox: KB,
+        peer: NodeIndex,
+        address: SocketAddr,
+        config: Config,
+        queue: Receiver<Arc<Vec<u8>>>,
+        reachable: Arc<AtomicBool>,
+        peer_events: mpsc::UnboundedSender<PeerEvent>,
+    ) -> Self {
+        Outgoing {
+            keybox,
+            peer,
+            address,
+            config,
+            queue,
+            reachable,
+            peer_events,
+        }
+    }
+
+    /// Runs until the queue of messages for the peer is closed.
+    pub(crate) async fn run(mut self) {
+        let mut delay = self.config.reconnect_delay;
+        loop {
+            match self.connect().await {
+                Ok(stream) => {
+                    debug!(target: "AlephBFT-tcp", "{:?} Connected to {:?}.", self.keybox.index(), self.peer);
+                    self.set_reachable(true);
+                    delay = self.config.reconnect_delay;
+                    match self.forward(stream).await {
+                        Ok(()) => return,
+                        Err(e) => {
+                            debug!(target: "AlephBFT-tcp", "{:?} Connection to {:?} lost: {}.", self.keybox.index(), self.peer, e)
+                        }
+                    }
+                }
+                Err(e) => {
+                    debug!(target: "AlephBFT-tcp", "{:?} Failed to connect to {:?} at {}: {}.", self.keybox.index(), self.peer, self.address, e)
+                }
+            }
+            self.set_reachable(false);
+            // Messages are not queued for unreachable peers, get rid of the ones left over.
+            while self.queue.try_recv().is_ok() {}
+            sleep(delay).await;
+            delay = (delay * 2).min(self.config.max_reconnect_delay);
+        }
+    }
+
+    async fn connect(&self) -> Result<TcpStream, HandshakeError> {
+        let connecting = async {
+            let mut stream = TcpStream::connect(self.address).await?;
+            stream.set_nodelay(true)?;
+            handshake(&mut stream, &self.keybox, Some(self.peer)).await?;
+            Ok(stream)
+        };
+        timeout(self.config.handshake_timeout, connecting)
+            .await
+            .unwrap_or(Err(HandshakeError::TimedOut))
+    }
+
+    /// Writes the queued messages to the stream. Returns `Ok` only if the queue was closed.
+    async fn forward(&mut self, mut stream: TcpStream) -> io::Result<()> {
+        let (mut reader, mut writer) = stream.split();
+        // Nothing is ever sent on this direction of the connection, so any read means it is over.
+        let mut buffer = [0u8; 1];
+        loop {
+            tokio::select! {
+                frame = self.queue.recv() => match frame {
+                    Some(frame) => write_frame(&mut writer, &frame).await?,
+                    None => return Ok(()),
+                },
+                _ = reader.read(&mut buffer) => {
+                    return Err(io::Error::new(io::ErrorKind::ConnectionAborted, "closed by peer"));
+                }
+            }
+        }
+    }
+
+    fn set_reachable(&self, reachable: bool) {
+        if self.reachable.swap(reachable, Ordering::SeqCst) == reachable {
+            return;
+        }
+        let event = match reachable {
+            true => PeerEvent::Connected(self.peer),
+            false => PeerEvent::Disconnected(self.peer),
+        };
+        // The network may already be gone, in which case nobody is interested.
+        let _ = self.peer_events.unbounded_send(event);
+    }
+}
@@ -0,0 +1,134 @@
+use aleph_bft::{
+    Index, KeyBox as KeyBoxT, MultiKeychain, MultiVerifier, NodeCount, NodeIndex,
+    PartialMultisignature as PartialMultisignatureT, Verifier,
+};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+// Not cryptographically secure, only good enough to tell apart the signatures of different nodes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
+pub(crate) struct Signature {
+    digest: u64,
+}
+
+impl Signature {
+    fn new(msg: &[u8], index: NodeIndex) -> Self {
+        let mut hasher = DefaultHasher::new();
+        msg.hash(&mut hasher);
+        index.0.hash(&mut hasher);
+        Signature {
+            digest: hasher.finish(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
+pub(crate) struct PartialMultisignature {
+    signed_by: Vec<NodeIndex>,
+}
+
+impl PartialMultisignatureT for PartialMultisignature {
+    type Signature = Signature;
+    fn add_signature(self, _: &Self::Signature, index: NodeIndex) -> Self {
+        let Self { mut signed_by } = self;
+        if !signed_by.contains(&index) {
+            signed_by.push(index);
+        }
+        Self { signed_by }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct KeyBox {
+    count: NodeCount,
+    index: NodeIndex,
+}
+
+impl KeyBox {
+    pub(crate) fn new(count: NodeCount, index: NodeIndex) -> Self {
+        KeyBox { count, index }
+    }
+}
+
+impl Index for KeyBox {
+    fn index(&self) -> NodeIndex {
+        self.index
+    }
+}
+
+impl Verifier for KeyBox {
+    type Signature = Signature;
+
+    fn node_count(&self) -> NodeCount {
+        self.count
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Signature, index: NodeIndex) -> bool {
+        *sgn == Signature::new(msg, index)
+    }
+}
+
+#[async_trait]
+impl KeyBoxT for KeyBox {
+    async fn sign(&self, msg: &[u8]) -> Signature {
+        Signature::new(msg, self.index)
+    }
+}
+
+impl MultiVerifier for KeyBox {
+    type PartialMultisignature = PartialMultisignature;
+
+    fn is_complete(&self, _: &[u8], partial: &PartialMultisignature) -> bool {
+        (self.count.0 * 2) / 3 < partial.signed_by.len()
+    }
+}
+
+impl MultiKeychain for KeyBox {
+    fn from_signature(&self, _: &Signature, index: NodeIndex) -> PartialMultisignature {
+        PartialMultisignature {
+            signed_by: vec![index],
+        }
+    }
+}
+
+/// Claims to be `claimed` while only having the keys of the wrapped keybox.
+#[derive(Clone)]
+pub(crate) struct Impostor {
+    keybox: KeyBox,
+    claimed: NodeIndex,
+}
+
+impl Impostor {
+    pub(crate) fn new(keybox: KeyBox, claimed: NodeIndex) -> Self {
+        Impostor { keybox, claimed }
+    }
+}
+
+impl Index for Impostor {
+    fn index(&self) -> NodeIndex {
+        self.claimed
+    }
+}
+
+impl Verifier for Impostor {
+    type Signature = Signature;
+
+    fn node_count(&self) -> NodeCount {
+        self.keybox.node_count()
+    }
+
+    fn verify(&self, msg: &[u8], sgn: &Signature, index: NodeIndex) -> bool {
+        self.keybox.verify(msg, sgn, index)
+    }
+}
+
+#[async_trait]
+impl KeyBoxT for Impostor {
+    async fn sign(&self, msg: &[u8]) -> Signature {
+        self.keybox.sign(msg).await
+    }
+}